@@ -0,0 +1,285 @@
+//! Checker for hand-written derivatives
+//!
+//! Compares a supplied gradient `df` and Jacobian-transpose product
+//! `JF1(u)^T d` (with the signatures handed to OpEn) against central finite
+//! differences at random points `u` and random directions `d`.
+
+use crate::finite_diff::{FiniteDiff, Scheme};
+use optimization_engine::SolverError;
+use rand::{Rng, SeedableRng, StdRng};
+use std::cell::Cell;
+use std::cmp::Ordering;
+use std::fmt;
+
+/// Where and how the points are sampled
+#[derive(Debug, Clone, PartialEq)]
+pub struct CheckOptions {
+    n: usize,
+    num_points: usize,
+    bounds: (f64, f64),
+    tolerance: f64,
+    seed: usize,
+}
+
+impl CheckOptions {
+    /// `n`: dimension of the decision variables
+    pub fn new(n: usize) -> Self {
+        CheckOptions {
+            n,
+            num_points: 10,
+            bounds: (-1.0, 1.0),
+            tolerance: 1e-6,
+            seed: 1,
+        }
+    }
+
+    pub fn with_num_points(mut self, num_points: usize) -> Self {
+        self.num_points = num_points;
+        self
+    }
+
+    /// Every coordinate of the points is sampled uniformly in `[lower, upper)`
+    pub fn with_bounds(mut self, lower: f64, upper: f64) -> Self {
+        assert!(lower < upper, "lower must be smaller than upper");
+        self.bounds = (lower, upper);
+        self
+    }
+
+    /// Largest relative error accepted by [`Report::passed`]
+    pub fn with_tolerance(mut self, tolerance: f64) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    pub fn with_seed(mut self, seed: usize) -> Self {
+        self.seed = seed;
+        self
+    }
+}
+
+/// Supplied and finite-difference values of one component at one point
+#[derive(Debug, Clone, PartialEq)]
+pub struct ComponentError {
+    pub point: Vec<f64>,
+    pub component: usize,
+    pub supplied: f64,
+    pub finite_diff: f64,
+    /// `|supplied - finite_diff| / max(1, |finite_diff|)`
+    pub relative_error: f64,
+}
+
+/// Outcome of a check
+#[derive(Debug, Clone, PartialEq)]
+pub struct Report {
+    name: &'static str,
+    tolerance: f64,
+    pub errors: Vec<ComponentError>,
+}
+
+impl Report {
+    /// Largest relative error of the given component over all the points
+    /// (NaN if any of them is NaN)
+    pub fn max_error(&self, component: usize) -> f64 {
+        self.errors
+            .iter()
+            .filter(|e| e.component == component)
+            .map(|e| e.relative_error)
+            .max_by(rank)
+            .unwrap_or(0.0)
+    }
+
+    /// Entry with the largest relative error, a NaN one first
+    pub fn worst(&self) -> Option<&ComponentError> {
+        self.errors
+            .iter()
+            .max_by(|p, q| rank(&p.relative_error, &q.relative_error))
+    }
+
+    /// Whether every relative error is within the tolerance (false for NaN)
+    pub fn passed(&self) -> bool {
+        self.errors
+            .iter()
+            .all(|e| e.relative_error <= self.tolerance)
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let status = if self.passed() { "OK" } else { "FAILED" };
+        writeln!(f, "{} check: {}", self.name, status)?;
+        let num_components = self.errors.iter().map(|e| e.component + 1).max();
+        for component in 0..num_components.unwrap_or(0) {
+            writeln!(
+                f,
+                "  component {}: max relative error {:.3e}",
+                component,
+                self.max_error(component)
+            )?;
+        }
+        if let Some(worst) = self.worst() {
+            write!(
+                f,
+                "  worst: u = {:.4?}, supplied {:.6e}, finite difference {:.6e}",
+                worst.point, worst.supplied, worst.finite_diff
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Checks the gradient `df` of the cost `f`
+pub fn check_gradient<F, DF>(options: &CheckOptions, f: F, df: DF) -> Result<Report, SolverError>
+where
+    F: Fn(&[f64], &mut f64) -> Result<(), SolverError>,
+    DF: Fn(&[f64], &mut [f64]) -> Result<(), SolverError>,
+{
+    let mut rng = rng(options);
+    let fd = FiniteDiff::new(Scheme::Central, options.n);
+    let mut report = Report {
+        name: "Gradient",
+        tolerance: options.tolerance,
+        errors: Vec::new(),
+    };
+    let mut supplied = vec![0.0; options.n];
+    let mut finite_diff = vec![0.0; options.n];
+    for _ in 0..options.num_points {
+        let u = sample(&mut rng, options.n, options.bounds);
+        df(&u, &mut supplied)?;
+        let status = Cell::new(Ok(()));
+        fd.gradient(
+            |u| {
+                let mut cost = 0.0;
+                status.set(status.get().and(f(u, &mut cost)));
+                cost
+            },
+            &u,
+            &mut finite_diff,
+        );
+        status.get()?;
+        push_errors(&mut report, &u, &supplied, &finite_diff);
+    }
+    Ok(report)
+}
+
+/// Checks the Jacobian-transpose product `f1_jacobian_product` of the mapping
+/// `f1` (of range dimension `n1`)
+pub fn check_jacobian_product<F1, JF1>(
+    options: &CheckOptions,
+    f1: F1,
+    f1_jacobian_product: JF1,
+    n1: usize,
+) -> Result<Report, SolverError>
+where
+    F1: Fn(&[f64], &mut [f64]) -> Result<(), SolverError>,
+    JF1: Fn(&[f64], &[f64], &mut [f64]) -> Result<(), SolverError>,
+{
+    let mut rng = rng(options);
+    let fd = FiniteDiff::new(Scheme::Central, options.n);
+    let mut report = Report {
+        name: "Jacobian product",
+        tolerance: options.tolerance,
+        errors: Vec::new(),
+    };
+    let mut supplied = vec![0.0; options.n];
+    let mut finite_diff = vec![0.0; options.n];
+    for _ in 0..options.num_points {
+        let u = sample(&mut rng, options.n, options.bounds);
+        let d = sample(&mut rng, n1, (-1.0, 1.0));
+        f1_jacobian_product(&u, &d, &mut supplied)?;
+        let status = Cell::new(Ok(()));
+        fd.jacobian_transpose_product(
+            |u, f1u| status.set(status.get().and(f1(u, f1u))),
+            &u,
+            &d,
+            &mut finite_diff,
+        );
+        status.get()?;
+        push_errors(&mut report, &u, &supplied, &finite_diff);
+    }
+    Ok(report)
+}
+
+/// Checks both derivatives of a problem given as for `AlmFactory::new`
+pub fn check_derivatives<F, DF, F1, JF1>(
+    options: &CheckOptions,
+    f: F,
+    df: DF,
+    f1: F1,
+    f1_jacobian_product: JF1,
+    n1: usize,
+) -> Result<(Report, Report), SolverError>
+where
+    F: Fn(&[f64], &mut f64) -> Result<(), SolverError>,
+    DF: Fn(&[f64], &mut [f64]) -> Result<(), SolverError>,
+    F1: Fn(&[f64], &mut [f64]) -> Result<(), SolverError>,
+    JF1: Fn(&[f64], &[f64], &mut [f64]) -> Result<(), SolverError>,
+{
+    Ok((
+        check_gradient(options, f, df)?,
+        check_jacobian_product(options, f1, f1_jacobian_product, n1)?,
+    ))
+}
+
+/// Orders relative errors with NaN above all the others
+fn rank(p: &f64, q: &f64) -> Ordering {
+    p.is_nan().cmp(&q.is_nan()).then(p.total_cmp(q))
+}
+
+fn rng(options: &CheckOptions) -> StdRng {
+    SeedableRng::from_seed(&[options.seed][..])
+}
+
+fn sample(rng: &mut StdRng, n: usize, (lower, upper): (f64, f64)) -> Vec<f64> {
+    (0..n).map(|_| rng.gen_range(lower, upper)).collect()
+}
+
+fn push_errors(report: &mut Report, u: &[f64], supplied: &[f64], finite_diff: &[f64]) {
+    for (component, (&s, &fd)) in supplied.iter().zip(finite_diff).enumerate() {
+        report.errors.push(ComponentError {
+            point: u.to_vec(),
+            component,
+            supplied: s,
+            finite_diff: fd,
+            relative_error: (s - fd).abs() / fd.abs().max(1.0),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn f(u: &[f64], cost: &mut f64) -> Result<(), SolverError> {
+        *cost = u[0].powi(2) + u[0] * u[1];
+        Ok(())
+    }
+
+    #[test]
+    fn correct_gradient_passes() {
+        let df = |u: &[f64], grad: &mut [f64]| -> Result<(), SolverError> {
+            grad[0] = 2.0 * u[0] + u[1];
+            grad[1] = u[0];
+            Ok(())
+        };
+        let report = check_gradient(&CheckOptions::new(2), f, df).unwrap();
+        assert_eq!(report.errors.len(), 20);
+        assert!(report.passed(), "{}", report);
+    }
+
+    #[test]
+    fn nan_gradient_is_the_worst_error() {
+        let df = |u: &[f64], grad: &mut [f64]| -> Result<(), SolverError> {
+            grad[0] = 2.0 * u[0] + u[1];
+            grad[1] = if u[1] > 0.0 { f64::NAN } else { u[0] + 1.0 };
+            Ok(())
+        };
+        let report = check_gradient(&CheckOptions::new(2), f, df).unwrap();
+        assert!(!report.passed());
+        assert!(report.max_error(1).is_nan());
+        assert!(report.max_error(0) < 1e-6);
+        let worst = report.worst().unwrap();
+        assert_eq!(worst.component, 1);
+        assert!(worst.supplied.is_nan());
+        assert!(report.to_string().contains("FAILED"));
+    }
+}
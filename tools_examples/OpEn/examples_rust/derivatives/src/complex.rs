@@ -0,0 +1,247 @@
+use crate::Scalar;
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+/// Complex number `re + im i`, used by complex-step differences
+///
+/// Evaluating `g(u + h e_j i)` for a tiny `h` gives `dg/du_j = im / h` without
+/// subtractive cancellation.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Complex {
+    pub re: f64,
+    pub im: f64,
+}
+
+impl Complex {
+    pub fn new(re: f64, im: f64) -> Self {
+        Complex { re, im }
+    }
+
+    /// `|z|`
+    pub fn norm(self) -> f64 {
+        self.re.hypot(self.im)
+    }
+}
+
+impl Add for Complex {
+    type Output = Complex;
+    fn add(self, rhs: Complex) -> Complex {
+        Complex::new(self.re + rhs.re, self.im + rhs.im)
+    }
+}
+
+impl Sub for Complex {
+    type Output = Complex;
+    fn sub(self, rhs: Complex) -> Complex {
+        Complex::new(self.re - rhs.re, self.im - rhs.im)
+    }
+}
+
+impl Mul for Complex {
+    type Output = Complex;
+    fn mul(self, rhs: Complex) -> Complex {
+        Complex::new(
+            self.re * rhs.re - self.im * rhs.im,
+            self.re * rhs.im + self.im * rhs.re,
+        )
+    }
+}
+
+impl Div for Complex {
+    type Output = Complex;
+    fn div(self, rhs: Complex) -> Complex {
+        let denominator = rhs.re * rhs.re + rhs.im * rhs.im;
+        Complex::new(
+            (self.re * rhs.re + self.im * rhs.im) / denominator,
+            (self.im * rhs.re - self.re * rhs.im) / denominator,
+        )
+    }
+}
+
+impl Neg for Complex {
+    type Output = Complex;
+    fn neg(self) -> Complex {
+        Complex::new(-self.re, -self.im)
+    }
+}
+
+impl Add<f64> for Complex {
+    type Output = Complex;
+    fn add(self, rhs: f64) -> Complex {
+        Complex::new(self.re + rhs, self.im)
+    }
+}
+
+impl Sub<f64> for Complex {
+    type Output = Complex;
+    fn sub(self, rhs: f64) -> Complex {
+        Complex::new(self.re - rhs, self.im)
+    }
+}
+
+impl Mul<f64> for Complex {
+    type Output = Complex;
+    fn mul(self, rhs: f64) -> Complex {
+        Complex::new(self.re * rhs, self.im * rhs)
+    }
+}
+
+impl Div<f64> for Complex {
+    type Output = Complex;
+    fn div(self, rhs: f64) -> Complex {
+        Complex::new(self.re / rhs, self.im / rhs)
+    }
+}
+
+impl Scalar for Complex {
+    fn from_f64(value: f64) -> Self {
+        Complex::new(value, 0.0)
+    }
+
+    fn value(self) -> f64 {
+        self.re
+    }
+
+    fn chain(x: &[Self], g: f64, dg: &[f64]) -> Self {
+        // First order in im, which is exact for complex steps
+        let im = x
+            .iter()
+            .zip(dg)
+            .filter(|(x_i, _)| x_i.im != 0.0)
+            .map(|(x_i, dg_i)| x_i.im * dg_i)
+            .sum();
+        Complex::new(g, im)
+    }
+
+    fn sqrt(self) -> Self {
+        if self.im == 0.0 {
+            return Complex::new(self.re.sqrt(), 0.0);
+        }
+        // Principal square root, avoiding r - re for tiny imaginary parts
+        let r = self.norm();
+        if self.re >= 0.0 {
+            let t = ((r + self.re) / 2.0).sqrt();
+            Complex::new(t, self.im / (2.0 * t))
+        } else {
+            let t = ((r - self.re) / 2.0).sqrt();
+            Complex::new(self.im.abs() / (2.0 * t), t.copysign(self.im))
+        }
+    }
+
+    fn powi(self, n: i32) -> Self {
+        let mut result = Complex::new(1.0, 0.0);
+        let mut base = self;
+        let mut k = n.unsigned_abs();
+        while k > 0 {
+            if k & 1 == 1 {
+                result = result * base;
+            }
+            base = base * base;
+            k >>= 1;
+        }
+        if n < 0 {
+            Complex::new(1.0, 0.0) / result
+        } else {
+            result
+        }
+    }
+
+    fn sin(self) -> Self {
+        Complex::new(
+            self.re.sin() * self.im.cosh(),
+            self.re.cos() * self.im.sinh(),
+        )
+    }
+
+    fn cos(self) -> Self {
+        Complex::new(
+            self.re.cos() * self.im.cosh(),
+            -self.re.sin() * self.im.sinh(),
+        )
+    }
+
+    fn exp(self) -> Self {
+        let e = self.re.exp();
+        Complex::new(e * self.im.cos(), e * self.im.sin())
+    }
+
+    fn ln(self) -> Self {
+        Complex::new(self.norm().ln(), self.im.atan2(self.re))
+    }
+
+    /// Analytic continuation of `|x|` from the real axis
+    fn abs(self) -> Self {
+        if self.re < 0.0 {
+            -self
+        } else {
+            self
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const H: f64 = 1e-20;
+
+    /// `(value, derivative)` of `g` at `x` by a complex step
+    fn eval(g: impl Fn(Complex) -> Complex, x: f64) -> (f64, f64) {
+        let r = g(Complex::new(x, H));
+        (r.re, r.im / H)
+    }
+
+    fn assert_close(actual: (f64, f64), expected: (f64, f64)) {
+        assert!(
+            (actual.0 - expected.0).abs() < 1e-14 && (actual.1 - expected.1).abs() < 1e-14,
+            "{:?} != {:?}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn arithmetic_matches_closed_forms() {
+        let x = 1.5;
+        let y = Complex::new(-0.5, 2.0 * H); // dy/dx = 2
+        assert_close(eval(|u| u + y, x), (1.0, 3.0));
+        assert_close(eval(|u| u - y, x), (2.0, -1.0));
+        assert_close(eval(|u| u * y, x), (-0.75, -0.5 + 3.0));
+        assert_close(eval(|u| u / y, x), (-3.0, (-0.5 - 3.0) / 0.25));
+        assert_close(eval(|u| -u, x), (-1.5, -1.0));
+        assert_close(eval(|u| u * 2.0 + 1.0, x), (4.0, 2.0));
+        assert_close(eval(|u| (u - 1.0) / 2.0, x), (0.25, 0.5));
+    }
+
+    #[test]
+    fn functions_match_closed_forms() {
+        let x: f64 = 0.7;
+        assert_close(eval(Scalar::sqrt, x), (x.sqrt(), 0.5 / x.sqrt()));
+        assert_close(eval(|u| u.powi(3), x), (x.powi(3), 3.0 * x * x));
+        assert_close(eval(|u| u.powi(-2), x), (x.powi(-2), -2.0 * x.powi(-3)));
+        assert_close(eval(Scalar::sin, x), (x.sin(), x.cos()));
+        assert_close(eval(Scalar::cos, x), (x.cos(), -x.sin()));
+        assert_close(eval(Scalar::exp, x), (x.exp(), x.exp()));
+        assert_close(eval(Scalar::ln, x), (x.ln(), 1.0 / x));
+        assert_close(eval(Scalar::abs, -x), (x, -1.0));
+        assert_close(eval(Scalar::abs, x), (x, 1.0));
+        assert_close(eval(|u| u.max(Complex::from_f64(1.0)), x), (1.0, 0.0));
+    }
+
+    #[test]
+    fn sqrt_is_the_principal_root() {
+        let z = Complex::new(-4.0, 1e-3).sqrt();
+        assert!((z * z - Complex::new(-4.0, 1e-3)).norm() < 1e-15);
+        assert!(z.im > 0.0);
+        assert_eq!(Complex::from_f64(4.0).sqrt(), Complex::new(2.0, 0.0));
+    }
+
+    #[test]
+    fn chain_is_first_order_in_the_imaginary_parts() {
+        // atan2(y, x) at (x, y) = (1, 2), with its gradient (-y, x) / (x^2 + y^2)
+        let (x, y) = (1.0_f64, 2.0_f64);
+        let g = y.atan2(x);
+        let dg = [-y / 5.0, x / 5.0];
+        let r = Complex::chain(&[Complex::new(x, H), Complex::from_f64(y)], g, &dg);
+        assert_close((r.re, r.im / H), (g, dg[0]));
+    }
+}
@@ -0,0 +1,228 @@
+use crate::Scalar;
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+/// Dual number `re + eps * e` with `e^2 = 0`
+///
+/// Evaluating `g(u + d e)` gives `g(u)` in `re` and the directional
+/// derivative `Dg(u) d` in `eps`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Dual {
+    pub re: f64,
+    pub eps: f64,
+}
+
+impl Dual {
+    pub fn new(re: f64, eps: f64) -> Self {
+        Dual { re, eps }
+    }
+
+    /// Independent variable (unit derivative)
+    pub fn variable(re: f64) -> Self {
+        Dual { re, eps: 1.0 }
+    }
+
+    /// `g(self)` given `g(re)` and `g'(re)`
+    fn apply(self, g: f64, dg: f64) -> Self {
+        // Skip dg when eps = 0, so that e.g. sqrt(0) of a constant stays finite
+        let eps = if self.eps == 0.0 { 0.0 } else { dg * self.eps };
+        Dual { re: g, eps }
+    }
+}
+
+impl Add for Dual {
+    type Output = Dual;
+    fn add(self, rhs: Dual) -> Dual {
+        Dual::new(self.re + rhs.re, self.eps + rhs.eps)
+    }
+}
+
+impl Sub for Dual {
+    type Output = Dual;
+    fn sub(self, rhs: Dual) -> Dual {
+        Dual::new(self.re - rhs.re, self.eps - rhs.eps)
+    }
+}
+
+impl Mul for Dual {
+    type Output = Dual;
+    fn mul(self, rhs: Dual) -> Dual {
+        Dual::new(self.re * rhs.re, self.eps * rhs.re + self.re * rhs.eps)
+    }
+}
+
+impl Div for Dual {
+    type Output = Dual;
+    fn div(self, rhs: Dual) -> Dual {
+        Dual::new(
+            self.re / rhs.re,
+            (self.eps * rhs.re - self.re * rhs.eps) / (rhs.re * rhs.re),
+        )
+    }
+}
+
+impl Neg for Dual {
+    type Output = Dual;
+    fn neg(self) -> Dual {
+        Dual::new(-self.re, -self.eps)
+    }
+}
+
+impl Add<f64> for Dual {
+    type Output = Dual;
+    fn add(self, rhs: f64) -> Dual {
+        Dual::new(self.re + rhs, self.eps)
+    }
+}
+
+impl Sub<f64> for Dual {
+    type Output = Dual;
+    fn sub(self, rhs: f64) -> Dual {
+        Dual::new(self.re - rhs, self.eps)
+    }
+}
+
+impl Mul<f64> for Dual {
+    type Output = Dual;
+    fn mul(self, rhs: f64) -> Dual {
+        Dual::new(self.re * rhs, self.eps * rhs)
+    }
+}
+
+impl Div<f64> for Dual {
+    type Output = Dual;
+    fn div(self, rhs: f64) -> Dual {
+        Dual::new(self.re / rhs, self.eps / rhs)
+    }
+}
+
+impl Scalar for Dual {
+    fn from_f64(value: f64) -> Self {
+        Dual::new(value, 0.0)
+    }
+
+    fn value(self) -> f64 {
+        self.re
+    }
+
+    fn chain(x: &[Self], g: f64, dg: &[f64]) -> Self {
+        let eps = x
+            .iter()
+            .zip(dg)
+            .filter(|(x_i, _)| x_i.eps != 0.0)
+            .map(|(x_i, dg_i)| x_i.eps * dg_i)
+            .sum();
+        Dual::new(g, eps)
+    }
+
+    fn sqrt(self) -> Self {
+        let s = self.re.sqrt();
+        self.apply(s, 0.5 / s)
+    }
+
+    fn powi(self, n: i32) -> Self {
+        self.apply(self.re.powi(n), f64::from(n) * self.re.powi(n - 1))
+    }
+
+    fn sin(self) -> Self {
+        self.apply(self.re.sin(), self.re.cos())
+    }
+
+    fn cos(self) -> Self {
+        self.apply(self.re.cos(), -self.re.sin())
+    }
+
+    fn exp(self) -> Self {
+        let e = self.re.exp();
+        self.apply(e, e)
+    }
+
+    fn ln(self) -> Self {
+        self.apply(self.re.ln(), 1.0 / self.re)
+    }
+
+    fn abs(self) -> Self {
+        self.apply(self.re.abs(), self.re.signum())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `(value, derivative)` of `g` at `x` in direction 1
+    fn eval(g: impl Fn(Dual) -> Dual, x: f64) -> (f64, f64) {
+        let r = g(Dual::variable(x));
+        (r.re, r.eps)
+    }
+
+    fn assert_close(actual: (f64, f64), expected: (f64, f64)) {
+        assert!(
+            (actual.0 - expected.0).abs() < 1e-12 && (actual.1 - expected.1).abs() < 1e-12,
+            "{:?} != {:?}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn arithmetic_matches_closed_forms() {
+        let x = 1.5;
+        let y = Dual::new(-0.5, 2.0); // dy/dx = 2
+        assert_close(eval(|u| u + y, x), (1.0, 3.0));
+        assert_close(eval(|u| u - y, x), (2.0, -1.0));
+        assert_close(eval(|u| u * y, x), (-0.75, -0.5 + 3.0));
+        assert_close(eval(|u| u / y, x), (-3.0, (-0.5 - 3.0) / 0.25));
+        assert_close(eval(|u| -u, x), (-1.5, -1.0));
+        assert_close(eval(|u| u + 2.0, x), (3.5, 1.0));
+        assert_close(eval(|u| u - 2.0, x), (-0.5, 1.0));
+        assert_close(eval(|u| u * 2.0, x), (3.0, 2.0));
+        assert_close(eval(|u| u / 2.0, x), (0.75, 0.5));
+    }
+
+    #[test]
+    fn functions_match_closed_forms() {
+        let x: f64 = 0.7;
+        assert_close(eval(Scalar::sqrt, x), (x.sqrt(), 0.5 / x.sqrt()));
+        assert_close(eval(|u| u.powi(3), x), (x.powi(3), 3.0 * x * x));
+        assert_close(eval(|u| u.powi(-2), x), (x.powi(-2), -2.0 * x.powi(-3)));
+        assert_close(eval(Scalar::sin, x), (x.sin(), x.cos()));
+        assert_close(eval(Scalar::cos, x), (x.cos(), -x.sin()));
+        assert_close(eval(Scalar::exp, x), (x.exp(), x.exp()));
+        assert_close(eval(Scalar::ln, x), (x.ln(), 1.0 / x));
+        assert_close(eval(Scalar::abs, -x), (x, -1.0));
+        assert_close(eval(Scalar::abs, x), (x, 1.0));
+    }
+
+    #[test]
+    fn sqrt_of_a_constant_zero_stays_finite() {
+        assert_eq!(Dual::from_f64(0.0).sqrt(), Dual::new(0.0, 0.0));
+        assert_eq!(Dual::variable(0.0).sqrt().eps, f64::INFINITY);
+    }
+
+    #[test]
+    fn max_and_min_follow_the_selected_branch() {
+        let zero = Dual::from_f64(0.0);
+        assert_close(eval(|u| u.max(zero), 0.5), (0.5, 1.0));
+        assert_close(eval(|u| u.max(zero), -0.5), (0.0, 0.0));
+        assert_close(eval(|u| u.min(zero), -0.5), (-0.5, 1.0));
+        // At the kink of max(0, .) the first argument wins
+        assert_close(eval(|u| u.max(zero), 0.0), (0.0, 1.0));
+        assert_close(eval(|u| zero.max(u), 0.0), (0.0, 0.0));
+    }
+
+    #[test]
+    fn chain_projects_the_gradient_on_the_seeded_direction() {
+        // atan2(y, x) at (x, y) = (1, 2), with its gradient (-y, x) / (x^2 + y^2)
+        let (x, y) = (1.0_f64, 2.0_f64);
+        let g = y.atan2(x);
+        let dg = [-y / 5.0, x / 5.0];
+        let d = (0.3, -1.1);
+        let r = Dual::chain(&[Dual::new(x, d.0), Dual::new(y, d.1)], g, &dg);
+        assert_close((r.re, r.eps), (g, dg[0] * d.0 + dg[1] * d.1));
+
+        // Constants do not contribute, even where dg is not finite
+        let r = Dual::chain(&[Dual::variable(x), Dual::from_f64(y)], g, &[dg[0], f64::NAN]);
+        assert_close((r.re, r.eps), (g, dg[0]));
+        assert_eq!(f64::chain(&[x, y], g, &dg), g);
+    }
+}
@@ -0,0 +1,352 @@
+//! Finite-difference derivatives
+//!
+//! The step for coordinate `i` is `relative_step * max(1, |u_i|)`. The
+//! perturbed points and the values of the function are written into a
+//! workspace, which is only resized when the dimensions change.
+
+use crate::{Complex, Scalar};
+use std::cell::RefCell;
+
+/// Finite-difference scheme
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scheme {
+    /// `(g(u + h e_i) - g(u)) / h`, one extra evaluation per coordinate
+    Forward,
+    /// `(g(u + h e_i) - g(u - h e_i)) / 2h`, two extra evaluations per coordinate
+    Central,
+    /// `Im g(u + i h e_i) / h`, needs a function evaluated on [`Complex`] numbers
+    ComplexStep,
+}
+
+impl Scheme {
+    /// Relative step that balances truncation and round-off errors
+    pub fn default_relative_step(self) -> f64 {
+        match self {
+            Scheme::Forward => f64::EPSILON.sqrt(),
+            Scheme::Central => f64::EPSILON.cbrt(),
+            // No subtraction, so the step can be as small as we like
+            Scheme::ComplexStep => 1e-20,
+        }
+    }
+}
+
+/// Number type a function is evaluated on by [`FiniteDiff`]
+pub trait StepScalar: Scalar {
+    /// `u_i + h i`, if this type has an imaginary part
+    fn imaginary_step(u_i: f64, h: f64) -> Option<Self>;
+
+    /// Imaginary part
+    fn im(self) -> f64;
+}
+
+impl StepScalar for f64 {
+    fn imaginary_step(_u_i: f64, _h: f64) -> Option<Self> {
+        None
+    }
+
+    fn im(self) -> f64 {
+        0.0
+    }
+}
+
+impl StepScalar for Complex {
+    fn imaginary_step(u_i: f64, h: f64) -> Option<Self> {
+        Some(Complex::new(u_i, h))
+    }
+
+    fn im(self) -> f64 {
+        self.im
+    }
+}
+
+/// Preallocated buffers
+#[derive(Debug)]
+struct Workspace<S> {
+    /// Perturbed point
+    u_h: Vec<S>,
+    /// Value of the function at `u_h`
+    f_h: Vec<S>,
+    f_plus: Vec<f64>,
+    f_minus: Vec<f64>,
+}
+
+/// Finite-difference engine for functions of `n` variables evaluated on `S`
+/// (`f64`, or [`Complex`] for [`Scheme::ComplexStep`])
+///
+/// Mappings write their value into the buffer they are given, like the `F1`
+/// closures handed to OpEn, so evaluations allocate nothing. The workspace
+/// lives in a `RefCell`, so the engine can be kept in a `ProblemMaster` and
+/// used from those closures.
+#[derive(Debug)]
+pub struct FiniteDiff<S = f64> {
+    scheme: Scheme,
+    relative_step: f64,
+    workspace: RefCell<Workspace<S>>,
+}
+
+impl<S: StepScalar> FiniteDiff<S> {
+    /// # Panics
+    ///
+    /// If `scheme` is [`Scheme::ComplexStep`] and `S` has no imaginary part
+    pub fn new(scheme: Scheme, n: usize) -> Self {
+        assert!(
+            scheme != Scheme::ComplexStep || S::imaginary_step(0.0, 1.0).is_some(),
+            "complex-step differences need a function evaluated on Complex numbers"
+        );
+        FiniteDiff {
+            scheme,
+            relative_step: scheme.default_relative_step(),
+            workspace: RefCell::new(Workspace {
+                u_h: vec![S::from_f64(0.0); n],
+                f_h: Vec::new(),
+                f_plus: Vec::new(),
+                f_minus: Vec::new(),
+            }),
+        }
+    }
+
+    pub fn with_relative_step(mut self, relative_step: f64) -> Self {
+        assert!(relative_step > 0.0, "relative_step must be positive");
+        self.relative_step = relative_step;
+        self
+    }
+
+    pub fn scheme(&self) -> Scheme {
+        self.scheme
+    }
+
+    /// Step used for a coordinate of value `u_i`
+    pub fn step(&self, u_i: f64) -> f64 {
+        let h = self.relative_step * u_i.abs().max(1.0);
+        match self.scheme {
+            // Use a step that is exactly representable around u_i
+            Scheme::Forward | Scheme::Central => (u_i + h) - u_i,
+            Scheme::ComplexStep => h,
+        }
+    }
+
+    /// Gradient of the scalar function `f` at `u`, written into `grad`
+    pub fn gradient<F>(&self, f: F, u: &[f64], grad: &mut [f64])
+    where
+        F: Fn(&[S]) -> S,
+    {
+        self.jacobian_columns(
+            |u, f_u| f_u[0] = f(u),
+            1,
+            u,
+            |j, column| grad[j] = column[0],
+        );
+    }
+
+    /// Jacobian of the mapping `f` (which writes `f(u)` into its second
+    /// argument) at `u`, written row-major into `jac`
+    pub fn jacobian<F>(&self, f: F, u: &[f64], jac: &mut [f64])
+    where
+        F: Fn(&[S], &mut [S]),
+    {
+        let n = u.len();
+        self.jacobian_columns(f, jac.len() / n, u, |j, column| {
+            for (i, &jac_ij) in column.iter().enumerate() {
+                jac[i * n + j] = jac_ij;
+            }
+        });
+    }
+
+    /// Jacobian-transpose product `JF(u)^T d` of the mapping `f` (which writes
+    /// `f(u)` into its second argument), written into `res`
+    pub fn jacobian_transpose_product<F>(&self, f: F, u: &[f64], d: &[f64], res: &mut [f64])
+    where
+        F: Fn(&[S], &mut [S]),
+    {
+        self.jacobian_columns(f, d.len(), u, |j, column| {
+            res[j] = column.iter().zip(d).map(|(jac_ij, d_i)| jac_ij * d_i).sum();
+        });
+    }
+
+    /// Calls `column(j, dF/du_j)` for every coordinate `j`, for a mapping `f`
+    /// with `m` components
+    fn jacobian_columns<F, C>(&self, f: F, m: usize, u: &[f64], mut column: C)
+    where
+        F: Fn(&[S], &mut [S]),
+        C: FnMut(usize, &[f64]),
+    {
+        let mut workspace = self.workspace.borrow_mut();
+        let Workspace {
+            u_h,
+            f_h,
+            f_plus,
+            f_minus,
+        } = &mut *workspace;
+        u_h.clear();
+        u_h.extend(u.iter().map(|&u_i| S::from_f64(u_i)));
+        f_h.resize(m, S::from_f64(0.0));
+        f_plus.resize(m, 0.0);
+        f_minus.resize(m, 0.0);
+
+        if self.scheme == Scheme::Forward {
+            evaluate(&f, u_h, f_h, f_minus, |f_i| f_i.value());
+        }
+
+        for j in 0..u.len() {
+            let h = self.step(u[j]);
+            match self.scheme {
+                Scheme::Forward => {
+                    u_h[j] = S::from_f64(u[j] + h);
+                    evaluate(&f, u_h, f_h, f_plus, |f_i| f_i.value());
+                    for (f_plus_i, f_0_i) in f_plus.iter_mut().zip(f_minus.iter()) {
+                        *f_plus_i = (*f_plus_i - f_0_i) / h;
+                    }
+                }
+                Scheme::Central => {
+                    u_h[j] = S::from_f64(u[j] + h);
+                    evaluate(&f, u_h, f_h, f_plus, |f_i| f_i.value());
+                    u_h[j] = S::from_f64(u[j] - h);
+                    evaluate(&f, u_h, f_h, f_minus, |f_i| f_i.value());
+                    for (f_plus_i, f_minus_i) in f_plus.iter_mut().zip(f_minus.iter()) {
+                        *f_plus_i = (*f_plus_i - f_minus_i) / (2.0 * h);
+                    }
+                }
+                Scheme::ComplexStep => {
+                    // Checked in new()
+                    u_h[j] = S::imaginary_step(u[j], h).unwrap();
+                    evaluate(&f, u_h, f_h, f_plus, |f_i| f_i.im() / h);
+                }
+            }
+            u_h[j] = S::from_f64(u[j]);
+            column(j, f_plus);
+        }
+    }
+}
+
+/// `out = map(f(u))`, with `f(u)` written into `f_u`
+fn evaluate<S, F, M>(f: &F, u: &[S], f_u: &mut [S], out: &mut [f64], map: M)
+where
+    S: Copy,
+    F: Fn(&[S], &mut [S]),
+    M: Fn(S) -> f64,
+{
+    f(u, f_u);
+    for (out_i, &f_i) in out.iter_mut().zip(f_u.iter()) {
+        *out_i = map(f_i);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `g(u) = (u_0^3 + u_0 sin u_1, exp(u_0 u_1))`
+    fn mapping<S: Scalar>(u: &[S], g: &mut [S]) {
+        g[0] = u[0].powi(3) + u[0] * u[1].sin();
+        g[1] = (u[0] * u[1]).exp();
+    }
+
+    /// Row-major Jacobian of `mapping`
+    fn jacobian(u: &[f64]) -> [f64; 4] {
+        let e = (u[0] * u[1]).exp();
+        [
+            3.0 * u[0] * u[0] + u[1].sin(),
+            u[0] * u[1].cos(),
+            u[1] * e,
+            u[0] * e,
+        ]
+    }
+
+    const POINTS: [[f64; 2]; 3] = [[0.0, 0.0], [0.8, -1.3], [-25.0, 0.05]];
+
+    fn assert_jacobian<S: StepScalar>(scheme: Scheme, tolerance: f64) {
+        let fd = FiniteDiff::<S>::new(scheme, 2);
+        for u in POINTS.iter() {
+            let expected = jacobian(u);
+            let mut jac = [0.0; 4];
+            fd.jacobian(mapping, u, &mut jac);
+            for (actual, expected) in jac.iter().zip(expected.iter()) {
+                let error = (actual - expected).abs() / expected.abs().max(1.0);
+                assert!(error < tolerance, "{:?}: {:?} at {:?}", scheme, jac, u);
+            }
+
+            let mut grad = [0.0; 2];
+            fd.gradient(
+                |u| {
+                    let mut g = [S::from_f64(0.0); 2];
+                    mapping(u, &mut g);
+                    g[1]
+                },
+                u,
+                &mut grad,
+            );
+            assert!((grad[0] - expected[2]).abs() / expected[2].abs().max(1.0) < tolerance);
+            assert!((grad[1] - expected[3]).abs() / expected[3].abs().max(1.0) < tolerance);
+
+            let d = [0.5, -2.0];
+            let mut res = [0.0; 2];
+            fd.jacobian_transpose_product(mapping, u, &d, &mut res);
+            for j in 0..2 {
+                let expected = expected[j] * d[0] + expected[2 + j] * d[1];
+                assert!((res[j] - expected).abs() / expected.abs().max(1.0) < tolerance);
+            }
+        }
+    }
+
+    #[test]
+    fn forward_differences() {
+        assert_jacobian::<f64>(Scheme::Forward, 1e-6);
+    }
+
+    #[test]
+    fn central_differences() {
+        assert_jacobian::<f64>(Scheme::Central, 1e-7);
+    }
+
+    #[test]
+    fn complex_step() {
+        assert_jacobian::<Complex>(Scheme::ComplexStep, 1e-14);
+    }
+
+    #[test]
+    #[should_panic]
+    fn complex_step_needs_complex_numbers() {
+        FiniteDiff::<f64>::new(Scheme::ComplexStep, 2);
+    }
+
+    #[test]
+    fn step_scales_with_the_coordinate() {
+        let fd = FiniteDiff::<f64>::new(Scheme::Central, 1).with_relative_step(1e-4);
+        // At least the relative step, also at u_i = 0
+        assert_eq!(fd.step(0.0), 1e-4);
+        assert_eq!(fd.step(-0.5), (-0.5 + 1e-4) - -0.5);
+        // Proportional to |u_i| above 1, and exactly representable around u_i
+        for &u_i in [300.0, -300.0, 1.0e6].iter() {
+            let h = fd.step(u_i);
+            assert!((h / (1e-4 * f64::abs(u_i)) - 1.0).abs() < 1e-9);
+            assert_eq!((u_i + h) - u_i, h);
+        }
+
+        let fd = FiniteDiff::<Complex>::new(Scheme::ComplexStep, 1);
+        assert_eq!(fd.step(0.0), 1e-20);
+        assert_eq!(fd.step(-300.0), 3e-18);
+    }
+
+    #[test]
+    fn workspace_is_reused() {
+        let fd = FiniteDiff::<f64>::new(Scheme::Central, 2);
+        let buffers = |fd: &FiniteDiff| {
+            let workspace = fd.workspace.borrow();
+            (
+                workspace.u_h.as_ptr(),
+                workspace.f_h.as_ptr(),
+                workspace.f_plus.as_ptr(),
+                workspace.f_minus.as_ptr(),
+            )
+        };
+        let mut jac = [0.0; 4];
+        fd.jacobian(mapping, &POINTS[1], &mut jac);
+        let allocated = buffers(&fd);
+        for u in POINTS.iter() {
+            fd.jacobian(mapping, u, &mut jac);
+            let mut res = [0.0; 2];
+            fd.jacobian_transpose_product(mapping, u, &[1.0, 1.0], &mut res);
+            assert_eq!(buffers(&fd), allocated);
+        }
+    }
+}
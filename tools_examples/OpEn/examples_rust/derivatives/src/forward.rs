@@ -0,0 +1,42 @@
+//! Forward-mode AD with [`Dual`] numbers
+//!
+//! One evaluation per coordinate of `u`, which is cheap for the small
+//! decision vectors of the examples.
+
+use crate::Dual;
+
+/// `u` with the `j`-th coordinate seeded as the independent variable
+fn seed(u: &[f64], j: usize, u_dual: &mut Vec<Dual>) {
+    u_dual.clear();
+    u_dual.extend(u.iter().map(|&u_i| Dual::new(u_i, 0.0)));
+    u_dual[j] = Dual::variable(u[j]);
+}
+
+/// Gradient of the scalar function `f` at `u`, written into `grad`
+pub fn gradient<F>(f: F, u: &[f64], grad: &mut [f64])
+where
+    F: Fn(&[Dual]) -> Dual,
+{
+    let mut u_dual = Vec::with_capacity(u.len());
+    for (j, grad_j) in grad.iter_mut().enumerate().take(u.len()) {
+        seed(u, j, &mut u_dual);
+        *grad_j = f(&u_dual).eps;
+    }
+}
+
+/// Jacobian-transpose product `JF(u)^T d` of the mapping `f`, written into `res`
+pub fn jacobian_transpose_product<F>(f: F, u: &[f64], d: &[f64], res: &mut [f64])
+where
+    F: Fn(&[Dual]) -> Vec<Dual>,
+{
+    let mut u_dual = Vec::with_capacity(u.len());
+    for (j, res_j) in res.iter_mut().enumerate().take(u.len()) {
+        seed(u, j, &mut u_dual);
+        // Column j of the Jacobian, projected on d
+        *res_j = f(&u_dual)
+            .iter()
+            .zip(d)
+            .map(|(f_i, d_i)| f_i.eps * d_i)
+            .sum();
+    }
+}
@@ -0,0 +1,25 @@
+//! # Derivatives
+//!
+//! Automatic differentiation for the cost `f` and the mapping `F1` handed to
+//! OpEn's `AlmFactory`.
+//!
+//! Write `f_call`/`f1_call` once, generic over a [`Scalar`], and obtain
+//! `df` and `JF1(u)^T d` from the functions in [`forward`] (one evaluation
+//! per coordinate of `u`) or [`reverse`] (one recorded evaluation and one
+//! backward sweep).
+//!
+//! [`finite_diff`] offers forward, central and complex-step differences for
+//! functions that are not (or cannot be) written generically, and
+//! [`check`] compares hand-written derivatives against them.
+
+pub mod check;
+mod complex;
+mod dual;
+pub mod finite_diff;
+pub mod forward;
+pub mod reverse;
+mod scalar;
+
+pub use complex::Complex;
+pub use dual::Dual;
+pub use scalar::Scalar;
@@ -0,0 +1,330 @@
+//! Reverse-mode AD with a tape
+//!
+//! `F1` is recorded once and `JF1(u)^T d` is obtained with a single backward
+//! sweep, whatever the dimension of `u`.
+
+use crate::Scalar;
+use std::cell::RefCell;
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+/// Record of the operations performed on [`Var`]s
+///
+/// Node `k` depends on `edges[nodes[k].0..nodes[k].1]`, each edge being
+/// `(parent, partial derivative)`.
+#[derive(Debug, Default)]
+pub struct Tape {
+    nodes: RefCell<Vec<(usize, usize)>>,
+    edges: RefCell<Vec<(usize, f64)>>,
+}
+
+impl Tape {
+    pub fn new() -> Self {
+        Tape::default()
+    }
+
+    /// New independent variable
+    pub fn variable(&self, value: f64) -> Var<'_> {
+        self.push(value, None)
+    }
+
+    fn push<I>(&self, value: f64, parents: I) -> Var<'_>
+    where
+        I: IntoIterator<Item = (usize, f64)>,
+    {
+        let mut nodes = self.nodes.borrow_mut();
+        let mut edges = self.edges.borrow_mut();
+        let start = edges.len();
+        edges.extend(parents);
+        nodes.push((start, edges.len()));
+        Var {
+            tape: Some(self),
+            index: nodes.len() - 1,
+            value,
+        }
+    }
+
+    /// Adjoints of all the nodes, given the adjoints of some of them
+    fn backward(&self, seeds: &[(usize, f64)]) -> Vec<f64> {
+        let nodes = self.nodes.borrow();
+        let edges = self.edges.borrow();
+        let mut adjoint = vec![0.0; nodes.len()];
+        for &(index, seed) in seeds {
+            adjoint[index] += seed;
+        }
+        for k in (0..nodes.len()).rev() {
+            // Skipping zero adjoints also avoids 0 * inf, e.g. sqrt at 0
+            if adjoint[k] == 0.0 {
+                continue;
+            }
+            let (start, end) = nodes[k];
+            for &(parent, partial) in &edges[start..end] {
+                adjoint[parent] += adjoint[k] * partial;
+            }
+        }
+        adjoint
+    }
+}
+
+/// Number recorded on a [`Tape`]; constants are not recorded
+#[derive(Debug, Clone, Copy)]
+pub struct Var<'t> {
+    tape: Option<&'t Tape>,
+    index: usize,
+    value: f64,
+}
+
+impl<'t> Var<'t> {
+    /// `g(self)` given `g(value)` and `g'(value)`
+    fn unary(self, g: f64, dg: f64) -> Self {
+        match self.tape {
+            Some(tape) => tape.push(g, Some((self.index, dg))),
+            None => Var::from_f64(g),
+        }
+    }
+
+    /// `g(self, rhs)` given `g` and its partial derivatives
+    fn binary(self, rhs: Self, g: f64, dg_lhs: f64, dg_rhs: f64) -> Self {
+        match (self.tape, rhs.tape) {
+            (Some(tape), Some(_)) => tape.push(
+                g,
+                [(self.index, dg_lhs), (rhs.index, dg_rhs)].iter().copied(),
+            ),
+            (Some(tape), None) => tape.push(g, Some((self.index, dg_lhs))),
+            (None, Some(tape)) => tape.push(g, Some((rhs.index, dg_rhs))),
+            (None, None) => Var::from_f64(g),
+        }
+    }
+}
+
+impl<'t> Add for Var<'t> {
+    type Output = Self;
+    fn add(self, rhs: Self) -> Self {
+        self.binary(rhs, self.value + rhs.value, 1.0, 1.0)
+    }
+}
+
+impl<'t> Sub for Var<'t> {
+    type Output = Self;
+    fn sub(self, rhs: Self) -> Self {
+        self.binary(rhs, self.value - rhs.value, 1.0, -1.0)
+    }
+}
+
+impl<'t> Mul for Var<'t> {
+    type Output = Self;
+    fn mul(self, rhs: Self) -> Self {
+        self.binary(rhs, self.value * rhs.value, rhs.value, self.value)
+    }
+}
+
+impl<'t> Div for Var<'t> {
+    type Output = Self;
+    fn div(self, rhs: Self) -> Self {
+        let g = self.value / rhs.value;
+        self.binary(rhs, g, 1.0 / rhs.value, -g / rhs.value)
+    }
+}
+
+impl<'t> Neg for Var<'t> {
+    type Output = Self;
+    fn neg(self) -> Self {
+        self.unary(-self.value, -1.0)
+    }
+}
+
+impl<'t> Add<f64> for Var<'t> {
+    type Output = Self;
+    fn add(self, rhs: f64) -> Self {
+        self.unary(self.value + rhs, 1.0)
+    }
+}
+
+impl<'t> Sub<f64> for Var<'t> {
+    type Output = Self;
+    fn sub(self, rhs: f64) -> Self {
+        self.unary(self.value - rhs, 1.0)
+    }
+}
+
+impl<'t> Mul<f64> for Var<'t> {
+    type Output = Self;
+    fn mul(self, rhs: f64) -> Self {
+        self.unary(self.value * rhs, rhs)
+    }
+}
+
+impl<'t> Div<f64> for Var<'t> {
+    type Output = Self;
+    fn div(self, rhs: f64) -> Self {
+        self.unary(self.value / rhs, 1.0 / rhs)
+    }
+}
+
+impl<'t> Scalar for Var<'t> {
+    fn from_f64(value: f64) -> Self {
+        Var {
+            tape: None,
+            index: 0,
+            value,
+        }
+    }
+
+    fn value(self) -> f64 {
+        self.value
+    }
+
+    fn chain(x: &[Self], g: f64, dg: &[f64]) -> Self {
+        match x.iter().find_map(|x_i| x_i.tape) {
+            Some(tape) => tape.push(
+                g,
+                x.iter()
+                    .zip(dg)
+                    .filter(|(x_i, _)| x_i.tape.is_some())
+                    .map(|(x_i, &dg_i)| (x_i.index, dg_i)),
+            ),
+            None => Var::from_f64(g),
+        }
+    }
+
+    fn sqrt(self) -> Self {
+        let s = self.value.sqrt();
+        self.unary(s, 0.5 / s)
+    }
+
+    fn powi(self, n: i32) -> Self {
+        self.unary(self.value.powi(n), f64::from(n) * self.value.powi(n - 1))
+    }
+
+    fn sin(self) -> Self {
+        self.unary(self.value.sin(), self.value.cos())
+    }
+
+    fn cos(self) -> Self {
+        self.unary(self.value.cos(), -self.value.sin())
+    }
+
+    fn exp(self) -> Self {
+        let e = self.value.exp();
+        self.unary(e, e)
+    }
+
+    fn ln(self) -> Self {
+        self.unary(self.value.ln(), 1.0 / self.value)
+    }
+
+    fn abs(self) -> Self {
+        self.unary(self.value.abs(), self.value.signum())
+    }
+}
+
+/// Gradient of the scalar function `f` at `u`, written into `grad`
+pub fn gradient<F>(f: F, u: &[f64], grad: &mut [f64])
+where
+    F: for<'t> Fn(&[Var<'t>]) -> Var<'t>,
+{
+    jacobian_transpose_product(|u| vec![f(u)], u, &[1.0], grad);
+}
+
+/// Jacobian-transpose product `JF(u)^T d` of the mapping `f`, written into `res`
+pub fn jacobian_transpose_product<F>(f: F, u: &[f64], d: &[f64], res: &mut [f64])
+where
+    F: for<'t> Fn(&[Var<'t>]) -> Vec<Var<'t>>,
+{
+    let tape = Tape::new();
+    let u_var: Vec<Var> = u.iter().map(|&u_i| tape.variable(u_i)).collect();
+    // Outputs that are constants do not contribute
+    let seeds: Vec<(usize, f64)> = f(&u_var)
+        .iter()
+        .zip(d)
+        .filter(|(f_i, _)| f_i.tape.is_some())
+        .map(|(f_i, &d_i)| (f_i.index, d_i))
+        .collect();
+    let adjoint = tape.backward(&seeds);
+    for (res_j, u_j) in res.iter_mut().zip(&u_var) {
+        *res_j = adjoint[u_j.index];
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::forward;
+
+    /// Mapping `R^3 -> R^3` using every operation of `Scalar`
+    fn mapping<S: Scalar>(u: &[S]) -> Vec<S> {
+        vec![
+            u[0] * u[1] / (u[2] + 3.0) - u[0].sqrt(),
+            (u[1].sin() * u[2].cos() + u[0].exp() * 2.0).ln() - u[2].abs(),
+            u[0].powi(3).max(u[1]) - u[2].min(S::from_f64(0.5)) / 2.0,
+        ]
+    }
+
+    #[test]
+    fn backward_matches_forward_mode() {
+        let d = [0.7, -1.3, 0.4];
+        for u in [[0.5, 1.2, -0.3], [2.0, 0.1, 0.9], [1.1, 2.5, 0.2]].iter() {
+            let (mut reverse, mut forward) = ([0.0; 3], [0.0; 3]);
+            jacobian_transpose_product(|u| mapping(u), u, &d, &mut reverse);
+            forward::jacobian_transpose_product(mapping, u, &d, &mut forward);
+            for (r, f) in reverse.iter().zip(forward.iter()) {
+                assert!((r - f).abs() < 1e-12, "{:?} != {:?}", reverse, forward);
+            }
+
+            let (mut reverse, mut forward) = ([0.0; 3], [0.0; 3]);
+            gradient(|u| mapping(u)[1], u, &mut reverse);
+            forward::gradient(|u| mapping(u)[1], u, &mut forward);
+            for (r, f) in reverse.iter().zip(forward.iter()) {
+                assert!((r - f).abs() < 1e-12, "{:?} != {:?}", reverse, forward);
+            }
+        }
+    }
+
+    #[test]
+    fn zero_adjoints_are_skipped() {
+        // sqrt(u_0) at 0 has an infinite partial derivative, but only feeds
+        // the first output, which has a zero adjoint
+        let mut res = [0.0; 2];
+        jacobian_transpose_product(
+            |u| vec![u[0].sqrt(), u[0] * u[1]],
+            &[0.0, 3.0],
+            &[0.0, 1.0],
+            &mut res,
+        );
+        assert_eq!(res, [3.0, 0.0]);
+
+        // Constant outputs are not seeded
+        jacobian_transpose_product(
+            |u| vec![Var::from_f64(1.0), u[1] * 2.0],
+            &[1.0, 1.0],
+            &[5.0, 1.0],
+            &mut res,
+        );
+        assert_eq!(res, [0.0, 2.0]);
+    }
+
+    #[test]
+    fn tape_can_be_reused() {
+        let tape = Tape::new();
+        let x = tape.variable(2.0);
+        let y = tape.variable(3.0);
+        let f = x * y + x.sin();
+        let g = x / y;
+
+        // Every sweep starts from fresh adjoints
+        for _ in 0..2 {
+            let adjoint = tape.backward(&[(f.index, 1.0)]);
+            assert_eq!(adjoint[x.index], 3.0 + 2.0_f64.cos());
+            assert_eq!(adjoint[y.index], 2.0);
+        }
+        let adjoint = tape.backward(&[(g.index, 1.0)]);
+        assert_eq!(adjoint[x.index], 1.0 / 3.0);
+        assert_eq!(adjoint[y.index], -2.0 / 9.0);
+
+        // Recording more operations after a sweep
+        let h = f * g;
+        let adjoint = tape.backward(&[(h.index, 1.0)]);
+        let (f, g) = (f.value(), g.value());
+        assert!((adjoint[x.index] - ((3.0 + 2.0_f64.cos()) * g + f / 3.0)).abs() < 1e-12);
+        assert!((adjoint[y.index] - (2.0 * g - f * 2.0 / 9.0)).abs() < 1e-12);
+    }
+}
@@ -0,0 +1,103 @@
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+/// Real number type that `f_call`/`f1_call` can be written against
+///
+/// Implemented by `f64` (plain evaluation) and by the AD number types.
+pub trait Scalar:
+    Copy
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Div<Output = Self>
+    + Neg<Output = Self>
+    + Add<f64, Output = Self>
+    + Sub<f64, Output = Self>
+    + Mul<f64, Output = Self>
+    + Div<f64, Output = Self>
+{
+    /// Constant (zero derivative)
+    fn from_f64(value: f64) -> Self;
+
+    /// Value without derivative information
+    fn value(self) -> f64;
+
+    /// `g(x)` for a function whose value `g` and gradient `dg` at
+    /// `x.value()` are computed elsewhere, e.g. an obstacle penalty
+    fn chain(x: &[Self], g: f64, dg: &[f64]) -> Self;
+
+    fn sqrt(self) -> Self;
+    fn powi(self, n: i32) -> Self;
+    fn sin(self) -> Self;
+    fn cos(self) -> Self;
+    fn exp(self) -> Self;
+    fn ln(self) -> Self;
+    fn abs(self) -> Self;
+
+    /// Larger of the two (the derivative follows the selected branch)
+    fn max(self, other: Self) -> Self {
+        if self.value() >= other.value() {
+            self
+        } else {
+            other
+        }
+    }
+
+    /// Smaller of the two (the derivative follows the selected branch)
+    fn min(self, other: Self) -> Self {
+        if self.value() <= other.value() {
+            self
+        } else {
+            other
+        }
+    }
+}
+
+impl Scalar for f64 {
+    fn from_f64(value: f64) -> Self {
+        value
+    }
+
+    fn value(self) -> f64 {
+        self
+    }
+
+    fn chain(_x: &[Self], g: f64, _dg: &[f64]) -> Self {
+        g
+    }
+
+    fn sqrt(self) -> Self {
+        f64::sqrt(self)
+    }
+
+    fn powi(self, n: i32) -> Self {
+        f64::powi(self, n)
+    }
+
+    fn sin(self) -> Self {
+        f64::sin(self)
+    }
+
+    fn cos(self) -> Self {
+        f64::cos(self)
+    }
+
+    fn exp(self) -> Self {
+        f64::exp(self)
+    }
+
+    fn ln(self) -> Self {
+        f64::ln(self)
+    }
+
+    fn abs(self) -> Self {
+        f64::abs(self)
+    }
+
+    fn max(self, other: Self) -> Self {
+        f64::max(self, other)
+    }
+
+    fn min(self, other: Self) -> Self {
+        f64::min(self, other)
+    }
+}
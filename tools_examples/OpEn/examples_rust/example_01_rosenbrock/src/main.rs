@@ -4,6 +4,7 @@
 //!
 //! [Rosenbrock function]: https://en.wikipedia.org/wiki/Rosenbrock_function
 
+use derivatives::check::{check_gradient, CheckOptions};
 use optimization_engine::{constraints::*, panoc::*, *};
 
 fn rosenbrock_cost(a: f64, b: f64, u: &[f64]) -> f64 {
@@ -45,6 +46,11 @@ fn main() {
         }
     };
 
+    // check the hand-written gradient against finite differences
+    let options = CheckOptions::new(problem_size).with_bounds(-1.5, 1.5);
+    let report = check_gradient(&options, f, df).unwrap();
+    println!("{}", report);
+
     // define the constraints
     let bounds = Ball2::new(None, radius);
 
@@ -60,4 +66,23 @@ fn main() {
     println!("Panoc solution: {:#?}", u);
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
 
+    #[test]
+    fn gradient_matches_finite_differences() {
+        let (a, b) = (1.0, 200.0);
+        let f = |u: &[f64], c: &mut f64| -> Result<(), SolverError> {
+            *c = rosenbrock_cost(a, b, u);
+            Ok(())
+        };
+        let df = |u: &[f64], grad: &mut [f64]| -> Result<(), SolverError> {
+            rosenbrock_grad(a, b, u, grad);
+            Ok(())
+        };
+        let options = CheckOptions::new(2).with_bounds(-1.5, 1.5);
+        let report = check_gradient(&options, f, df).unwrap();
+        assert!(report.passed(), "{}", report);
+    }
+}
@@ -4,6 +4,7 @@
 //!
 //! [Rosenbrock function]: https://en.wikipedia.org/wiki/Rosenbrock_function
 
+use derivatives::check::{check_derivatives, CheckOptions};
 use optimization_engine::{
     alm::*,
     core::{constraints::*, panoc::*},
@@ -49,6 +50,11 @@ fn main() {
     let panoc_cache = PANOCCache::new(nx, tolerance, lbfgs_mem);
     let mut alm_cache = AlmCache::new(panoc_cache, n1, n2);
 
+    // Check df and f1_jacobian_product against finite differences
+    let (grad_report, jac_report) =
+        check_derivatives(&CheckOptions::new(nx), f, df, f1, f1_jacobian_product, n1).unwrap();
+    println!("{}\n{}", grad_report, jac_report);
+
     let set_c = Ball2::new(None, 0.5);
     let bounds = Ball2::new(None, 10.0);
     let set_y = Ball2::new(None, 1e12);
@@ -96,4 +102,17 @@ fn main() {
     let r = solver_result.unwrap();
     println!("\n\nSolver result : {:#.7?}\n", r);
     println!("Solution u = {:#.6?}", u);
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn derivatives_match_finite_differences() {
+        let (grad_report, jac_report) =
+            check_derivatives(&CheckOptions::new(3), f, df, f1, f1_jacobian_product, 2).unwrap();
+        assert!(grad_report.passed(), "{}", grad_report);
+        assert!(jac_report.passed(), "{}", jac_report);
+    }
+}
@@ -4,10 +4,11 @@
 //!
 //! [Rosenbrock function]: https://en.wikipedia.org/wiki/Rosenbrock_function
 
+use derivatives::check::{check_derivatives, CheckOptions};
 use optimization_engine::{
     alm::*,
     core::{constraints::*, panoc::*},
-    matrix_operations, SolverError,
+    SolverError,
 };
 
 // Smooth cost function
@@ -16,7 +17,7 @@ pub fn f(u: &[f64], cost: &mut f64) -> Result<(), SolverError> {
         Ok(())
 }
 
-pub fn df(u: &[f64], grad: &mut [f64]) -> Result<(), SolverError> {
+pub fn df(_u: &[f64], grad: &mut [f64]) -> Result<(), SolverError> {
         grad[0] = 0.0;
         grad[1] = 0.0;
         grad[2] = 1.0;    
@@ -51,6 +52,10 @@ fn main() {
     // (i.e., all those data that the algorithm updates)
     let mut alm_cache = AlmCache::new(panoc_cache, n1, n2);
 
+    // Check df and f1_jacobian_product against finite differences
+    let (grad_report, jac_report) = check_derivatives(&CheckOptions::new(nx), f, df, f1, f1_jacobian_product, n1).unwrap();
+    println!("{}\n{}", grad_report, jac_report);
+
     let set_c = Zero::new(); // Set C
     let bounds = Ball2::new(None, 100.0); // Set U
     let set_y = Ball2::new(None, 1e12);  // Set Y
@@ -103,4 +108,17 @@ fn main() {
     let r = solver_result.unwrap();
     println!("\n\nSolver result : {:#.7?}\n", r);
     println!("Solution u = {:#.6?}", u);
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn derivatives_match_finite_differences() {
+        let (grad_report, jac_report) =
+            check_derivatives(&CheckOptions::new(3), f, df, f1, f1_jacobian_product, 2).unwrap();
+        assert!(grad_report.passed(), "{}", grad_report);
+        assert!(jac_report.passed(), "{}", jac_report);
+    }
+}
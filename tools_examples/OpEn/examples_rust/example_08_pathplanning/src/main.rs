@@ -6,6 +6,10 @@ use optimization_engine::{
     constraints::*, panoc::*, *
 };
 use nalgebra::base::{*};
+// For Finite Differences
+use derivatives::finite_diff::{FiniteDiff, Scheme};
+// For Obstacles
+use obstacles::{Circle, Obstacle};
 // For plot
 use plotlib::page::Page;
 use plotlib::repr::Plot;
@@ -19,6 +23,7 @@ pub struct ProblemMaster{
     x_ref: Matrix2x1<f64>, // Robot Goal Position
     x_obs: Matrix2x1<f64>, // Obstacle Position
     u_max: f64, // Dyanmics Radius
+    fd: FiniteDiff, // Finite Differences (with workspace)
 }
 
 impl ProblemMaster{
@@ -27,14 +32,14 @@ impl ProblemMaster{
         let x_ref = _x_ref;
         let x_obs = _x_obs;
         let u_max = _u_max;
-        Self{x_now, x_ref, x_obs, u_max}            
+        let fd = FiniteDiff::new(Scheme::Forward, 2); // or Scheme::Central
+        Self{x_now, x_ref, x_obs, u_max, fd}            
     }
     
     
     // ========= Cost function (You need to modify this) =========
     pub fn f_call(&self, u: &[f64]) -> f64{        
-        let cost = (u[0]-self.x_ref[(0,0)]).powi(2) + (u[1]-self.x_ref[(1,0)]).powi(2);
-        cost
+        (u[0]-self.x_ref[(0,0)]).powi(2) + (u[1]-self.x_ref[(1,0)]).powi(2)
     }
     // ===========================================================
     
@@ -44,22 +49,14 @@ impl ProblemMaster{
     
     // Gradient of the cost function
     pub fn df(&self, u: &[f64], grad: &mut [f64]){
-        let f_0 = self.f_call(u);
-        
-        for i in 0..u.len() {
-            let mut u_h = u.to_vec();
-            u_h[i] += 0.000001;
-            let f_h = self.f_call(u_h.as_slice());
-            grad[i] = (-f_0 + f_h) / 0.000001;
-        }
-       
+        self.fd.gradient(|u| self.f_call(u), u, grad);
     } 
     
     // ========= F1 Constraint (You need to modify this) =========
-    pub fn f1_call(&self, u: &[f64])-> Vec<f64> {
-        let mut f1u = vec![0.0; u.len()];
+    // Writes F1(u) into f1u (no allocation per evaluation)
+    pub fn f1_call(&self, u: &[f64], f1u: &mut [f64]) {
         // Obstacle Avoidance Constraint (C2)
-        f1u[0] = (1.0 - (u[0]-self.x_obs[(0,0)]).powi(2) - (u[1]-self.x_obs[(1,0)]).powi(2) ).max(0.0);
+        f1u[0] = Circle::new((self.x_obs[(0,0)], self.x_obs[(1,0)]), 1.0).penalty(u);
         
         // Dynamics Constraint (C1)
         let mut u_now = Matrix2x1::new(0.0, 0.0);
@@ -68,40 +65,25 @@ impl ProblemMaster{
         }
         let delta = u_now - self.x_now;
         f1u[1] = (delta.norm() - self.u_max).max(0.0);
-        
-        return f1u;
     }
     // ===========================================================
     
     pub fn f1(&self, u: &[f64], f1u: &mut [f64]){
-        let f1u_vec = self.f1_call(u); 
-        for i in 0..f1u_vec.len(){
-            f1u[i] = f1u_vec[i];
-        }
+        self.f1_call(u, f1u);
     }    
     
     // Jacobian of F1
     pub fn jf1_call(&self, u: &[f64])-> Matrix2<f64> {
-        let mut jf1 = Matrix2::new(0.0, 0.0,
-                              0.0, 0.0);
-        
-        let f1_0 = self.f1_call(u);
-
-        for i in 0..f1_0.len(){
-            for j in 0..u.len() {
-                let mut u_h = u.to_vec();
-                u_h[j] += 0.000001;
-                let f_h = self.f1_call(u_h.as_slice());
-                jf1[(i,j)] = (-f1_0[i] + f_h[i]) / 0.000001;
-            }                        
-        }
+        let mut jf1 = [0.0; 4]; // Row-major
+        self.fd.jacobian(|u, f1u| self.f1(u, f1u), u, &mut jf1);
 
-        return jf1;        
+        Matrix2::from_row_slice(&jf1)
     } 
     
     // Jacobian Product (JF_1^{\top}*d)
     pub fn f1_jacobian_product(&self, u: &[f64], d: &[f64], res: &mut [f64]){
-        let test = self.f1_call(u);
+        let mut test = [0.0; 2];
+        self.f1_call(u, &mut test);
         
         let mut jf1_matrix = self.jf1_call(u);
         if test[0] < 0.0{ // Outside the obstacle
@@ -125,7 +107,7 @@ impl ProblemMaster{
 // Optimisation Loop for Each Time Step
 fn main_loop(_x_now: &[f64], _x_ref: &[f64], _x_obs: &[f64], _u_max: f64) -> Vec<f64> {
  
-    /// ===========================================
+    // ===========================================
     let mut x_now = Matrix2x1::new(0.0, 0.0);
     for i in 0.._x_now.len(){
         x_now[(i,0)] = _x_now[i];
@@ -145,7 +127,7 @@ fn main_loop(_x_now: &[f64], _x_ref: &[f64], _x_obs: &[f64], _u_max: f64) -> Vec
     
     let pm = ProblemMaster::init(x_now, x_ref, x_obs, u_max);
     
-    /// ===========================================
+    // ===========================================
     
     let tolerance = 1e-5;
     let nx = 2; // problem_size: dimension of the decision variables
@@ -233,11 +215,11 @@ fn main_loop(_x_now: &[f64], _x_ref: &[f64], _x_obs: &[f64], _u_max: f64) -> Vec
 
     let mut u = _x_now.to_vec(); // vec![0.0; nx]; // Initial guess
     let solver_result = alm_optimizer.solve(&mut u);
-    let r = solver_result.unwrap();
+    let _r = solver_result.unwrap();
 //     println!("\n\nSolver result : {:#.7?}\n", r);
 //     println!("Solution u = {:#.6?}", u);
     
-    return u;
+    u
     
 }
 
@@ -269,7 +251,7 @@ fn main_instance(_x_now: &[f64], _x_ref: &[f64], _x_obs: &[f64], _u_max: f64) ->
         }
         println!("Solution x_now = {:#.6?}   {:#.6?}", x_now[0], x_now[1]);
            
-        return path_result;
+        path_result
     //     }
             
         
@@ -291,7 +273,7 @@ fn get_points_obstacle(_x_obs: &[f64]) -> Vec<(f64, f64)> {
         points.push((x,y));
         println!("Circle = {:#.6?}   {:#.6?}", x, y);
     }
-    return points;
+    points
 
 }
 
@@ -1,251 +1,111 @@
 // Nonlinear-shaped Obstacle Avoidance Path Planning Example with Multiple Obstacles
 // by Dr Inmo Jang (inmo3592@gmail.com) 
 
-use optimization_engine::{
-    alm::*,
-    constraints::*, panoc::*, *
-};
-use nalgebra::base::{*};
+// For Obstacles
+use obstacles::{get_points, Difference, Ellipse, Nlr1, Nlr2, Polygon, Polyhedron, Pose, PosedObstacle, Union};
 // For plot
 use plotlib::page::Page;
 use plotlib::repr::Plot;
 use plotlib::view::ContinuousView;
-use plotlib::style::{PointMarker, PointStyle};
-
-
-// Obstacles
-// ------------ (1) Elipsoid ------------ 
-fn h_elip(x: &[f64], elip: (f64, f64, f64, f64), print: bool) -> f64{
-    // x: user position
-    // elip : (centre_x, centre_y, radius_x, radius_y)
-    // print: Flag to indicate whether to print the result
-
-    let h = (1.0 - ((x[0]-elip.0)/elip.2).powi(2) - ((x[1]-elip.1)/elip.3).powi(2) ).max(0.0);  
-    
-    if print {
-        if h > 0.0 {
-            println!("x is inside the obstacle");
-        }
-        else{
-            println!("x is outside the obstacle");
-        }
-    }    
-    return h;
-}
-
-
-// Points for Elipsoid (For Plot)
-fn get_points_elip(elip: (f64, f64, f64, f64), search_area: ((f64, f64), (f64, f64), f64) ) -> Vec<(f64, f64)> {
-    let mut points = Vec::new(); // Output initialisation    
-    let x_range = search_area.0;
-    let y_range = search_area.1;
-    let resolution = search_area.2;
-    
-    let mut x = x_range.0; // Start point
-    let mut y = y_range.0;
-
-    while x <= x_range.1{
-        while y <= y_range.1{            
-            let h = h_elip(&[x,y], elip.clone(), false);
-            if h > 0.0 {
-                points.push((x,y));
-            } 
-            y += resolution;
-        }
-        x += resolution;
-        y = y_range.0;
-    }
-    return points;
-}
-
-// ------------  (2) Polyhedral ------------ 
-fn h_poly(x: &[f64], poly: Vec<((f64, f64), f64)>, centre: (f64, f64), print: bool) -> f64{
-    // x: user position
-    // poly : <(a_i, b_i)>    
-    // print: Flag to indicate whether to print the result
-
-    let mut h: f64 = 1.0;
-    for i in 0..poly.len(){
-        let a_i = poly[i].0;
-        let b_i = poly[i].1;
-        h *= (b_i - (a_i.0*(x[0] - centre.0) + a_i.1*(x[1]- centre.1))).max(0.0);        
-    }
-    
-    if print {
-        if h > 0.0 {
-            println!("x is inside the obstacle");
-        }
-        else{
-            println!("x is outside the obstacle");
-        }
-    } 
-    
-    return h;
-}
-
-// Points for Polyhedral (For Plot)
-fn get_points_poly(poly: Vec<((f64, f64), f64)>, centre: (f64, f64), search_area: ((f64, f64), (f64, f64), f64)) -> Vec<(f64, f64)> {
-    let mut points = Vec::new(); // Output initialisation    
-    let x_range = search_area.0;
-    let y_range = search_area.1;
-    let resolution = search_area.2;
-    
-    let mut x = x_range.0; // Start point
-    let mut y = y_range.0;
-
-    while x <= x_range.1{
-        while y <= y_range.1{            
-            let h = h_poly(&[x,y], poly.clone(), centre.clone(), false);
-            if h > 0.0 {
-                points.push((x,y));
-            } 
-            y += resolution;
-        }
-        x += resolution;
-        y = y_range.0;
-    }
-    return points;
-}
-
-// ------------  (3) Nonlinear Obstacles in Sathya et al. 2019 ------------
-fn h_nlr_1(x: &[f64], centre: (f64, f64), print: bool) -> f64{
-    // x: user position
-    // centre : (c_x, c_y)    
-    
-    let h1 = ((x[1] - centre.1) - ((x[0] - centre.0)).powi(2)).max(0.0);
-    let h2 = (1.0 + (x[0] - centre.0).powi(2)/2.0 - (x[1] - centre.1)).max(0.0);
-    
-    let h = h1*h2;
-    
-    if print {
-        if h > 0.0 {
-            println!("x is inside the obstacle");
-        }
-        else{
-            println!("x is outside the obstacle");
-        }
-    } 
-    
-    return h;
-}
-
-
-
-fn h_nlr_2(x: &[f64], centre: (f64, f64), print: bool) -> f64{
-    // x: user position
-    // centre : (c_x, c_y)    
-    
-    let h1 = ((x[1] - centre.1) - 2.0*(-(x[0] - centre.0)/2.0).sin()).max(0.0);
-    let h2_1 = (3.0*(((x[0] - centre.0)/2.0) - 1.0).sin() - (x[1] - centre.1)).max(0.0);
-    let h2_2 = (x[0] - centre.0 - 1.0).max(0.0);
-    let h2_3 = (8.0 - (x[0] - centre.0)).max(0.0);
-
-        
-    let h = h1*h2_1*h2_2*h2_3;
-    
-    if print {
-        if h > 0.0 {
-            println!("x is inside the obstacle");
-        }
-        else{
-            println!("x is outside the obstacle");
-        }
-    } 
-    
-    return h;
-}
-
-// Points for Predefined object (For Plot)
-fn get_points_predefined_obj(obj_kind: u32, centre: (f64, f64), search_area: ((f64, f64), (f64, f64), f64)) -> Vec<(f64, f64)> {
-    // obj_kind: object kind
-
-    let mut points = Vec::new(); // Output initialisation    
-    let x_range = search_area.0;
-    let y_range = search_area.1;
-    let resolution = search_area.2;
-    
-    let mut x = x_range.0; // Start point
-    let mut y = y_range.0;
-    
-
-    while x <= x_range.1{
-        while y <= y_range.1{
-            let mut h = 0.0;
-            match obj_kind {
-                1 => h = h_nlr_1(&[x,y], centre.clone(), false), 
-                2 => h = h_nlr_2(&[x,y], centre.clone(), false), 
-                _ => h=0.0,
-            }            
-            
-            if h > 0.0 {
-                points.push((x,y));
-            }; 
-            y += resolution;
-        }
-        x += resolution;
-        y = y_range.0;
-    }
-    return points;
-}
-
+use plotlib::style::PointStyle;
 
 
+// Obstacles: (1) Elipsoid, (2) Polyhedral, (3) Nonlinear Obstacles in Sathya et al. 2019
+// Their mathematical formulations are in the `obstacles` crate (`../obstacles/src`)
 fn main(){
-    let elip_A = (5.0, 0.0, 2.0, 1.5);
-    let mut poly_A = Vec::new();
-    poly_A.push(((1.0, 0.0), 1.0));
-    poly_A.push(((-1.0, 0.0), 1.0));
-    poly_A.push(((0.0, 1.0), 1.0));
-    poly_A.push(((0.0, -1.0), 1.0));
+    let elip_a = Ellipse::new((5.0, 0.0), (2.0, 1.5));
+    // Convex polygons can be given by their vertices instead of half-spaces
+    let poly_a = Polyhedron::from_vertices(&[(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)], (0.0, 0.0)).unwrap();
+
+    let poly_b = Polyhedron::new(vec![
+        ((1.0, 2.0), 2.0),
+        ((-2.0, 1.0), 2.0),
+        ((-1.0, -1.0), 2.0),
+        ((1.0, -1.0), 2.0),
+        ((0.0, -1.0), 1.5),
+    ], (0.0, 5.0));
+
+    // Non-convex polygons are decomposed into convex pieces
+    let poly_u = Polygon::new(&[
+        (-1.5, -1.5), (1.5, -1.5), (1.5, 1.5), (0.5, 1.5),
+        (0.5, -0.5), (-0.5, -0.5), (-0.5, 1.5), (-1.5, 1.5),
+    ], (-6.0, 6.0)).unwrap();
+
+    // Shapes can be combined: a room (outer square without the inner one) with a door on the left
+    let square = |centre: (f64, f64), half_width: f64, half_height: f64| Polyhedron::from_vertices(&[
+        (-half_width, -half_height), (half_width, -half_height), (half_width, half_height), (-half_width, half_height),
+    ], centre).unwrap();
+    let room = Difference::new(
+        Box::new(square((8.5, 0.0), 1.2, 1.2)),
+        Box::new(Union::new(vec![Box::new(square((8.5, 0.0), 1.0, 1.0)), Box::new(square((7.3, 0.0), 0.4, 0.4))]).unwrap()),
+    );
 
-    let mut poly_B = Vec::new();
-    poly_B.push(((1.0, 2.0), 2.0));
-    poly_B.push(((-2.0, 1.0), 2.0));
-    poly_B.push(((-1.0, -1.0), 2.0));
-    poly_B.push(((1.0, -1.0), 2.0));
-    poly_B.push(((0.0, -1.0), 1.5));
+    let nlr_1 = Nlr1::new((-5.0, 0.0));
+    let nlr_2 = Nlr2::new((-5.0, -5.0));
+
+    // Any shape can be rotated and translated by a pose (shapes given in their own frame)
+    let elip_b = PosedObstacle::new(Box::new(Ellipse::new((0.0, 0.0), (2.0, 0.8))), Pose::new((5.0, 6.0), std::f64::consts::PI / 6.0));
+    let poly_c = PosedObstacle::new(Box::new(Polyhedron::new(vec![
+        ((1.0, 0.0), 1.0),
+        ((-1.0, 0.0), 1.0),
+        ((0.0, 1.0), 1.0),
+        ((0.0, -1.0), 1.0),
+    ], (0.0, 0.0))), Pose::new((5.0, -6.0), std::f64::consts::PI / 4.0));
+    let nlr_3 = PosedObstacle::new(Box::new(Nlr1::new((0.0, 0.0))), Pose::new((-1.0, -6.0), std::f64::consts::PI / 2.0));
 
 
     let search_area = ((-10.0, 10.0), (-10.0, 10.0), 0.05);
-    let p_elip_A = get_points_elip(elip_A.clone(), search_area.clone());
-    let p_poly_A = get_points_poly(poly_A.clone(), (0.0, 0.0), search_area.clone());
-    let p_poly_B = get_points_poly(poly_B.clone(), (0.0, 5.0), search_area.clone());
+    let p_elip_a = get_points(&elip_a, search_area);
+    let p_poly_a = get_points(&poly_a, search_area);
+    let mut p_poly_b = get_points(&poly_b, search_area);
+    p_poly_b.extend(get_points(&poly_u, search_area));
+    p_poly_b.extend(get_points(&room, search_area));
+
+    let p_nlr_1 = get_points(&nlr_1, search_area);
+    let p_nlr_2 = get_points(&nlr_2, search_area);
 
-    let p_nlr_1 = get_points_predefined_obj(1, (-5.0, 0.0), search_area.clone());
-    let p_nlr_2 = get_points_predefined_obj(2, (-5.0, -5.0), search_area.clone());
+    let mut p_posed = get_points(&elip_b, search_area);
+    p_posed.extend(get_points(&poly_c, search_area));
+    p_posed.extend(get_points(&nlr_3, search_area));
 
     // ======================= (2) Plot ================================
-    let s1: Plot = Plot::new(p_elip_A.clone()).point_style(
+    let s1: Plot = Plot::new(p_elip_a).point_style(
         PointStyle::new()
             // .marker(PointMarker::Square) // setting the marker to be a square
             .size(0.5)
             .colour("#DD3355"),
     ); 
 
-    let s2: Plot = Plot::new(p_poly_A.clone()).point_style(
+    let s2: Plot = Plot::new(p_poly_a).point_style(
         PointStyle::new() // uses the default marker
             .size(0.5)
             .colour("#55dd33"),
     ); 
 
-    let s3: Plot = Plot::new(p_nlr_1.clone()).point_style(
+    let s3: Plot = Plot::new(p_nlr_1).point_style(
         PointStyle::new() // uses the default marker
             .size(0.5)
             .colour("#bb33dd"),
     ); 
 
-    let s4: Plot = Plot::new(p_nlr_2.clone()).point_style(
+    let s4: Plot = Plot::new(p_nlr_2).point_style(
         PointStyle::new() // uses the default marker
             .size(0.5)
             .colour("#35C788"),
     );     
 
-    let s5: Plot = Plot::new(p_poly_B.clone()).point_style(
+    let s5: Plot = Plot::new(p_poly_b).point_style(
         PointStyle::new() // uses the default marker
             .size(0.5)
             .colour("#ddbb33"),
     ); 
 
+    let s6: Plot = Plot::new(p_posed).point_style(
+        PointStyle::new() // uses the default marker
+            .size(0.5)
+            .colour("#3355dd"),
+    ); 
+
     // Plot: The 'view' describes what set of data is drawn
     let v = ContinuousView::new()
         .add(s1)
@@ -253,6 +113,7 @@ fn main(){
         .add(s3)
         .add(s4)
         .add(s5)
+        .add(s6)
 
 
         .x_range(-10., 10.)
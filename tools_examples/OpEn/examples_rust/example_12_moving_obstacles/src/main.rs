@@ -0,0 +1,135 @@
+// Path Planning Example with Moving Obstacles
+// The planner places every obstacle where it will be when each waypoint of the horizon is reached
+
+// For Path Planning (ProblemMaster, main_loop and main_instance are in the `planner` crate)
+use planner::{main_instance, Holonomic, Horizon, Limits};
+// For Obstacles
+use obstacles::{get_points, Footprint, ObstacleSpec, Trajectory};
+// For plot
+use plotlib::page::Page;
+use plotlib::repr::Plot;
+use plotlib::view::ContinuousView;
+use plotlib::style::{PointMarker, PointStyle};
+// For the custom trajectory
+use std::sync::Arc;
+
+
+// Points for Obstacles at time t (For Plot)
+fn get_points_obstacle(obstacle_info: &[ObstacleSpec], t: f64, search_area: ((f64, f64), (f64, f64), f64)) -> Vec<(f64, f64)> {
+    let mut points = Vec::new(); // Output initialisation
+    for spec in obstacle_info {
+        let obstacle = spec.build_moving(&Footprint::point()).unwrap();
+        let (dx, dy) = obstacle.trajectory().displacement(t);
+        let shape_points = get_points(obstacle.shape(), search_area);
+        points.extend(shape_points.iter().map(|&(x, y)| (x + dx, y + dy)));
+    }
+    points
+}
+
+
+// Main function
+fn main(){
+
+    // Problem Instance Parameters (time is counted in steps)
+    let pos_start = &[0.0, 0.0];
+    let pos_goal = &[10.0, 10.0];
+    let pos_obstacle = vec![
+        // Crossing the straight path from the bottom right
+        ObstacleSpec::Moving {
+            shape: Box::new(ObstacleSpec::Circle { centre: (9.0, 1.0), radius: 1.0 }),
+            trajectory: Trajectory::ConstantVelocity((-0.06, 0.06)),
+        },
+        // Going back and forth across the diagonal
+        ObstacleSpec::Moving {
+            shape: Box::new(ObstacleSpec::Ellipse { centre: (1.0, 5.0), radii: (1.0, 0.6) }),
+            trajectory: Trajectory::Waypoints(vec![(0.0, (0.0, 0.0)), (60.0, (4.0, -4.0)), (120.0, (0.0, 0.0))]),
+        },
+        // Circling around the goal
+        ObstacleSpec::Moving {
+            shape: Box::new(ObstacleSpec::Circle { centre: (8.0, 8.0), radius: 0.5 }),
+            trajectory: Trajectory::Custom(Arc::new(|t: f64| (2.0 * (0.05 * t).cos(), 2.0 * (0.05 * t).sin()))),
+        },
+    ];
+    let max_movement = 0.1_f64;
+    let horizon = Horizon::new(5).unwrap(); // Receding horizon of 5 waypoints
+
+    // ==================== (1) Get Points =============================
+    // Generate a path avoding the obstacles
+    let plan = main_instance(pos_start, pos_goal, &pos_obstacle, Holonomic::new(max_movement).unwrap(), &Footprint::point(), horizon, Limits::default()).unwrap();
+    println!("Planning result: {} after {} steps", plan.outcome, plan.path.len() - 1);
+    let path = plan.path;
+
+    // Deepest penetration into an obstacle at the time each position was reached
+    let mut max_penalty = 0.0_f64;
+    for spec in &pos_obstacle {
+        let obstacle = spec.build_moving(&Footprint::point()).unwrap();
+        for (k, &(x, y)) in path.iter().enumerate() {
+            max_penalty = max_penalty.max(obstacle.penalty(k as f64, &[x, y]));
+        }
+    }
+    println!("Maximum obstacle penalty along the path: {:e}", max_penalty);
+
+    // Get the points for the obstacles when the robot is half way
+    let search_area = ((-5.0, 15.0), (-5.0, 15.0), 0.05);
+    let obstacle = get_points_obstacle(&pos_obstacle, (path.len() / 2) as f64, search_area);
+
+
+    // ======================= (2) Plot ================================
+    // Path Plot
+    let data1 = path.clone();
+    let s1: Plot = Plot::new(data1).point_style(
+        PointStyle::new()
+            .marker(PointMarker::Square) // setting the marker to be a square
+            .size(1.0)
+            .colour("#DD3355"),
+    );
+
+    // Obstacle Plot
+    let data2 = obstacle.clone();
+    let s2: Plot = Plot::new(data2).point_style(
+        PointStyle::new() // uses the default marker
+            .size(0.5)
+            .colour("#bb33dd"),
+    );
+
+    // Starting point & Goal point
+    let point_start = vec![(pos_start[0], pos_start[1])];
+    let s3: Plot = Plot::new(point_start).point_style(
+        PointStyle::new() // uses the default marker
+            .colour("#35C788"),
+    ); // and a different colour
+
+    let point_goal = vec![(pos_goal[0], pos_goal[1])];
+    // We can plot multiple data sets in the same view
+    let s4: Plot = Plot::new(point_goal).point_style(
+        PointStyle::new() // uses the default marker
+            .colour("#35C788"),
+    ); // and a different colour
+
+    // The robot when the obstacles are drawn
+    let point_half = vec![path[path.len() / 2]];
+    let s5: Plot = Plot::new(point_half).point_style(
+        PointStyle::new()
+            .size(4.0)
+            .colour("#3355DD"),
+    );
+
+
+    // Plot: The 'view' describes what set of data is drawn
+    let v = ContinuousView::new()
+        .add(s2)
+        .add(s1)
+        .add(s3)
+        .add(s4)
+        .add(s5)
+
+        .x_range(-5., 15.)
+        .y_range(-5., 15.)
+        .x_label("X (m)")
+        .y_label("Y (m)");
+
+    // A page with a single view is then saved to an SVG file
+    Page::single(&v).save("path_result.svg").unwrap();
+
+    println!("Done - Visual Result Generated");
+}
@@ -0,0 +1,144 @@
+// Path Planning Example in 3D
+// The same planner with 3D positions, around a sphere, a box, an ellipsoid and a polytope
+
+// For Path Planning (ProblemMaster, main_loop and main_instance are in the `planner` crate)
+use planner::{main_instance, Holonomic, Horizon, Limits};
+// For Obstacles
+use obstacles::{Footprint, ObstacleSpec3};
+// For plot
+use plotlib::page::Page;
+use plotlib::repr::Plot;
+use plotlib::view::ContinuousView;
+use plotlib::style::{PointMarker, PointStyle};
+
+
+// Points inside the obstacles, projected onto the plane of the axes `(i, j)` (For Plot)
+fn get_points_projected(obstacle_info: &[ObstacleSpec3], axes: (usize, usize), search_area: [(f64, f64); 3], resolution: f64) -> Vec<(f64, f64)> {
+    let mut points = Vec::new(); // Output initialisation
+    let k = 3 - axes.0 - axes.1; // Projected-out axis
+    for spec in obstacle_info {
+        let obstacle = spec.build().unwrap();
+        let bbox = obstacle.bounding_box();
+        let range = |a: usize| (search_area[a].0.max(bbox.ranges[a].0), search_area[a].1.min(bbox.ranges[a].1));
+        let (r_i, r_j, r_k) = (range(axes.0), range(axes.1), range(k));
+        let mut a = r_i.0;
+        while a <= r_i.1 {
+            let mut b = r_j.0;
+            while b <= r_j.1 {
+                // Keep the point if the obstacle is anywhere along the projected-out axis
+                let mut c = r_k.0;
+                while c <= r_k.1 {
+                    let mut x = [0.0; 3];
+                    x[axes.0] = a;
+                    x[axes.1] = b;
+                    x[k] = c;
+                    if obstacle.is_inside(&x) {
+                        points.push((a, b));
+                        break;
+                    }
+                    c += resolution;
+                }
+                b += resolution;
+            }
+            a += resolution;
+        }
+    }
+    points
+}
+
+
+// Path, obstacles, start and goal projected onto the axes `(i, j)`
+fn plot_projection(path: &[Vec<f64>], obstacle_info: &[ObstacleSpec3], pos_start: &[f64], pos_goal: &[f64], axes: (usize, usize), labels: (&str, &str), file: &str) {
+    let search_area = [(-1.0, 11.0), (-3.0, 3.0), (-3.0, 3.0)];
+
+    // Path Plot
+    let data1 = path.iter().map(|x| (x[axes.0], x[axes.1])).collect();
+    let s1: Plot = Plot::new(data1).point_style(
+        PointStyle::new()
+            .marker(PointMarker::Square) // setting the marker to be a square
+            .size(1.0)
+            .colour("#DD3355"),
+    );
+
+    // Obstacle Plot
+    let data2 = get_points_projected(obstacle_info, axes, search_area, 0.05);
+    let s2: Plot = Plot::new(data2).point_style(
+        PointStyle::new() // uses the default marker
+            .size(0.5)
+            .colour("#bb33dd"),
+    );
+
+    // Starting point & Goal point
+    let point_start = vec![(pos_start[axes.0], pos_start[axes.1])];
+    let s3: Plot = Plot::new(point_start).point_style(
+        PointStyle::new() // uses the default marker
+            .colour("#35C788"),
+    ); // and a different colour
+
+    let point_goal = vec![(pos_goal[axes.0], pos_goal[axes.1])];
+    let s4: Plot = Plot::new(point_goal).point_style(
+        PointStyle::new() // uses the default marker
+            .colour("#35C788"),
+    ); // and a different colour
+
+    // Plot: The 'view' describes what set of data is drawn
+    let v = ContinuousView::new()
+        .add(s2)
+        .add(s1)
+        .add(s3)
+        .add(s4)
+
+        .x_range(search_area[axes.0].0, search_area[axes.0].1)
+        .y_range(search_area[axes.1].0, search_area[axes.1].1)
+        .x_label(labels.0)
+        .y_label(labels.1);
+
+    // A page with a single view is then saved to an SVG file
+    Page::single(&v).save(file).unwrap();
+}
+
+
+// Main function
+fn main(){
+
+    // Problem Instance Parameters
+    let pos_start = &[0.0, 0.0, 0.0];
+    let pos_goal = &[10.0, 0.0, 0.0];
+    let pos_obstacle = vec![
+        ObstacleSpec3::Sphere { centre: (2.5, 0.1, 0.0), radius: 1.0 },
+        // A wall to fly around
+        ObstacleSpec3::Cuboid { centre: (5.5, 0.0, 0.2), half_widths: (0.5, 1.5, 1.5) },
+        ObstacleSpec3::Ellipsoid { centre: (8.0, 0.3, -0.2), radii: (0.6, 1.2, 0.8) },
+        // A tetrahedron above the start
+        ObstacleSpec3::Polytope {
+            centre: (1.0, 0.0, 1.2),
+            halfspaces: vec![((0.0, 0.0, -1.0), 0.5), ((1.0, 1.0, 1.0), 1.0), ((-1.0, 1.0, 0.0), 1.0), ((0.0, -1.0, 0.0), 1.0)],
+        },
+    ];
+    let max_movement = 0.2_f64;
+    let footprint = Footprint::circle(0.2); // A ball of radius 0.2 in 3D
+
+    // ==================== (1) Get Points =============================
+    // Generate a path avoding the obstacles
+    let plan = main_instance(pos_start, pos_goal, &pos_obstacle, Holonomic::<3>::new(max_movement).unwrap(), &footprint, Horizon::default(), Limits::default().with_goal_tolerance(1e-3).unwrap()).unwrap();
+    println!("Planning result: {} after {} steps", plan.outcome, plan.states.len() - 1);
+    let path = plan.states;
+
+    // Closest approach to an obstacle (positive: clear of it)
+    let mut min_distance = f64::INFINITY;
+    for spec in &pos_obstacle {
+        let obstacle = spec.build().unwrap();
+        for x in &path {
+            min_distance = min_distance.min(obstacle.signed_distance(x));
+        }
+    }
+    println!("Minimum distance to the obstacles along the path: {:.4}", min_distance);
+
+
+    // ======================= (2) Plot ================================
+    // Top view and side view
+    plot_projection(&path, &pos_obstacle, pos_start, pos_goal, (0, 1), ("X (m)", "Y (m)"), "path_result_xy.svg");
+    plot_projection(&path, &pos_obstacle, pos_start, pos_goal, (0, 2), ("X (m)", "Z (m)"), "path_result_xz.svg");
+
+    println!("Done - Visual Result Generated");
+}
@@ -0,0 +1,96 @@
+// Path Planning Example on an Occupancy-Grid Map
+// The map (map.pgm and map.yaml, as for a map server) becomes a signed distance field,
+// which the planner uses as any other obstacle
+
+// For Path Planning (ProblemMaster, main_loop and main_instance are in the `planner` crate)
+use planner::{main_instance, Holonomic, Horizon, Limits};
+// For Obstacles
+use obstacles::{get_points, Footprint, OccupancyGrid, ObstacleSpec};
+// For plot
+use plotlib::page::Page;
+use plotlib::repr::Plot;
+use plotlib::view::ContinuousView;
+use plotlib::style::{PointMarker, PointStyle};
+
+
+// Main function
+fn main(){
+
+    // Map: walls, two blocks, a pillar and an unobserved (grey) area, which counts as occupied
+    let grid = OccupancyGrid::load(concat!(env!("CARGO_MANIFEST_DIR"), "/map.yaml")).unwrap();
+    println!("Map: {} x {} cells of {} m", grid.width(), grid.height(), grid.resolution());
+
+    // Problem Instance Parameters
+    let pos_start = &[0.0, 0.0];
+    let pos_goal = &[8.0, 8.0];
+    let pos_obstacle = vec![ObstacleSpec::Map { field: grid.distance_field() }];
+    let max_movement = 0.1_f64;
+    let footprint = Footprint::circle(0.2); // Robot radius
+    let horizon = Horizon::default(); // Greedy one-step planner
+
+    // ==================== (1) Get Points =============================
+    // Generate a path avoding the obstacles
+    let plan = main_instance(pos_start, pos_goal, &pos_obstacle, Holonomic::new(max_movement).unwrap(), &footprint, horizon, Limits::default().with_goal_tolerance(1e-3).unwrap()).unwrap();
+    println!("Planning result: {} after {} steps", plan.outcome, plan.path.len() - 1);
+    let path = plan.path;
+
+    // Closest approach to the occupied cells (at least the robot radius if clear)
+    let map = pos_obstacle[0].build().unwrap();
+    let min_distance = path.iter().map(|&(x, y)| map.signed_distance(&[x, y])).fold(f64::INFINITY, f64::min);
+    println!("Minimum distance to the occupied cells along the path: {:.4}", min_distance);
+
+    // Get the points for the occupied cells
+    let search_area = ((-1.0, 11.0), (-1.0, 11.0), 0.05);
+    let obstacle = get_points(map.as_ref(), search_area);
+
+
+    // ======================= (2) Plot ================================
+    // Path Plot
+    let data1 = path.clone();
+    let s1: Plot = Plot::new(data1).point_style(
+        PointStyle::new()
+            .marker(PointMarker::Square) // setting the marker to be a square
+            .size(1.0)
+            .colour("#DD3355"),
+    );
+
+    // Obstacle Plot
+    let data2 = obstacle.clone();
+    let s2: Plot = Plot::new(data2).point_style(
+        PointStyle::new() // uses the default marker
+            .size(0.5)
+            .colour("#bb33dd"),
+    );
+
+    // Starting point & Goal point
+    let point_start = vec![(pos_start[0], pos_start[1])];
+    let s3: Plot = Plot::new(point_start).point_style(
+        PointStyle::new() // uses the default marker
+            .colour("#35C788"),
+    ); // and a different colour
+
+    let point_goal = vec![(pos_goal[0], pos_goal[1])];
+    // We can plot multiple data sets in the same view
+    let s4: Plot = Plot::new(point_goal).point_style(
+        PointStyle::new() // uses the default marker
+            .colour("#35C788"),
+    ); // and a different colour
+
+
+    // Plot: The 'view' describes what set of data is drawn
+    let v = ContinuousView::new()
+        .add(s2)
+        .add(s1)
+        .add(s3)
+        .add(s4)
+
+        .x_range(-1., 11.)
+        .y_range(-1., 11.)
+        .x_label("X (m)")
+        .y_label("Y (m)");
+
+    // A page with a single view is then saved to an SVG file
+    Page::single(&v).save("path_result.svg").unwrap();
+
+    println!("Done - Visual Result Generated");
+}
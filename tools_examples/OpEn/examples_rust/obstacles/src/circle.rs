@@ -0,0 +1,93 @@
+use crate::{BoundingBox, Obstacle};
+
+/// Circular obstacle
+///
+/// `h(x) = max(0, r^2 - |x - c|^2)`, as used in Example 09.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Circle {
+    centre: (f64, f64),
+    radius: f64,
+}
+
+impl Circle {
+    pub fn new(centre: (f64, f64), radius: f64) -> Self {
+        Circle { centre, radius }
+    }
+
+    pub fn centre(&self) -> (f64, f64) {
+        self.centre
+    }
+
+    pub fn radius(&self) -> f64 {
+        self.radius
+    }
+}
+
+impl Obstacle for Circle {
+    fn penalty(&self, x: &[f64]) -> f64 {
+        (self.radius.powi(2) - (x[0] - self.centre.0).powi(2) - (x[1] - self.centre.1).powi(2))
+            .max(0.0)
+    }
+
+    fn gradient(&self, x: &[f64], grad: &mut [f64]) {
+        if self.penalty(x) > 0.0 {
+            grad[0] = -2.0 * (x[0] - self.centre.0);
+            grad[1] = -2.0 * (x[1] - self.centre.1);
+        } else {
+            grad[0] = 0.0;
+            grad[1] = 0.0;
+        }
+    }
+
+    fn signed_distance(&self, x: &[f64]) -> f64 {
+        ((x[0] - self.centre.0).powi(2) + (x[1] - self.centre.1).powi(2)).sqrt() - self.radius
+    }
+
+    fn signed_distance_gradient(&self, x: &[f64], grad: &mut [f64]) {
+        let (dx, dy) = (x[0] - self.centre.0, x[1] - self.centre.1);
+        let norm = (dx * dx + dy * dy).sqrt();
+        if norm > 0.0 {
+            grad[0] = dx / norm;
+            grad[1] = dy / norm;
+        } else {
+            // Any direction leaves the centre at the same rate
+            grad[0] = 1.0;
+            grad[1] = 0.0;
+        }
+    }
+
+    fn bounding_box(&self) -> BoundingBox {
+        BoundingBox::new(
+            (self.centre.0 - self.radius, self.centre.0 + self.radius),
+            (self.centre.1 - self.radius, self.centre.1 + self.radius),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::assert_gradient;
+
+    /// Penalty of Example 09 before the shapes were extracted
+    fn baseline(u: &[f64], x_obs: (f64, f64, f64)) -> f64 {
+        (1.0 * (x_obs.2).powi(2) - (u[0] - x_obs.0).powi(2) - (u[1] - x_obs.1).powi(2)).max(0.0)
+    }
+
+    const POINTS: [[f64; 2]; 5] = [[5.0, 5.0], [5.3, 4.6], [6.2, 5.9], [3.0, 3.0], [5.0, 6.5]];
+
+    #[test]
+    fn penalty_matches_baseline() {
+        let circle = Circle::new((5.0, 5.0), 1.2);
+        for x in POINTS.iter() {
+            let h = baseline(x, (5.0, 5.0, 1.2));
+            assert_eq!(circle.penalty(x), h);
+            assert_eq!(circle.is_inside(x), h > 0.0);
+        }
+    }
+
+    #[test]
+    fn gradient_matches_finite_differences() {
+        assert_gradient(&Circle::new((5.0, 5.0), 1.2), &POINTS);
+    }
+}
@@ -0,0 +1,342 @@
+//! Constructive solid geometry over obstacle shapes
+
+use crate::{BoundingBox, Obstacle, ObstacleError};
+
+/// Union of shapes: `h(x) = sum_i h_i(x)`, `sdf(x) = min_i sdf_i(x)`
+///
+/// The signed distance is exact outside and underestimates the depth inside.
+/// Shapes that only touch leave a seam where the penalty vanishes; let them
+/// overlap instead.
+pub struct Union<const D: usize = 2> {
+    shapes: Vec<Box<dyn Obstacle<D>>>,
+}
+
+impl<const D: usize> Union<D> {
+    /// Fails if `shapes` is empty
+    pub fn new(shapes: Vec<Box<dyn Obstacle<D>>>) -> Result<Self, ObstacleError> {
+        if shapes.is_empty() {
+            return Err(ObstacleError::EmptyCombination);
+        }
+        Ok(Union { shapes })
+    }
+
+    pub fn shapes(&self) -> &[Box<dyn Obstacle<D>>] {
+        &self.shapes
+    }
+}
+
+impl<const D: usize> Obstacle<D> for Union<D> {
+    fn penalty(&self, x: &[f64]) -> f64 {
+        self.shapes.iter().map(|shape| shape.penalty(x)).sum()
+    }
+
+    fn gradient(&self, x: &[f64], grad: &mut [f64]) {
+        grad.fill(0.0);
+        let mut grad_i = [0.0; D];
+        for shape in &self.shapes {
+            shape.gradient(x, &mut grad_i);
+            grad.iter_mut()
+                .zip(grad_i.iter())
+                .for_each(|(g, g_i)| *g += g_i);
+        }
+    }
+
+    fn signed_distance(&self, x: &[f64]) -> f64 {
+        self.shapes
+            .iter()
+            .map(|shape| shape.signed_distance(x))
+            .fold(f64::INFINITY, f64::min)
+    }
+
+    fn signed_distance_gradient(&self, x: &[f64], grad: &mut [f64]) {
+        let i = extreme(&self.shapes, x, |d, best| d < best);
+        self.shapes[i].signed_distance_gradient(x, grad);
+    }
+
+    fn is_inside(&self, x: &[f64]) -> bool {
+        self.shapes.iter().any(|shape| shape.is_inside(x))
+    }
+
+    fn bounding_box(&self) -> BoundingBox<D> {
+        self.shapes
+            .iter()
+            .map(|shape| shape.bounding_box())
+            .reduce(|a, b| a.union(&b))
+            .unwrap()
+    }
+}
+
+/// Intersection of shapes: `h(x) = prod_i h_i(x)`, `sdf(x) = max_i sdf_i(x)`
+///
+/// The signed distance is exact inside and underestimates the distance
+/// outside. The polyhedra and the shapes of Sathya et al. 2019 are built the
+/// same way from their defining half-planes and curves.
+pub struct Intersection<const D: usize = 2> {
+    shapes: Vec<Box<dyn Obstacle<D>>>,
+}
+
+impl<const D: usize> Intersection<D> {
+    /// Fails if `shapes` is empty
+    pub fn new(shapes: Vec<Box<dyn Obstacle<D>>>) -> Result<Self, ObstacleError> {
+        if shapes.is_empty() {
+            return Err(ObstacleError::EmptyCombination);
+        }
+        Ok(Intersection { shapes })
+    }
+
+    pub fn shapes(&self) -> &[Box<dyn Obstacle<D>>] {
+        &self.shapes
+    }
+}
+
+impl<const D: usize> Obstacle<D> for Intersection<D> {
+    fn penalty(&self, x: &[f64]) -> f64 {
+        self.shapes.iter().map(|shape| shape.penalty(x)).product()
+    }
+
+    // Product rule: sum_i (prod_{j != i} h_j) grad h_i
+    fn gradient(&self, x: &[f64], grad: &mut [f64]) {
+        let penalties: Vec<f64> = self.shapes.iter().map(|shape| shape.penalty(x)).collect();
+        grad.fill(0.0);
+        let mut grad_i = [0.0; D];
+        for (i, shape) in self.shapes.iter().enumerate() {
+            let others: f64 = penalties
+                .iter()
+                .enumerate()
+                .filter(|&(j, _)| j != i)
+                .map(|(_, h_j)| h_j)
+                .product();
+            if others != 0.0 {
+                shape.gradient(x, &mut grad_i);
+                grad.iter_mut()
+                    .zip(grad_i.iter())
+                    .for_each(|(g, g_i)| *g += others * g_i);
+            }
+        }
+    }
+
+    fn signed_distance(&self, x: &[f64]) -> f64 {
+        self.shapes
+            .iter()
+            .map(|shape| shape.signed_distance(x))
+            .fold(f64::NEG_INFINITY, f64::max)
+    }
+
+    fn signed_distance_gradient(&self, x: &[f64], grad: &mut [f64]) {
+        let i = extreme(&self.shapes, x, |d, best| d > best);
+        self.shapes[i].signed_distance_gradient(x, grad);
+    }
+
+    fn is_inside(&self, x: &[f64]) -> bool {
+        self.shapes.iter().all(|shape| shape.is_inside(x))
+    }
+
+    fn bounding_box(&self) -> BoundingBox<D> {
+        self.shapes
+            .iter()
+            .map(|shape| shape.bounding_box())
+            .reduce(|a, b| a.intersection(&b))
+            .unwrap()
+    }
+}
+
+/// `shape` without `minus`: `h(x) = h_A(x) max(0, sdf_B(x))`,
+/// `sdf(x) = max(sdf_A(x), -sdf_B(x))`
+///
+/// The factor `max(0, sdf_B)` vanishes on `minus` and grows away from it, so
+/// that the penalty is zero iff `x` is outside `shape` or in `minus`. The
+/// signed distance is exact inside and underestimates the distance outside.
+pub struct Difference<const D: usize = 2> {
+    shape: Box<dyn Obstacle<D>>,
+    minus: Box<dyn Obstacle<D>>,
+}
+
+impl<const D: usize> Difference<D> {
+    pub fn new(shape: Box<dyn Obstacle<D>>, minus: Box<dyn Obstacle<D>>) -> Self {
+        Difference { shape, minus }
+    }
+
+    pub fn shape(&self) -> &dyn Obstacle<D> {
+        self.shape.as_ref()
+    }
+
+    pub fn minus(&self) -> &dyn Obstacle<D> {
+        self.minus.as_ref()
+    }
+}
+
+impl<const D: usize> Obstacle<D> for Difference<D> {
+    fn penalty(&self, x: &[f64]) -> f64 {
+        let h = self.shape.penalty(x);
+        if h > 0.0 {
+            h * self.minus.signed_distance(x).max(0.0)
+        } else {
+            0.0
+        }
+    }
+
+    fn gradient(&self, x: &[f64], grad: &mut [f64]) {
+        let h = self.shape.penalty(x);
+        let d = self.minus.signed_distance(x);
+        if h > 0.0 && d > 0.0 {
+            let mut grad_d = [0.0; D];
+            self.shape.gradient(x, grad);
+            self.minus.signed_distance_gradient(x, &mut grad_d);
+            grad.iter_mut()
+                .zip(grad_d.iter())
+                .for_each(|(g, g_d)| *g = d * *g + h * g_d);
+        } else {
+            grad.fill(0.0);
+        }
+    }
+
+    fn signed_distance(&self, x: &[f64]) -> f64 {
+        self.shape
+            .signed_distance(x)
+            .max(-self.minus.signed_distance(x))
+    }
+
+    fn signed_distance_gradient(&self, x: &[f64], grad: &mut [f64]) {
+        if self.shape.signed_distance(x) >= -self.minus.signed_distance(x) {
+            self.shape.signed_distance_gradient(x, grad);
+        } else {
+            self.minus.signed_distance_gradient(x, grad);
+            grad.iter_mut().for_each(|g| *g = -*g);
+        }
+    }
+
+    fn bounding_box(&self) -> BoundingBox<D> {
+        self.shape.bounding_box()
+    }
+}
+
+/// Index of the shape whose signed distance at `x` is `better` than all the
+/// others (the first one on ties)
+fn extreme<const D: usize>(
+    shapes: &[Box<dyn Obstacle<D>>],
+    x: &[f64],
+    better: impl Fn(f64, f64) -> bool,
+) -> usize {
+    let mut best = (0, shapes[0].signed_distance(x));
+    for (i, shape) in shapes.iter().enumerate().skip(1) {
+        let d = shape.signed_distance(x);
+        if better(d, best.1) {
+            best = (i, d);
+        }
+    }
+    best.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::assert_gradient;
+    use crate::Circle;
+
+    #[test]
+    fn combinations_of_no_shapes_are_rejected() {
+        assert!(matches!(
+            Union::<2>::new(Vec::new()),
+            Err(ObstacleError::EmptyCombination)
+        ));
+        assert!(matches!(
+            Intersection::<2>::new(Vec::new()),
+            Err(ObstacleError::EmptyCombination)
+        ));
+        let circle = || Box::new(Circle::new((0.0, 0.0), 1.0)) as Box<dyn Obstacle>;
+        assert!(Union::new(vec![circle()]).is_ok());
+        assert!(Intersection::new(vec![circle(), circle()]).is_ok());
+    }
+
+    /// Circles of radius 1 around (0, 0) and (1.5, 0), which overlap for
+    /// `0.5 < x < 1`
+    fn circles() -> Vec<Box<dyn Obstacle>> {
+        vec![
+            Box::new(Circle::new((0.0, 0.0), 1.0)),
+            Box::new(Circle::new((1.5, 0.0), 1.0)),
+        ]
+    }
+
+    const ONLY_A: [f64; 2] = [-0.5, 0.0];
+    const ONLY_B: [f64; 2] = [2.0, 0.0];
+    const BOTH: [f64; 2] = [0.75, 0.0];
+    const NEITHER: [f64; 2] = [0.0, 3.0];
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-12,
+            "{} != {}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn union_covers_either_shape() {
+        let union = Union::new(circles()).unwrap();
+        assert_close(union.penalty(&ONLY_A), 0.75);
+        assert_close(union.penalty(&ONLY_B), 0.75);
+        assert_close(union.penalty(&BOTH), 2.0 * 0.4375);
+        assert_eq!(union.penalty(&NEITHER), 0.0);
+        assert!(union.is_inside(&ONLY_A) && union.is_inside(&ONLY_B) && union.is_inside(&BOTH));
+        assert!(!union.is_inside(&NEITHER));
+
+        assert_close(union.signed_distance(&ONLY_A), -0.5);
+        assert_close(union.signed_distance(&BOTH), -0.25);
+        assert_close(union.signed_distance(&NEITHER), 2.0);
+        let mut grad = [0.0; 2];
+        union.signed_distance_gradient(&NEITHER, &mut grad);
+        assert_eq!(grad, [0.0, 1.0]);
+
+        assert_gradient(&union, &[[-0.5, 0.2], [2.0, -0.3], [0.75, 0.1], NEITHER]);
+        let bbox = union.bounding_box();
+        assert_eq!((bbox.x_range(), bbox.y_range()), ((-1.0, 2.5), (-1.0, 1.0)));
+    }
+
+    #[test]
+    fn intersection_covers_the_overlap() {
+        let intersection = Intersection::new(circles()).unwrap();
+        assert_eq!(intersection.penalty(&ONLY_A), 0.0);
+        assert_eq!(intersection.penalty(&ONLY_B), 0.0);
+        assert_close(intersection.penalty(&BOTH), 0.4375 * 0.4375);
+        assert_eq!(intersection.penalty(&NEITHER), 0.0);
+        assert!(intersection.is_inside(&BOTH));
+        assert!(!intersection.is_inside(&ONLY_A) && !intersection.is_inside(&ONLY_B));
+
+        assert_close(intersection.signed_distance(&BOTH), -0.25);
+        assert_close(intersection.signed_distance(&ONLY_A), 1.0);
+        assert!(intersection.signed_distance(&NEITHER) > 0.0);
+
+        assert_gradient(
+            &intersection,
+            &[[0.75, 0.1], [0.7, -0.2], [-0.5, 0.2], NEITHER],
+        );
+        let bbox = intersection.bounding_box();
+        assert_eq!((bbox.x_range(), bbox.y_range()), ((0.5, 1.0), (-1.0, 1.0)));
+    }
+
+    #[test]
+    fn difference_removes_the_second_shape() {
+        let mut shapes = circles();
+        let minus = shapes.pop().unwrap();
+        let difference = Difference::new(shapes.pop().unwrap(), minus);
+        // h_A = 0.75 and sdf_B = 1
+        assert_close(difference.penalty(&ONLY_A), 0.75);
+        assert_eq!(difference.penalty(&BOTH), 0.0);
+        assert_eq!(difference.penalty(&ONLY_B), 0.0);
+        assert_eq!(difference.penalty(&NEITHER), 0.0);
+        assert!(difference.is_inside(&ONLY_A));
+        assert!(!difference.is_inside(&BOTH));
+
+        assert_close(difference.signed_distance(&ONLY_A), -0.5);
+        assert_close(difference.signed_distance(&BOTH), 0.25);
+        assert_close(difference.signed_distance(&ONLY_B), 1.0);
+        let mut grad = [0.0; 2];
+        difference.signed_distance_gradient(&BOTH, &mut grad);
+        assert_eq!(grad, [1.0, 0.0]);
+
+        assert_gradient(&difference, &[[-0.5, 0.2], [0.2, 0.3], [0.75, 0.1], ONLY_B]);
+        let bbox = difference.bounding_box();
+        assert_eq!((bbox.x_range(), bbox.y_range()), ((-1.0, 1.0), (-1.0, 1.0)));
+    }
+}
@@ -0,0 +1,178 @@
+use crate::{BoundingBox, Obstacle};
+
+/// Axis-aligned elliptic obstacle
+///
+/// `h(x) = max(0, 1 - ((x_0 - c_0)/r_0)^2 - ((x_1 - c_1)/r_1)^2)`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Ellipse {
+    centre: (f64, f64),
+    radii: (f64, f64),
+}
+
+impl Ellipse {
+    /// `centre`: (centre_x, centre_y), `radii`: (radius_x, radius_y)
+    pub fn new(centre: (f64, f64), radii: (f64, f64)) -> Self {
+        Ellipse { centre, radii }
+    }
+
+    pub fn centre(&self) -> (f64, f64) {
+        self.centre
+    }
+
+    pub fn radii(&self) -> (f64, f64) {
+        self.radii
+    }
+
+    /// `x` relative to the centre, and the closest point of the boundary
+    /// (relative to the centre)
+    ///
+    /// D. Eberly, "Distance from a Point to an Ellipse, an Ellipsoid, or a
+    /// Hyperellipsoid": bisection on the Lagrange multiplier, in the first
+    /// quadrant with the major axis first.
+    fn closest_point(&self, x: &[f64]) -> ((f64, f64), (f64, f64)) {
+        let p = (x[0] - self.centre.0, x[1] - self.centre.1);
+        let swap = self.radii.0 < self.radii.1;
+        let (e0, e1, y0, y1) = if swap {
+            (self.radii.1, self.radii.0, p.1.abs(), p.0.abs())
+        } else {
+            (self.radii.0, self.radii.1, p.0.abs(), p.1.abs())
+        };
+
+        let (x0, x1) = if y1 > 0.0 {
+            if y0 > 0.0 {
+                let (z0, z1) = (y0 / e0, y1 / e1);
+                let g = z0 * z0 + z1 * z1 - 1.0;
+                if g != 0.0 {
+                    let r0 = (e0 / e1).powi(2);
+                    let s = ellipse_root(r0, z0, z1, g);
+                    (r0 * y0 / (s + r0), y1 / (s + 1.0))
+                } else {
+                    (y0, y1)
+                }
+            } else {
+                (0.0, e1)
+            }
+        } else {
+            let numer0 = e0 * y0;
+            let denom0 = e0 * e0 - e1 * e1;
+            if numer0 < denom0 {
+                let xde0 = numer0 / denom0;
+                (e0 * xde0, e1 * (1.0 - xde0 * xde0).sqrt())
+            } else {
+                (e0, 0.0)
+            }
+        };
+
+        // Back to the original quadrant and axes
+        let (c0, c1) = if swap { (x1, x0) } else { (x0, x1) };
+        (p, (c0.copysign(p.0), c1.copysign(p.1)))
+    }
+}
+
+/// Root `s` of `(r0 z0 / (s + r0))^2 + (z1 / (s + 1))^2 = 1` by bisection
+fn ellipse_root(r0: f64, z0: f64, z1: f64, g: f64) -> f64 {
+    let n0 = r0 * z0;
+    let mut s0 = z1 - 1.0;
+    let mut s1 = if g < 0.0 {
+        0.0
+    } else {
+        (n0 * n0 + z1 * z1).sqrt() - 1.0
+    };
+    let mut s = 0.0;
+    // The interval halves at every iteration, so this reaches machine precision
+    for _ in 0..1100 {
+        s = (s0 + s1) / 2.0;
+        if s == s0 || s == s1 {
+            break;
+        }
+        let g = (n0 / (s + r0)).powi(2) + (z1 / (s + 1.0)).powi(2) - 1.0;
+        if g > 0.0 {
+            s0 = s;
+        } else if g < 0.0 {
+            s1 = s;
+        } else {
+            break;
+        }
+    }
+    s
+}
+
+impl Obstacle for Ellipse {
+    fn penalty(&self, x: &[f64]) -> f64 {
+        (1.0 - ((x[0] - self.centre.0) / self.radii.0).powi(2)
+            - ((x[1] - self.centre.1) / self.radii.1).powi(2))
+        .max(0.0)
+    }
+
+    fn gradient(&self, x: &[f64], grad: &mut [f64]) {
+        if self.penalty(x) > 0.0 {
+            grad[0] = -2.0 * (x[0] - self.centre.0) / self.radii.0.powi(2);
+            grad[1] = -2.0 * (x[1] - self.centre.1) / self.radii.1.powi(2);
+        } else {
+            grad[0] = 0.0;
+            grad[1] = 0.0;
+        }
+    }
+
+    fn signed_distance(&self, x: &[f64]) -> f64 {
+        let (p, closest) = self.closest_point(x);
+        let distance = ((p.0 - closest.0).powi(2) + (p.1 - closest.1).powi(2)).sqrt();
+        if self.is_inside(x) {
+            -distance
+        } else {
+            distance
+        }
+    }
+
+    fn signed_distance_gradient(&self, x: &[f64], grad: &mut [f64]) {
+        // Unit normal at the closest point, pointing out of the ellipse
+        let (p, closest) = self.closest_point(x);
+        let (mut n0, mut n1) = (p.0 - closest.0, p.1 - closest.1);
+        if self.is_inside(x) {
+            n0 = -n0;
+            n1 = -n1;
+        }
+        if n0 == 0.0 && n1 == 0.0 {
+            n0 = closest.0 / self.radii.0.powi(2);
+            n1 = closest.1 / self.radii.1.powi(2);
+        }
+        let norm = (n0 * n0 + n1 * n1).sqrt();
+        grad[0] = n0 / norm;
+        grad[1] = n1 / norm;
+    }
+
+    fn bounding_box(&self) -> BoundingBox {
+        BoundingBox::new(
+            (self.centre.0 - self.radii.0, self.centre.0 + self.radii.0),
+            (self.centre.1 - self.radii.1, self.centre.1 + self.radii.1),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::assert_gradient;
+
+    /// `h_elip` of Examples 10 and 11 before the shapes were extracted
+    fn h_elip(x: &[f64], elip: (f64, f64, f64, f64)) -> f64 {
+        (1.0 - ((x[0] - elip.0) / elip.2).powi(2) - ((x[1] - elip.1) / elip.3).powi(2)).max(0.0)
+    }
+
+    const POINTS: [[f64; 2]; 5] = [[5.0, 5.0], [6.1, 5.2], [4.2, 4.7], [8.0, 5.0], [5.0, 6.5]];
+
+    #[test]
+    fn penalty_matches_baseline() {
+        let ellipse = Ellipse::new((5.0, 5.0), (1.5, 0.8));
+        for x in POINTS.iter() {
+            let h = h_elip(x, (5.0, 5.0, 1.5, 0.8));
+            assert_eq!(ellipse.penalty(x), h);
+            assert_eq!(ellipse.is_inside(x), h > 0.0);
+        }
+    }
+
+    #[test]
+    fn gradient_matches_finite_differences() {
+        assert_gradient(&Ellipse::new((5.0, 5.0), (1.5, 0.8)), &POINTS);
+    }
+}
@@ -0,0 +1,321 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{BoundingBox, Obstacle, ObstacleError, Pose};
+
+/// Number of samples on a circular footprint (see `Swept`)
+const CIRCLE_SAMPLES: usize = 16;
+/// Number of samples on every edge of a polygonal footprint (see `Swept`)
+const EDGE_SAMPLES: usize = 4;
+
+/// Shape of the robot around its reference point
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FootprintShape {
+    Point,
+    Circle(f64),
+    /// Convex polygon, vertices in either orientation
+    Polygon(Vec<(f64, f64)>),
+}
+
+/// Robot footprint and clearance margin
+///
+/// The robot at position `p` occupies `p + F`, where `F` is the shape
+/// enlarged by the clearance. The planner keeps it out of the obstacles by
+/// keeping `p` out of the obstacles inflated by `-F` (Minkowski sum).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Footprint {
+    shape: FootprintShape,
+    #[serde(default)]
+    clearance: f64,
+}
+
+impl Footprint {
+    /// Point robot without clearance (the original planner)
+    pub fn point() -> Self {
+        Footprint {
+            shape: FootprintShape::Point,
+            clearance: 0.0,
+        }
+    }
+
+    pub fn circle(radius: f64) -> Self {
+        Footprint {
+            shape: FootprintShape::Circle(radius),
+            clearance: 0.0,
+        }
+    }
+
+    /// Convex polygon, relative to the reference point of the robot
+    pub fn polygon(vertices: Vec<(f64, f64)>) -> Self {
+        Footprint {
+            shape: FootprintShape::Polygon(vertices),
+            clearance: 0.0,
+        }
+    }
+
+    /// Extra distance kept from every obstacle
+    pub fn with_clearance(mut self, clearance: f64) -> Self {
+        self.clearance = clearance;
+        self
+    }
+
+    pub fn shape(&self) -> &FootprintShape {
+        &self.shape
+    }
+
+    pub fn clearance(&self) -> f64 {
+        self.clearance
+    }
+
+    /// Whether the footprint is a point without clearance
+    pub fn is_point(&self) -> bool {
+        self.shape == FootprintShape::Point && self.clearance == 0.0
+    }
+
+    /// Checks the radius, the clearance and the polygon
+    pub fn validate(&self) -> Result<(), ObstacleError> {
+        if !self.clearance.is_finite() {
+            return Err(ObstacleError::NotFinite);
+        }
+        if self.clearance < 0.0 {
+            return Err(ObstacleError::NegativeClearance(self.clearance));
+        }
+        match &self.shape {
+            FootprintShape::Point => Ok(()),
+            FootprintShape::Circle(radius) => {
+                if !radius.is_finite() {
+                    Err(ObstacleError::NotFinite)
+                } else if *radius > 0.0 {
+                    Ok(())
+                } else {
+                    Err(ObstacleError::NonPositiveRadius(*radius))
+                }
+            }
+            FootprintShape::Polygon(vertices) => {
+                if vertices
+                    .iter()
+                    .any(|v| !v.0.is_finite() || !v.1.is_finite())
+                {
+                    return Err(ObstacleError::NotFinite);
+                }
+                if is_convex(vertices) {
+                    Ok(())
+                } else {
+                    Err(ObstacleError::InvalidFootprint)
+                }
+            }
+        }
+    }
+
+    /// Largest distance from the reference point to the footprint, including
+    /// the clearance
+    pub fn radius(&self) -> f64 {
+        let radius = match &self.shape {
+            FootprintShape::Point => 0.0,
+            FootprintShape::Circle(radius) => *radius,
+            FootprintShape::Polygon(vertices) => vertices
+                .iter()
+                .map(|v| (v.0 * v.0 + v.1 * v.1).sqrt())
+                .fold(0.0, f64::max),
+        };
+        radius + self.clearance
+    }
+
+    /// Vertices of the polygonal part of the footprint (the reference point
+    /// for `Point` and `Circle`)
+    pub(crate) fn vertices(&self) -> Vec<(f64, f64)> {
+        match &self.shape {
+            FootprintShape::Polygon(vertices) => vertices.clone(),
+            _ => vec![(0.0, 0.0)],
+        }
+    }
+
+    /// Radius of the disc that is added to `vertices` (circle and clearance)
+    pub(crate) fn disc_radius(&self) -> f64 {
+        match &self.shape {
+            FootprintShape::Circle(radius) => radius + self.clearance,
+            _ => self.clearance,
+        }
+    }
+
+    /// The footprint rotated by `angle` about the reference point
+    pub(crate) fn rotated(&self, angle: f64) -> Footprint {
+        let shape = match &self.shape {
+            FootprintShape::Polygon(vertices) => {
+                let pose = Pose::new((0.0, 0.0), angle);
+                FootprintShape::Polygon(vertices.iter().map(|&v| pose.rotate(v)).collect())
+            }
+            shape => shape.clone(),
+        };
+        Footprint {
+            shape,
+            clearance: self.clearance,
+        }
+    }
+
+    /// Points on the boundary of the footprint, and the reference point
+    fn samples(&self) -> Vec<(f64, f64)> {
+        let mut samples = vec![(0.0, 0.0)];
+        let circle = |centre: (f64, f64), radius: f64, samples: &mut Vec<(f64, f64)>| {
+            for k in 0..CIRCLE_SAMPLES {
+                let angle = 2.0 * std::f64::consts::PI * k as f64 / CIRCLE_SAMPLES as f64;
+                samples.push((
+                    centre.0 + radius * angle.cos(),
+                    centre.1 + radius * angle.sin(),
+                ));
+            }
+        };
+        match &self.shape {
+            FootprintShape::Point | FootprintShape::Circle(_) => {
+                if self.disc_radius() > 0.0 {
+                    circle((0.0, 0.0), self.disc_radius(), &mut samples);
+                }
+            }
+            FootprintShape::Polygon(vertices) => {
+                for (k, &v) in vertices.iter().enumerate() {
+                    let w = vertices[(k + 1) % vertices.len()];
+                    for j in 0..EDGE_SAMPLES {
+                        let s = j as f64 / EDGE_SAMPLES as f64;
+                        samples.push((v.0 + s * (w.0 - v.0), v.1 + s * (w.1 - v.1)));
+                    }
+                    if self.clearance > 0.0 {
+                        circle(v, self.clearance, &mut samples);
+                    }
+                }
+            }
+        }
+        samples
+    }
+}
+
+/// The original point robot
+impl Default for Footprint {
+    fn default() -> Self {
+        Footprint::point()
+    }
+}
+
+/// Whether the polygon is simple (so not a star such as a pentagram), has a
+/// nonzero area and no reflex vertex
+fn is_convex(vertices: &[(f64, f64)]) -> bool {
+    let n = vertices.len();
+    if n < 3 {
+        return false;
+    }
+    let edge = |k: usize| {
+        let (a, b) = (vertices[k], vertices[(k + 1) % n]);
+        (b.0 - a.0, b.1 - a.1)
+    };
+    // Signed turning angle from edge k to edge k + 1
+    let turn = |k: usize| {
+        let (e, f) = (edge(k), edge((k + 1) % n));
+        (e.0 * f.1 - e.1 * f.0).atan2(e.0 * f.0 + e.1 * f.1)
+    };
+    let turns: Vec<f64> = (0..n).map(turn).collect();
+    let all_left = turns.iter().all(|&t| t >= 0.0);
+    let all_right = turns.iter().all(|&t| t <= 0.0);
+    // A star turns around more than once
+    let winding = turns.iter().sum::<f64>() / (2.0 * std::f64::consts::PI);
+    let area: f64 = (0..n)
+        .map(|k| {
+            let (a, b) = (vertices[k], vertices[(k + 1) % n]);
+            a.0 * b.1 - a.1 * b.0
+        })
+        .sum();
+    (all_left || all_right) && (winding.abs() - 1.0).abs() < 1e-9 && area != 0.0
+}
+
+/// Obstacle for a robot with a footprint, from the penalties at sample
+/// points of the footprint: `h_F(p) = sum_j h(p + v_j)`
+///
+/// Used for the shapes without a closed-form Minkowski sum. Thin parts of the
+/// obstacle may pass between the samples.
+pub(crate) struct Swept {
+    shape: Box<dyn Obstacle>,
+    samples: Vec<(f64, f64)>,
+    radius: f64,
+}
+
+impl Swept {
+    pub(crate) fn new(shape: Box<dyn Obstacle>, footprint: &Footprint) -> Self {
+        Swept {
+            shape,
+            samples: footprint.samples(),
+            radius: footprint.radius(),
+        }
+    }
+}
+
+impl Obstacle for Swept {
+    fn penalty(&self, x: &[f64]) -> f64 {
+        self.samples
+            .iter()
+            .map(|v| self.shape.penalty(&[x[0] + v.0, x[1] + v.1]))
+            .sum()
+    }
+
+    fn gradient(&self, x: &[f64], grad: &mut [f64]) {
+        grad[0] = 0.0;
+        grad[1] = 0.0;
+        let mut grad_j = [0.0; 2];
+        for v in &self.samples {
+            self.shape.gradient(&[x[0] + v.0, x[1] + v.1], &mut grad_j);
+            grad[0] += grad_j[0];
+            grad[1] += grad_j[1];
+        }
+    }
+
+    // Smallest signed distance over the samples
+    fn signed_distance(&self, x: &[f64]) -> f64 {
+        self.samples
+            .iter()
+            .map(|v| self.shape.signed_distance(&[x[0] + v.0, x[1] + v.1]))
+            .fold(f64::INFINITY, f64::min)
+    }
+
+    fn signed_distance_gradient(&self, x: &[f64], grad: &mut [f64]) {
+        let mut closest = (f64::INFINITY, (0.0, 0.0));
+        for v in &self.samples {
+            let d = self.shape.signed_distance(&[x[0] + v.0, x[1] + v.1]);
+            if d < closest.0 {
+                closest = (d, *v);
+            }
+        }
+        let v = closest.1;
+        self.shape
+            .signed_distance_gradient(&[x[0] + v.0, x[1] + v.1], grad);
+    }
+
+    fn bounding_box(&self) -> BoundingBox {
+        self.shape.bounding_box().inflate(self.radius)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::PI;
+
+    #[test]
+    fn polygon_footprints_must_be_convex() {
+        let square = vec![(-0.3, -0.2), (0.3, -0.2), (0.3, 0.2), (-0.3, 0.2)];
+        assert!(Footprint::polygon(square.clone()).validate().is_ok());
+        let clockwise = square.into_iter().rev().collect();
+        assert!(Footprint::polygon(clockwise).validate().is_ok());
+
+        // Reflex vertex
+        let arrow = vec![(0.0, 0.0), (1.0, -1.0), (0.2, 0.0), (1.0, 1.0)];
+        assert!(Footprint::polygon(arrow).validate().is_err());
+        // No area
+        let segment = vec![(0.0, 0.0), (1.0, 0.0), (2.0, 0.0)];
+        assert!(Footprint::polygon(segment).validate().is_err());
+        // Pentagram: every turn has the same sign, but the edges cross
+        let pentagram = (0..5)
+            .map(|k| {
+                let angle = 2.0 * PI * (2 * k) as f64 / 5.0;
+                (0.5 * angle.cos(), 0.5 * angle.sin())
+            })
+            .collect();
+        assert!(Footprint::polygon(pentagram).validate().is_err());
+    }
+}
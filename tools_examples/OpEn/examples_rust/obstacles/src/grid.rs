@@ -0,0 +1,757 @@
+//! Occupancy-grid maps (PGM image and YAML metadata, as used by the common
+//! map servers) and their signed distance fields
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use serde::Deserialize;
+
+use crate::{BoundingBox, Obstacle, Pose};
+
+/// Stand-in for an infinite squared distance in the distance transform
+const FAR: f64 = 1e20;
+
+/// State of a grid cell
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cell {
+    Free,
+    Occupied,
+    /// Neither free nor occupied (e.g., not yet observed)
+    Unknown,
+}
+
+/// Metadata of a map, as in the YAML file of a map server
+///
+/// ```yaml
+/// image: map.pgm
+/// resolution: 0.05
+/// origin: [-10.0, -10.0, 0.0]
+/// negate: 0
+/// occupied_thresh: 0.65
+/// free_thresh: 0.196
+/// ```
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct MapMetadata {
+    /// Image of the map, relative to the YAML file
+    pub image: PathBuf,
+    /// Side of a cell (m)
+    pub resolution: f64,
+    /// Pose `(x, y, yaw)` of the lower-left corner of the map
+    pub origin: [f64; 3],
+    /// Whether white (rather than black) is occupied (0 or 1)
+    #[serde(default)]
+    pub negate: u8,
+    /// Occupied above this probability, `(255 - value) / 255` unless negated
+    #[serde(default = "default_occupied_thresh")]
+    pub occupied_thresh: f64,
+    /// Free below this probability
+    #[serde(default = "default_free_thresh")]
+    pub free_thresh: f64,
+}
+
+fn default_occupied_thresh() -> f64 {
+    0.65
+}
+
+fn default_free_thresh() -> f64 {
+    0.196
+}
+
+/// Reasons for failing to load a map
+#[derive(Debug)]
+pub enum MapError {
+    Io(io::Error),
+    Yaml(serde_yaml::Error),
+    /// The image is not a valid PGM file
+    InvalidPgm(String),
+    /// A resolution, origin or threshold that is not usable, or a number
+    /// of cells that does not match the size of the grid
+    InvalidMap(String),
+}
+
+impl fmt::Display for MapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MapError::Io(e) => write!(f, "cannot read the map: {}", e),
+            MapError::Yaml(e) => write!(f, "invalid map metadata: {}", e),
+            MapError::InvalidPgm(reason) => write!(f, "invalid PGM image: {}", reason),
+            MapError::InvalidMap(reason) => write!(f, "invalid map: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for MapError {}
+
+impl From<io::Error> for MapError {
+    fn from(e: io::Error) -> Self {
+        MapError::Io(e)
+    }
+}
+
+impl From<serde_yaml::Error> for MapError {
+    fn from(e: serde_yaml::Error) -> Self {
+        MapError::Yaml(e)
+    }
+}
+
+/// Occupancy grid of `width x height` square cells
+///
+/// Cell `(i, j)` covers `[i r, (i + 1) r] x [j r, (j + 1) r]` in the frame of
+/// the map, whose pose in the world is `origin`; the cells are stored row by
+/// row from `j = 0` (the bottom row of the image).
+#[derive(Debug, Clone, PartialEq)]
+pub struct OccupancyGrid {
+    width: usize,
+    height: usize,
+    resolution: f64,
+    origin: Pose,
+    cells: Vec<Cell>,
+}
+
+impl OccupancyGrid {
+    pub fn new(
+        width: usize,
+        height: usize,
+        resolution: f64,
+        origin: Pose,
+        cells: Vec<Cell>,
+    ) -> Result<Self, MapError> {
+        if width == 0 || height == 0 || cells.len() != width * height {
+            return Err(MapError::InvalidMap(format!(
+                "{} cells for a {} x {} grid",
+                cells.len(),
+                width,
+                height
+            )));
+        }
+        if !(resolution.is_finite() && resolution > 0.0) {
+            return Err(MapError::InvalidMap(format!(
+                "resolution must be positive (got {})",
+                resolution
+            )));
+        }
+        let (x, y) = origin.position;
+        if ![x, y, origin.angle].iter().all(|v| v.is_finite()) {
+            return Err(MapError::InvalidMap("origin must be finite".to_string()));
+        }
+        Ok(OccupancyGrid {
+            width,
+            height,
+            resolution,
+            origin,
+            cells,
+        })
+    }
+
+    /// Reads the YAML metadata at `path` and the image it refers to
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, MapError> {
+        let path = path.as_ref();
+        let metadata: MapMetadata = serde_yaml::from_str(&fs::read_to_string(path)?)?;
+        let image = path
+            .parent()
+            .unwrap_or_else(|| Path::new(""))
+            .join(&metadata.image);
+        OccupancyGrid::from_pgm(&fs::read(image)?, &metadata)
+    }
+
+    /// Grid of a PGM image (binary `P5` or plain `P2`), classified with the
+    /// thresholds of `metadata` (whose `image` is ignored)
+    pub fn from_pgm(pgm: &[u8], metadata: &MapMetadata) -> Result<Self, MapError> {
+        let (occupied, free) = (metadata.occupied_thresh, metadata.free_thresh);
+        if !(0.0..=1.0).contains(&free) || !(0.0..=1.0).contains(&occupied) || free > occupied {
+            return Err(MapError::InvalidMap(format!(
+                "thresholds must satisfy 0 <= free ({}) <= occupied ({}) <= 1",
+                free, occupied
+            )));
+        }
+        let (width, height, pixels) = read_pgm(pgm)?;
+        let mut cells = Vec::with_capacity(width * height);
+        // The first row of the image is the top of the map
+        for row in pixels.chunks(width).rev() {
+            cells.extend(row.iter().map(|&value| {
+                let p = if metadata.negate != 0 {
+                    value
+                } else {
+                    1.0 - value
+                };
+                if p > occupied {
+                    Cell::Occupied
+                } else if p < free {
+                    Cell::Free
+                } else {
+                    Cell::Unknown
+                }
+            }));
+        }
+        let [x, y, yaw] = metadata.origin;
+        OccupancyGrid::new(
+            width,
+            height,
+            metadata.resolution,
+            Pose::new((x, y), yaw),
+            cells,
+        )
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn resolution(&self) -> f64 {
+        self.resolution
+    }
+
+    pub fn origin(&self) -> Pose {
+        self.origin
+    }
+
+    /// Cell in column `i` and row `j` (from the bottom)
+    pub fn cell(&self, i: usize, j: usize) -> Cell {
+        self.cells[j * self.width + i]
+    }
+
+    /// Signed distance field of the occupied and unknown cells
+    ///
+    /// Euclidean distance transform (Felzenszwalb and Huttenlocher 2012) of
+    /// the cell centres: a free cell is `d - r / 2` from the obstacles, where
+    /// `d` is the distance to the closest occupied centre, and an occupied
+    /// one `-(d - r / 2)` with `d` to the closest free centre. This is exact
+    /// across the faces of the cells and within half a cell elsewhere.
+    pub fn distance_field(&self) -> DistanceField {
+        let blocked: Vec<bool> = self.cells.iter().map(|&c| c != Cell::Free).collect();
+        let free: Vec<bool> = blocked.iter().map(|&b| !b).collect();
+        let to_blocked = distance_transform(&blocked, self.width, self.height);
+        let to_free = distance_transform(&free, self.width, self.height);
+        // Without any blocked (free) cell, the distance is large but finite
+        let cap = (self.width + self.height) as f64;
+        let values = blocked
+            .iter()
+            .zip(to_blocked.iter().zip(to_free.iter()))
+            .map(|(&b, (&d_blocked, &d_free))| {
+                let d = if b {
+                    -(d_free.min(cap) - 0.5)
+                } else {
+                    d_blocked.min(cap) - 0.5
+                };
+                d * self.resolution
+            })
+            .collect();
+        DistanceField {
+            width: self.width,
+            height: self.height,
+            resolution: self.resolution,
+            origin: self.origin,
+            values: Arc::new(values),
+        }
+    }
+}
+
+/// `(width, height, pixels)` of a PGM image, with the pixels scaled to
+/// `[0, 1]` and stored row by row from the top
+fn read_pgm(pgm: &[u8]) -> Result<(usize, usize, Vec<f64>), MapError> {
+    let invalid = |reason: &str| MapError::InvalidPgm(reason.to_string());
+    // Header: magic number, width, height and maximum value, separated by
+    // whitespace and comments
+    let mut pos = 0;
+    let next_token = |pos: &mut usize| -> Option<String> {
+        loop {
+            while *pos < pgm.len() && pgm[*pos].is_ascii_whitespace() {
+                *pos += 1;
+            }
+            if *pos < pgm.len() && pgm[*pos] == b'#' {
+                while *pos < pgm.len() && pgm[*pos] != b'\n' {
+                    *pos += 1;
+                }
+            } else {
+                break;
+            }
+        }
+        let start = *pos;
+        while *pos < pgm.len() && !pgm[*pos].is_ascii_whitespace() {
+            *pos += 1;
+        }
+        (*pos > start).then(|| String::from_utf8_lossy(&pgm[start..*pos]).into_owned())
+    };
+    let magic = next_token(&mut pos).ok_or_else(|| invalid("empty file"))?;
+    let number = |pos: &mut usize, name: &str| -> Result<usize, MapError> {
+        next_token(pos)
+            .and_then(|token| token.parse().ok())
+            .ok_or_else(|| invalid(&format!("missing {}", name)))
+    };
+    let width = number(&mut pos, "width")?;
+    let height = number(&mut pos, "height")?;
+    let max_value = number(&mut pos, "maximum value")?;
+    if width == 0 || height == 0 || max_value == 0 || max_value > 65535 {
+        return Err(invalid("zero size or maximum value out of range"));
+    }
+    let n = width * height;
+    let raw: Vec<usize> = match magic.as_str() {
+        "P5" => {
+            // A single whitespace character separates the header from the data
+            let data = &pgm[(pos + 1).min(pgm.len())..];
+            if max_value < 256 {
+                data.iter().take(n).map(|&b| b as usize).collect()
+            } else {
+                data.chunks_exact(2)
+                    .take(n)
+                    .map(|b| ((b[0] as usize) << 8) | b[1] as usize)
+                    .collect()
+            }
+        }
+        "P2" => (0..n)
+            .map_while(|_| next_token(&mut pos).and_then(|token| token.parse().ok()))
+            .collect(),
+        _ => return Err(invalid("not a P2 or P5 image")),
+    };
+    if raw.len() < n {
+        return Err(invalid("fewer pixels than width x height"));
+    }
+    let pixels = raw
+        .iter()
+        .map(|&v| v.min(max_value) as f64 / max_value as f64)
+        .collect();
+    Ok((width, height, pixels))
+}
+
+/// Distance (in cells) from the centre of every cell to the closest centre
+/// of a `feature` cell, `sqrt(FAR)` or more if there is none
+fn distance_transform(feature: &[bool], width: usize, height: usize) -> Vec<f64> {
+    let mut squared: Vec<f64> = feature.iter().map(|&f| if f { 0.0 } else { FAR }).collect();
+    let n = width.max(height);
+    let (mut f, mut d) = (vec![0.0; n], vec![0.0; n]);
+    let (mut v, mut z) = (vec![0; n], vec![0.0; n + 1]);
+    for i in 0..width {
+        for j in 0..height {
+            f[j] = squared[j * width + i];
+        }
+        distance_transform_1d(&f[..height], &mut d[..height], &mut v, &mut z);
+        for j in 0..height {
+            squared[j * width + i] = d[j];
+        }
+    }
+    for row in squared.chunks_mut(width) {
+        f[..width].copy_from_slice(row);
+        distance_transform_1d(&f[..width], &mut d[..width], &mut v, &mut z);
+        row.copy_from_slice(&d[..width]);
+    }
+    squared.iter().map(|s| s.sqrt()).collect()
+}
+
+/// `d[q] = min_p (q - p)^2 + f[p]`, by the lower envelope of the parabolas
+fn distance_transform_1d(f: &[f64], d: &mut [f64], v: &mut [usize], z: &mut [f64]) {
+    let n = f.len();
+    let intersection = |q: usize, p: usize| {
+        let (q_f, p_f) = (q as f64, p as f64);
+        ((f[q] + q_f * q_f) - (f[p] + p_f * p_f)) / (2.0 * (q_f - p_f))
+    };
+    let mut k = 0;
+    v[0] = 0;
+    z[0] = f64::NEG_INFINITY;
+    z[1] = f64::INFINITY;
+    for q in 1..n {
+        let mut s = intersection(q, v[k]);
+        while s <= z[k] {
+            k -= 1;
+            s = intersection(q, v[k]);
+        }
+        k += 1;
+        v[k] = q;
+        z[k] = s;
+        z[k + 1] = f64::INFINITY;
+    }
+    k = 0;
+    for (q, d_q) in d.iter_mut().enumerate() {
+        while z[k + 1] < q as f64 {
+            k += 1;
+        }
+        let offset = q as f64 - v[k] as f64;
+        *d_q = offset * offset + f[v[k]];
+    }
+}
+
+/// Signed distance field of an occupancy grid, as an obstacle
+///
+/// The signed distance is interpolated bilinearly between the cell centres,
+/// so it is continuous with a piecewise-continuous gradient. Outside the
+/// rectangle of the centres, the distance to that rectangle is added to the
+/// value at its closest point, so that the field grows away from the map.
+/// The penalty is `h(x) = max(0, -sdf(x))`.
+/// Cheap to clone (the values are shared).
+#[derive(Clone, PartialEq)]
+pub struct DistanceField {
+    width: usize,
+    height: usize,
+    resolution: f64,
+    origin: Pose,
+    values: Arc<Vec<f64>>, // Signed distance at the cell centres, row by row
+}
+
+impl DistanceField {
+    /// The field of the obstacles inflated by `margin`, i.e., shifted down
+    /// by `margin` (exact away from the cell corners)
+    pub fn inflate(&self, margin: f64) -> DistanceField {
+        DistanceField {
+            values: Arc::new(self.values.iter().map(|v| v - margin).collect()),
+            ..self.clone()
+        }
+    }
+
+    /// Signed distance at the centre of cell `(i, j)`
+    pub fn value(&self, i: usize, j: usize) -> f64 {
+        self.values[j * self.width + i]
+    }
+
+    /// Signed distance at `x` and its gradient, in the frame of the map
+    fn interpolate(&self, x: (f64, f64)) -> (f64, (f64, f64)) {
+        // Continuous index of the point among the cell centres, and the
+        // nearest point of the rectangle of the centres
+        let index = |u: f64, cells: usize| {
+            let g = u / self.resolution - 0.5;
+            let clamped = g.max(0.0).min((cells - 1) as f64);
+            let i0 = (clamped.floor() as usize).min(cells.saturating_sub(2));
+            let i1 = (i0 + 1).min(cells - 1);
+            (i0, i1, clamped - i0 as f64, (g - clamped) * self.resolution)
+        };
+        let (i0, i1, s, outside_x) = index(x.0, self.width);
+        let (j0, j1, t, outside_y) = index(x.1, self.height);
+        let (v00, v10) = (self.value(i0, j0), self.value(i1, j0));
+        let (v01, v11) = (self.value(i0, j1), self.value(i1, j1));
+        let value = (1.0 - t) * ((1.0 - s) * v00 + s * v10) + t * ((1.0 - s) * v01 + s * v11);
+        let mut grad = (
+            ((1.0 - t) * (v10 - v00) + t * (v11 - v01)) / self.resolution,
+            ((1.0 - s) * (v01 - v00) + s * (v11 - v10)) / self.resolution,
+        );
+
+        let outside = (outside_x * outside_x + outside_y * outside_y).sqrt();
+        if outside > 0.0 {
+            // Only the distance to the map changes along the clamped axes
+            if outside_x != 0.0 {
+                grad.0 = outside_x / outside;
+            }
+            if outside_y != 0.0 {
+                grad.1 = outside_y / outside;
+            }
+        }
+        (value + outside, grad)
+    }
+}
+
+impl fmt::Debug for DistanceField {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DistanceField")
+            .field("width", &self.width)
+            .field("height", &self.height)
+            .field("resolution", &self.resolution)
+            .field("origin", &self.origin)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Obstacle for DistanceField {
+    fn penalty(&self, x: &[f64]) -> f64 {
+        (-self.signed_distance(x)).max(0.0)
+    }
+
+    fn gradient(&self, x: &[f64], grad: &mut [f64]) {
+        if self.signed_distance(x) < 0.0 {
+            self.signed_distance_gradient(x, grad);
+            grad.iter_mut().for_each(|g| *g = -*g);
+        } else {
+            grad.fill(0.0);
+        }
+    }
+
+    fn signed_distance(&self, x: &[f64]) -> f64 {
+        self.interpolate(self.origin.apply_inverse((x[0], x[1]))).0
+    }
+
+    fn signed_distance_gradient(&self, x: &[f64], grad: &mut [f64]) {
+        let (_, g) = self.interpolate(self.origin.apply_inverse((x[0], x[1])));
+        let g = self.origin.rotate(g);
+        grad[0] = g.0;
+        grad[1] = g.1;
+    }
+
+    // The map, whose rotated corners are enclosed, plus the depth of the
+    // obstacles (which may extend beyond the map)
+    fn bounding_box(&self) -> BoundingBox {
+        let depth = self.values.iter().fold(0.0, |d: f64, &v| d.max(-v));
+        let (w, h) = (
+            self.width as f64 * self.resolution + depth,
+            self.height as f64 * self.resolution + depth,
+        );
+        [(-depth, -depth), (w, -depth), (-depth, h), (w, h)]
+            .iter()
+            .map(|&corner| {
+                let (x, y) = self.origin.apply(corner);
+                BoundingBox::new((x, x), (y, y))
+            })
+            .reduce(|a, b| a.union(&b))
+            .unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::assert_gradient;
+
+    fn metadata() -> MapMetadata {
+        MapMetadata {
+            image: PathBuf::from("map.pgm"),
+            resolution: 0.5,
+            origin: [0.0, 0.0, 0.0],
+            negate: 0,
+            occupied_thresh: default_occupied_thresh(),
+            free_thresh: default_free_thresh(),
+        }
+    }
+
+    fn invalid_pgm(pgm: &[u8]) -> String {
+        match read_pgm(pgm) {
+            Err(MapError::InvalidPgm(reason)) => reason,
+            other => panic!("not an invalid PGM: {:?}", other),
+        }
+    }
+
+    /// Grid of the rows of `map` from the top, `#` occupied and `.` free
+    fn grid(map: &[&str], resolution: f64, origin: Pose) -> OccupancyGrid {
+        let cells = map
+            .iter()
+            .rev()
+            .flat_map(|row| {
+                row.chars().map(|c| match c {
+                    '#' => Cell::Occupied,
+                    _ => Cell::Free,
+                })
+            })
+            .collect();
+        OccupancyGrid::new(map[0].len(), map.len(), resolution, origin, cells).unwrap()
+    }
+
+    #[test]
+    fn plain_pgm_with_comments() {
+        let pgm =
+            b"P2\n# A comment\n3 2 # width and height\n# Another one\n255\n0 255 128\n255 255 0\n";
+        let (width, height, pixels) = read_pgm(pgm).unwrap();
+        assert_eq!((width, height), (3, 2));
+        assert_eq!(pixels, vec![0.0, 1.0, 128.0 / 255.0, 1.0, 1.0, 0.0]);
+
+        // Black is occupied, white free and the grey in between unknown; the
+        // last row of the image is the bottom of the map
+        let grid = OccupancyGrid::from_pgm(pgm, &metadata()).unwrap();
+        assert_eq!((grid.width(), grid.height()), (3, 2));
+        assert_eq!(
+            [grid.cell(0, 0), grid.cell(1, 0), grid.cell(2, 0)],
+            [Cell::Free, Cell::Free, Cell::Occupied]
+        );
+        assert_eq!(
+            [grid.cell(0, 1), grid.cell(1, 1), grid.cell(2, 1)],
+            [Cell::Occupied, Cell::Free, Cell::Unknown]
+        );
+    }
+
+    #[test]
+    fn binary_pgm_with_any_maximum_value() {
+        let pgm = [b"P5\n# Binary\n2 2\n15\n".as_slice(), &[0, 15, 6, 20]].concat();
+        let (_, _, pixels) = read_pgm(&pgm).unwrap();
+        // Values above the maximum are clipped
+        assert_eq!(pixels, vec![0.0, 1.0, 0.4, 1.0]);
+
+        // Two bytes per pixel, most significant first
+        let pgm = [b"P5 2 1 1000\n".as_slice(), &[0x03, 0xe8, 0x01, 0xf4]].concat();
+        assert_eq!(read_pgm(&pgm).unwrap(), (2, 1, vec![1.0, 0.5]));
+    }
+
+    #[test]
+    fn thresholds_and_negation_classify_the_cells() {
+        let pgm = b"P2 4 1 100 0 30 60 100";
+        let cells = |metadata: &MapMetadata| {
+            let grid = OccupancyGrid::from_pgm(pgm, metadata).unwrap();
+            (0..4).map(|i| grid.cell(i, 0)).collect::<Vec<_>>()
+        };
+        use Cell::{Free, Occupied, Unknown};
+        // Probabilities 1, 0.7, 0.4 and 0
+        assert_eq!(cells(&metadata()), [Occupied, Occupied, Unknown, Free]);
+        let strict = MapMetadata {
+            occupied_thresh: 0.8,
+            free_thresh: 0.5,
+            ..metadata()
+        };
+        assert_eq!(cells(&strict), [Occupied, Unknown, Free, Free]);
+        let negated = MapMetadata {
+            negate: 1,
+            ..metadata()
+        };
+        assert_eq!(cells(&negated), [Free, Unknown, Unknown, Occupied]);
+
+        let crossed = MapMetadata {
+            occupied_thresh: 0.4,
+            free_thresh: 0.5,
+            ..metadata()
+        };
+        assert!(matches!(
+            OccupancyGrid::from_pgm(pgm, &crossed),
+            Err(MapError::InvalidMap(_))
+        ));
+        let zero = MapMetadata {
+            resolution: 0.0,
+            ..metadata()
+        };
+        assert!(matches!(
+            OccupancyGrid::from_pgm(pgm, &zero),
+            Err(MapError::InvalidMap(_))
+        ));
+    }
+
+    #[test]
+    fn malformed_pgm_is_rejected() {
+        assert_eq!(invalid_pgm(b""), "empty file");
+        assert_eq!(invalid_pgm(b"# Only a comment\n"), "empty file");
+        assert_eq!(invalid_pgm(b"P3 1 1 255 0 0 0"), "not a P2 or P5 image");
+        assert_eq!(invalid_pgm(b"P2 3"), "missing height");
+        assert_eq!(invalid_pgm(b"P2 3 x 255"), "missing height");
+        assert_eq!(invalid_pgm(b"P2 3 2"), "missing maximum value");
+        assert_eq!(
+            invalid_pgm(b"P2 0 2 255"),
+            "zero size or maximum value out of range"
+        );
+        assert_eq!(
+            invalid_pgm(b"P2 1 1 65536 0"),
+            "zero size or maximum value out of range"
+        );
+        assert_eq!(
+            invalid_pgm(b"P2 2 2 255 0 0 0"),
+            "fewer pixels than width x height"
+        );
+        assert_eq!(
+            invalid_pgm(b"P2 2 2 255 0 0 x 0"),
+            "fewer pixels than width x height"
+        );
+        assert_eq!(
+            invalid_pgm(b"P5 2 2 255\n\x00\x00\x00"),
+            "fewer pixels than width x height"
+        );
+    }
+
+    #[test]
+    fn distance_transform_matches_brute_force() {
+        // Small random grids, from a linear congruential generator
+        let mut seed: u64 = 12345;
+        let mut random = || {
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (seed >> 33) as f64 / (1u64 << 31) as f64
+        };
+        for &(width, height, density) in &[(1, 1, 0.5), (7, 1, 0.3), (1, 5, 0.3), (9, 6, 0.2)] {
+            for _ in 0..20 {
+                let feature: Vec<bool> = (0..width * height).map(|_| random() < density).collect();
+                let distances = distance_transform(&feature, width, height);
+                for (k, &distance) in distances.iter().enumerate() {
+                    let (i, j) = ((k % width) as f64, (k / width) as f64);
+                    let brute_force = feature
+                        .iter()
+                        .enumerate()
+                        .filter(|&(_, &f)| f)
+                        .map(|(l, _)| {
+                            let (p, q) = ((l % width) as f64, (l / width) as f64);
+                            (i - p).hypot(j - q)
+                        })
+                        .fold(f64::INFINITY, f64::min);
+                    if brute_force.is_finite() {
+                        assert!((distance - brute_force).abs() < 1e-12);
+                    } else {
+                        assert!(distance >= FAR.sqrt());
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn distance_field_is_signed_at_the_cell_centres() {
+        let grid = grid(
+            &[".....", ".....", "..#..", ".....", "....."],
+            1.0,
+            Pose::new((0.0, 0.0), 0.0),
+        );
+        let field = grid.distance_field();
+        assert_eq!(field.value(2, 2), -0.5);
+        assert_eq!(field.value(0, 2), 1.5);
+        assert_eq!(field.value(4, 4), 8.0_f64.sqrt() - 0.5);
+        assert_eq!(field.signed_distance(&[2.5, 2.5]), -0.5);
+        assert_eq!(field.signed_distance(&[0.5, 2.5]), 1.5);
+        assert!(field.is_inside(&[2.5, 2.7]));
+        assert!(!field.is_inside(&[1.5, 2.5]));
+        // Beyond the map, the distance to the rectangle of the centres is added
+        assert_eq!(field.signed_distance(&[-1.5, 2.5]), 3.5);
+
+        // Inflating shifts the field down
+        let inflated = field.inflate(0.25);
+        assert_eq!(inflated.value(2, 2), -0.75);
+        assert_eq!(inflated.signed_distance(&[0.5, 2.5]), 1.25);
+        assert_eq!(inflated.penalty(&[2.5, 2.5]), 0.75);
+    }
+
+    #[test]
+    fn distance_field_gradient_matches_finite_differences() {
+        let map = ["......", ".###..", ".####.", "..##..", "......"];
+        // Points off the lines through the cell centres (kinks of the field)
+        let points = [
+            [1.37, 1.41],
+            [1.12, 0.93],
+            [1.6, 1.7],
+            [2.1, 1.2],
+            [4.0, 0.4],
+        ];
+        let field = grid(&map, 0.5, Pose::new((0.0, 0.0), 0.0)).distance_field();
+        assert!(points[..4].iter().all(|x| field.is_inside(x)));
+        assert_gradient(&field, &points);
+        assert_gradient(&field.inflate(0.2), &points);
+
+        // Rotated map: the same points, moved with the map
+        let pose = Pose::new((1.0, -2.0), 0.6);
+        let field = grid(&map, 0.5, pose).distance_field();
+        let moved: Vec<[f64; 2]> = points
+            .iter()
+            .map(|x| {
+                let (x0, x1) = pose.apply((x[0], x[1]));
+                [x0, x1]
+            })
+            .collect();
+        assert!(moved[..4].iter().all(|x| field.is_inside(x)));
+        assert_gradient(&field, &moved);
+    }
+
+    #[test]
+    fn example_14_map_loads() {
+        let path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../example_14_occupancy_grid/map.yaml"
+        );
+        let grid = OccupancyGrid::load(path).unwrap();
+        assert_eq!((grid.width(), grid.height()), (240, 240));
+        assert_eq!(grid.resolution(), 0.05);
+        assert_eq!(grid.origin(), Pose::new((-1.0, -1.0), 0.0));
+        let count = |cell: Cell| grid.cells.iter().filter(|&&c| c == cell).count();
+        assert_eq!(count(Cell::Occupied), 8740);
+        assert_eq!(count(Cell::Unknown), 1600);
+        assert_eq!(count(Cell::Free), 47260);
+        // The border of the map is a wall
+        assert_eq!(grid.cell(0, 0), Cell::Occupied);
+        assert_eq!(grid.cell(120, 120), Cell::Free);
+
+        assert!(matches!(
+            OccupancyGrid::load("no/such/map.yaml"),
+            Err(MapError::Io(_))
+        ));
+    }
+}
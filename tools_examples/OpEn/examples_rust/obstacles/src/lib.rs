@@ -0,0 +1,204 @@
+//! # Obstacles
+//!
+//! Obstacle shapes shared by the path planning examples (08 - 14).
+//!
+//! Every shape is described by a penalty function `h(x)`, which is zero
+//! whenever the position `x` is outside the obstacle and positive inside it.
+//! The planners impose `h(x) = 0` through the `F1`/`Zero` set mechanism of
+//! OpEn's ALM solver, or `sdf(x) >= margin` with the signed distance `sdf`
+//! of the shape and a half-line set. A `PosedObstacle` rotates and
+//! translates any shape by a `Pose`, and a `MovingObstacle` translates a
+//! shape along a `Trajectory`, so that its penalty also depends on time.
+//! Shapes are combined by `Union`, `Intersection` and `Difference`. For
+//! a robot that is not a point, the obstacles are inflated by its `Footprint`
+//! ([`ObstacleSpec::build_inflated`]). Maps are loaded as an `OccupancyGrid`
+//! (PGM image and YAML metadata) and become an obstacle through the signed
+//! `DistanceField` of their occupied cells.
+//!
+//! The shapes above are planar. The `Obstacle` trait, the combinations and
+//! `MovingObstacle` are generic over the dimension `D` (2 by default), and
+//! `Sphere`, `Ellipsoid`, `Cuboid` and `Polytope` exist in any dimension; in
+//! 3D they are described by [`ObstacleSpec3`].
+
+mod circle;
+mod csg;
+mod ellipse;
+mod footprint;
+mod grid;
+mod moving;
+mod nonlinear;
+mod polygon;
+mod polyhedron;
+mod pose;
+mod solid;
+mod spec;
+
+pub use circle::Circle;
+pub use csg::{Difference, Intersection, Union};
+pub use ellipse::Ellipse;
+pub use footprint::{Footprint, FootprintShape};
+pub use grid::{Cell, DistanceField, MapError, MapMetadata, OccupancyGrid};
+pub use moving::{MovingObstacle, Trajectory};
+pub use nonlinear::{Nlr1, Nlr2};
+pub use polygon::Polygon;
+pub use polyhedron::Polyhedron;
+pub use pose::{Pose, PosedObstacle};
+pub use solid::{Cuboid, Ellipsoid, Polytope, Sphere};
+pub use spec::{BuildObstacle, ObstacleError, ObstacleSpec, ObstacleSpec3};
+
+/// Axis-aligned box enclosing an obstacle, one `(min, max)` range per axis
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoundingBox<const D: usize = 2> {
+    pub ranges: [(f64, f64); D],
+}
+
+impl<const D: usize> BoundingBox<D> {
+    pub fn from_ranges(ranges: [(f64, f64); D]) -> Self {
+        BoundingBox { ranges }
+    }
+
+    /// Box covering the whole space (e.g., for unbounded polyhedra)
+    pub fn unbounded() -> Self {
+        BoundingBox::from_ranges([(f64::NEG_INFINITY, f64::INFINITY); D])
+    }
+
+    /// Smallest box enclosing both boxes
+    pub fn union(&self, other: &Self) -> Self {
+        let mut ranges = self.ranges;
+        for (range, other) in ranges.iter_mut().zip(other.ranges.iter()) {
+            *range = (range.0.min(other.0), range.1.max(other.1));
+        }
+        BoundingBox::from_ranges(ranges)
+    }
+
+    /// Common part of both boxes (with `min > max` if they do not overlap)
+    pub fn intersection(&self, other: &Self) -> Self {
+        let mut ranges = self.ranges;
+        for (range, other) in ranges.iter_mut().zip(other.ranges.iter()) {
+            *range = (range.0.max(other.0), range.1.min(other.1));
+        }
+        BoundingBox::from_ranges(ranges)
+    }
+
+    /// The box enlarged by `margin` on every side
+    pub fn inflate(&self, margin: f64) -> Self {
+        let mut ranges = self.ranges;
+        for range in ranges.iter_mut() {
+            *range = (range.0 - margin, range.1 + margin);
+        }
+        BoundingBox::from_ranges(ranges)
+    }
+}
+
+impl BoundingBox<2> {
+    /// `(x_min, x_max) x (y_min, y_max)`
+    pub fn new(x_range: (f64, f64), y_range: (f64, f64)) -> Self {
+        BoundingBox::from_ranges([x_range, y_range])
+    }
+
+    pub fn x_range(&self) -> (f64, f64) {
+        self.ranges[0]
+    }
+
+    pub fn y_range(&self) -> (f64, f64) {
+        self.ranges[1]
+    }
+}
+
+/// Common interface of all obstacle shapes in `D` dimensions (2 unless
+/// stated otherwise)
+///
+/// The positions `x` and the gradients have `D` entries.
+pub trait Obstacle<const D: usize = 2> {
+    /// Penalty `h(x) >= 0`, which is zero iff `x` is outside the obstacle
+    fn penalty(&self, x: &[f64]) -> f64;
+
+    /// Gradient of the penalty at `x`, written into `grad`
+    ///
+    /// Closed-form; where a `max(0, .)` factor is not active the gradient is zero.
+    fn gradient(&self, x: &[f64], grad: &mut [f64]);
+
+    /// Signed distance from `x` to the boundary of the obstacle: positive
+    /// outside, negative inside
+    ///
+    /// Unlike the penalty, it is informative away from the obstacle. Exact
+    /// for the convex shapes.
+    fn signed_distance(&self, x: &[f64]) -> f64;
+
+    /// Gradient of the signed distance at `x`, written into `grad`
+    fn signed_distance_gradient(&self, x: &[f64], grad: &mut [f64]);
+
+    /// Whether `x` lies (strictly) inside the obstacle
+    fn is_inside(&self, x: &[f64]) -> bool {
+        self.penalty(x) > 0.0
+    }
+
+    /// Axis-aligned box enclosing the obstacle
+    fn bounding_box(&self) -> BoundingBox<D>;
+}
+
+/// Grid points of `search_area` lying inside the obstacle (For Plot)
+///
+/// `search_area` is `((x_min, x_max), (y_min, y_max), resolution)`. Only the
+/// part of the grid within the obstacle's bounding box is evaluated.
+pub fn get_points<O: Obstacle + ?Sized>(
+    obstacle: &O,
+    search_area: ((f64, f64), (f64, f64), f64),
+) -> Vec<(f64, f64)> {
+    let mut points = Vec::new(); // Output initialisation
+    let (x_range, y_range, resolution) = search_area;
+    let bbox = obstacle.bounding_box();
+
+    // Keep the grid anchored at the corner of the search area
+    let first_index = |lower: f64, start: f64| ((lower - start) / resolution).ceil().max(0.0);
+    let x_start = x_range.0 + first_index(bbox.x_range().0, x_range.0) * resolution;
+    let y_start = y_range.0 + first_index(bbox.y_range().0, y_range.0) * resolution;
+    let x_end = x_range.1.min(bbox.x_range().1);
+    let y_end = y_range.1.min(bbox.y_range().1);
+
+    let mut x = x_start;
+    while x <= x_end {
+        let mut y = y_start;
+        while y <= y_end {
+            if obstacle.is_inside(&[x, y]) {
+                points.push((x, y));
+            }
+            y += resolution;
+        }
+        x += resolution;
+    }
+    points
+}
+
+#[cfg(test)]
+pub(crate) mod testing {
+    use crate::Obstacle;
+
+    /// Asserts that the closed-form gradient of the penalty matches central
+    /// differences at `points` (which must not lie on a kink of the penalty)
+    pub(crate) fn assert_gradient<O: Obstacle<D> + ?Sized, const D: usize>(
+        obstacle: &O,
+        points: &[[f64; D]],
+    ) {
+        let step = 1e-6;
+        for x in points {
+            let mut grad = [0.0; D];
+            obstacle.gradient(x, &mut grad);
+            for i in 0..D {
+                let (mut x_plus, mut x_minus) = (*x, *x);
+                x_plus[i] += step;
+                x_minus[i] -= step;
+                let numeric =
+                    (obstacle.penalty(&x_plus) - obstacle.penalty(&x_minus)) / (2.0 * step);
+                assert!(
+                    (grad[i] - numeric).abs() <= 1e-6 * (1.0 + numeric.abs()),
+                    "gradient {} at {:?}: {} (finite differences: {})",
+                    i,
+                    x,
+                    grad[i],
+                    numeric
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,273 @@
+use std::fmt;
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Obstacle, ObstacleError};
+
+/// Displacement of an obstacle from the position in its specification, as
+/// a function of time
+///
+/// `Custom` trajectories cannot be written to scenario files.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Trajectory {
+    /// The obstacle does not move
+    Static,
+    /// Displacement `v t`
+    ConstantVelocity((f64, f64)),
+    /// Piecewise-linear through `(t_i, displacement_i)`, with `t_i` strictly
+    /// increasing; held at the first/last displacement outside `[t_0, t_n]`
+    Waypoints(Vec<(f64, (f64, f64))>),
+    /// User-defined displacement
+    #[serde(skip)]
+    Custom(Arc<dyn Fn(f64) -> (f64, f64) + Send + Sync>),
+}
+
+impl Trajectory {
+    /// Displacement at time `t`
+    pub fn displacement(&self, t: f64) -> (f64, f64) {
+        match self {
+            Trajectory::Static => (0.0, 0.0),
+            Trajectory::ConstantVelocity(v) => (v.0 * t, v.1 * t),
+            Trajectory::Waypoints(waypoints) => {
+                let k = waypoints.iter().take_while(|w| w.0 <= t).count();
+                if k == 0 {
+                    return waypoints[0].1;
+                }
+                if k == waypoints.len() {
+                    return waypoints[k - 1].1;
+                }
+                let (t0, d0) = waypoints[k - 1];
+                let (t1, d1) = waypoints[k];
+                let s = (t - t0) / (t1 - t0);
+                (d0.0 + s * (d1.0 - d0.0), d0.1 + s * (d1.1 - d0.1))
+            }
+            Trajectory::Custom(displacement) => displacement(t),
+        }
+    }
+
+    pub fn is_static(&self) -> bool {
+        matches!(self, Trajectory::Static)
+    }
+
+    /// Checks that the velocity and the waypoints are finite, and that there
+    /// is at least one waypoint, at strictly increasing times
+    pub fn validate(&self) -> Result<(), ObstacleError> {
+        match self {
+            Trajectory::Static | Trajectory::Custom(_) => Ok(()),
+            Trajectory::ConstantVelocity(v) => {
+                if v.0.is_finite() && v.1.is_finite() {
+                    Ok(())
+                } else {
+                    Err(ObstacleError::NotFinite)
+                }
+            }
+            Trajectory::Waypoints(waypoints) => {
+                if waypoints.is_empty() {
+                    return Err(ObstacleError::EmptyTrajectory);
+                }
+                for (i, &(t, d)) in waypoints.iter().enumerate() {
+                    if ![t, d.0, d.1].iter().all(|v| v.is_finite()) {
+                        return Err(ObstacleError::NotFinite);
+                    }
+                    if i > 0 && t <= waypoints[i - 1].0 {
+                        return Err(ObstacleError::UnorderedWaypoints(i));
+                    }
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl fmt::Debug for Trajectory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Trajectory::Static => write!(f, "Static"),
+            Trajectory::ConstantVelocity(v) => f.debug_tuple("ConstantVelocity").field(v).finish(),
+            Trajectory::Waypoints(w) => f.debug_tuple("Waypoints").field(w).finish(),
+            Trajectory::Custom(_) => write!(f, "Custom(..)"),
+        }
+    }
+}
+
+/// Custom trajectories are equal only if they share the same closure
+impl PartialEq for Trajectory {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Trajectory::Static, Trajectory::Static) => true,
+            (Trajectory::ConstantVelocity(v), Trajectory::ConstantVelocity(w)) => v == w,
+            (Trajectory::Waypoints(v), Trajectory::Waypoints(w)) => v == w,
+            (Trajectory::Custom(v), Trajectory::Custom(w)) => Arc::ptr_eq(v, w),
+            _ => false,
+        }
+    }
+}
+
+/// Obstacle shape moving along a trajectory
+///
+/// At time `t` the penalty is that of the shape translated by the
+/// displacement `d(t)`, i.e., `h(x - d(t))`. In three dimensions the shape
+/// moves in the `(x, y)` plane.
+pub struct MovingObstacle<const D: usize = 2> {
+    shape: Box<dyn Obstacle<D>>,
+    trajectory: Trajectory,
+}
+
+impl<const D: usize> MovingObstacle<D> {
+    /// Fails if the trajectory is invalid (see `Trajectory::validate`)
+    pub fn new(shape: Box<dyn Obstacle<D>>, trajectory: Trajectory) -> Result<Self, ObstacleError> {
+        trajectory.validate()?;
+        Ok(MovingObstacle { shape, trajectory })
+    }
+
+    /// Shape at its initial position
+    pub fn shape(&self) -> &dyn Obstacle<D> {
+        self.shape.as_ref()
+    }
+
+    pub fn trajectory(&self) -> &Trajectory {
+        &self.trajectory
+    }
+
+    /// `x` in the frame of the shape at time `t`
+    fn relative(&self, t: f64, x: &[f64]) -> [f64; D] {
+        let d = self.trajectory.displacement(t);
+        let mut relative = [0.0; D];
+        relative.copy_from_slice(x);
+        relative[0] -= d.0;
+        relative[1] -= d.1;
+        relative
+    }
+
+    /// Penalty at time `t`
+    pub fn penalty(&self, t: f64, x: &[f64]) -> f64 {
+        self.shape.penalty(&self.relative(t, x))
+    }
+
+    /// Gradient of the penalty with respect to `x` at time `t`
+    pub fn gradient(&self, t: f64, x: &[f64], grad: &mut [f64]) {
+        self.shape.gradient(&self.relative(t, x), grad);
+    }
+
+    /// Signed distance at time `t`
+    pub fn signed_distance(&self, t: f64, x: &[f64]) -> f64 {
+        self.shape.signed_distance(&self.relative(t, x))
+    }
+
+    /// Gradient of the signed distance with respect to `x` at time `t`
+    pub fn signed_distance_gradient(&self, t: f64, x: &[f64], grad: &mut [f64]) {
+        self.shape
+            .signed_distance_gradient(&self.relative(t, x), grad);
+    }
+
+    /// Whether `x` lies (strictly) inside the obstacle at time `t`
+    pub fn is_inside(&self, t: f64, x: &[f64]) -> bool {
+        self.shape.is_inside(&self.relative(t, x))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Circle, Sphere};
+    use nalgebra::Vector3;
+
+    fn waypoints() -> Trajectory {
+        Trajectory::Waypoints(vec![
+            (1.0, (0.0, 0.0)),
+            (3.0, (2.0, -4.0)),
+            (4.0, (2.0, 0.0)),
+        ])
+    }
+
+    #[test]
+    fn constant_velocity_moves_linearly() {
+        let trajectory = Trajectory::ConstantVelocity((0.5, -1.0));
+        assert_eq!(trajectory.displacement(0.0), (0.0, 0.0));
+        assert_eq!(trajectory.displacement(2.0), (1.0, -2.0));
+        assert!(!trajectory.is_static());
+        assert_eq!(Trajectory::Static.displacement(5.0), (0.0, 0.0));
+    }
+
+    #[test]
+    fn waypoints_are_interpolated() {
+        let trajectory = waypoints();
+        assert_eq!(trajectory.displacement(1.0), (0.0, 0.0));
+        assert_eq!(trajectory.displacement(2.0), (1.0, -2.0));
+        assert_eq!(trajectory.displacement(3.0), (2.0, -4.0));
+        assert_eq!(trajectory.displacement(3.5), (2.0, -2.0));
+    }
+
+    #[test]
+    fn waypoints_are_held_outside_their_times() {
+        let trajectory = waypoints();
+        assert_eq!(trajectory.displacement(0.0), (0.0, 0.0));
+        assert_eq!(trajectory.displacement(4.0), (2.0, 0.0));
+        assert_eq!(trajectory.displacement(10.0), (2.0, 0.0));
+        let single = Trajectory::Waypoints(vec![(2.0, (1.0, 1.0))]);
+        assert_eq!(single.displacement(0.0), (1.0, 1.0));
+        assert_eq!(single.displacement(5.0), (1.0, 1.0));
+    }
+
+    #[test]
+    fn invalid_trajectories_are_rejected() {
+        let circle = || Box::new(Circle::new((0.0, 0.0), 1.0)) as Box<dyn Obstacle>;
+        let new = |trajectory| MovingObstacle::new(circle(), trajectory).err();
+        assert_eq!(
+            new(Trajectory::Waypoints(vec![])),
+            Some(ObstacleError::EmptyTrajectory)
+        );
+        assert_eq!(
+            new(Trajectory::Waypoints(vec![
+                (1.0, (0.0, 0.0)),
+                (1.0, (1.0, 0.0))
+            ])),
+            Some(ObstacleError::UnorderedWaypoints(1))
+        );
+        assert_eq!(
+            new(Trajectory::Waypoints(vec![
+                (0.0, (0.0, 0.0)),
+                (2.0, (1.0, 0.0)),
+                (1.0, (0.0, 1.0))
+            ])),
+            Some(ObstacleError::UnorderedWaypoints(2))
+        );
+        assert_eq!(
+            new(Trajectory::Waypoints(vec![(f64::NAN, (0.0, 0.0))])),
+            Some(ObstacleError::NotFinite)
+        );
+        assert_eq!(
+            new(Trajectory::ConstantVelocity((f64::INFINITY, 0.0))),
+            Some(ObstacleError::NotFinite)
+        );
+        assert_eq!(new(waypoints()), None);
+    }
+
+    #[test]
+    fn shape_is_translated_by_the_displacement() {
+        let moving = MovingObstacle::new(
+            Box::new(Circle::new((0.0, 0.0), 1.0)),
+            Trajectory::ConstantVelocity((1.0, 0.0)),
+        )
+        .unwrap();
+        // At t = 2 the circle is centred on (2, 0)
+        assert_eq!(moving.penalty(2.0, &[2.0, 0.0]), 1.0);
+        assert_eq!(moving.penalty(0.0, &[2.0, 0.0]), 0.0);
+        assert!(moving.is_inside(2.0, &[2.5, 0.0]));
+        assert!((moving.signed_distance(2.0, &[4.0, 0.0]) - 1.0).abs() < 1e-12);
+        let mut grad = [0.0; 2];
+        moving.gradient(2.0, &[2.5, 0.0], &mut grad);
+        assert_eq!(grad, [-1.0, 0.0]);
+
+        // In 3D the shape moves in the (x, y) plane
+        let moving = MovingObstacle::new(
+            Box::new(Sphere::new(Vector3::new(0.0, 0.0, 1.0), 1.0)),
+            Trajectory::ConstantVelocity((0.0, 1.0)),
+        )
+        .unwrap();
+        assert!(moving.is_inside(3.0, &[0.0, 3.0, 1.0]));
+        assert!(!moving.is_inside(3.0, &[0.0, 0.0, 1.0]));
+    }
+}
@@ -0,0 +1,249 @@
+//! Nonlinear obstacles in Sathya et al. 2019
+
+use crate::{BoundingBox, Obstacle};
+
+/// `(g, (dg/dx_0, dg/dx_1), d^2 g / dx_0^2)`, where `g` depends on `x_1` linearly
+type Term = (f64, (f64, f64), f64);
+
+/// First-order signed distance to the region `{g_i > 0 for all i}`:
+/// `max_i (-g_i / |grad g_i|)`, with its gradient written into `grad`
+///
+/// Each `-g_i / |grad g_i|` is the distance to the curve `g_i = 0` after
+/// linearising `g_i`; it is exact for straight boundaries.
+fn first_order_distance(terms: &[Term], grad: Option<&mut [f64]>) -> f64 {
+    let mut active = (f64::NEG_INFINITY, 0);
+    for (i, &(g, dg, _)) in terms.iter().enumerate() {
+        let d = -g / (dg.0 * dg.0 + dg.1 * dg.1).sqrt();
+        if d > active.0 {
+            active = (d, i);
+        }
+    }
+    if let Some(grad) = grad {
+        // d(-g / n) = -dg / n + g dn / n^2, dn = H dg / n with H = diag(g_00, 0)
+        let (g, dg, g_00) = terms[active.1];
+        let n = (dg.0 * dg.0 + dg.1 * dg.1).sqrt();
+        grad[0] = -dg.0 / n + g * g_00 * dg.0 / n.powi(3);
+        grad[1] = -dg.1 / n;
+    }
+    active.0
+}
+
+/// Region between two parabolas, `x_0^2 < x_1 < 1 + x_0^2 / 2` (relative to the centre)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Nlr1 {
+    centre: (f64, f64),
+}
+
+impl Nlr1 {
+    pub fn new(centre: (f64, f64)) -> Self {
+        Nlr1 { centre }
+    }
+
+    pub fn centre(&self) -> (f64, f64) {
+        self.centre
+    }
+}
+
+impl Nlr1 {
+    /// Defining functions `g_i > 0` with their gradients and `d^2 g_i / dx_0^2`
+    fn terms(&self, x: &[f64]) -> [Term; 2] {
+        let dx = x[0] - self.centre.0;
+        let dy = x[1] - self.centre.1;
+        [
+            (dy - dx.powi(2), (-2.0 * dx, 1.0), -2.0),
+            (1.0 + dx.powi(2) / 2.0 - dy, (dx, -1.0), 1.0),
+        ]
+    }
+}
+
+impl Obstacle for Nlr1 {
+    fn penalty(&self, x: &[f64]) -> f64 {
+        let dx = x[0] - self.centre.0;
+        let dy = x[1] - self.centre.1;
+        let h1 = (dy - dx.powi(2)).max(0.0);
+        let h2 = (1.0 + dx.powi(2) / 2.0 - dy).max(0.0);
+        h1 * h2
+    }
+
+    fn gradient(&self, x: &[f64], grad: &mut [f64]) {
+        let dx = x[0] - self.centre.0;
+        let dy = x[1] - self.centre.1;
+        let h1 = dy - dx.powi(2);
+        let h2 = 1.0 + dx.powi(2) / 2.0 - dy;
+        if h1 > 0.0 && h2 > 0.0 {
+            grad[0] = -2.0 * dx * h2 + h1 * dx;
+            grad[1] = h2 - h1;
+        } else {
+            grad[0] = 0.0;
+            grad[1] = 0.0;
+        }
+    }
+
+    fn signed_distance(&self, x: &[f64]) -> f64 {
+        first_order_distance(&self.terms(x), None)
+    }
+
+    fn signed_distance_gradient(&self, x: &[f64], grad: &mut [f64]) {
+        first_order_distance(&self.terms(x), Some(grad));
+    }
+
+    fn bounding_box(&self) -> BoundingBox {
+        // The two parabolas intersect at dx = +-sqrt(2), dy = 2
+        let half_width = 2.0_f64.sqrt();
+        BoundingBox::new(
+            (self.centre.0 - half_width, self.centre.0 + half_width),
+            (self.centre.1, self.centre.1 + 2.0),
+        )
+    }
+}
+
+/// Region between two sinusoids, cut at `1 < x_0 < 8` (relative to the centre)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Nlr2 {
+    centre: (f64, f64),
+}
+
+impl Nlr2 {
+    pub fn new(centre: (f64, f64)) -> Self {
+        Nlr2 { centre }
+    }
+
+    pub fn centre(&self) -> (f64, f64) {
+        self.centre
+    }
+}
+
+impl Nlr2 {
+    /// Defining functions `g_i > 0` with their gradients and `d^2 g_i / dx_0^2`
+    fn terms(&self, x: &[f64]) -> [Term; 4] {
+        let dx = x[0] - self.centre.0;
+        let dy = x[1] - self.centre.1;
+        [
+            (
+                dy - 2.0 * (-dx / 2.0).sin(),
+                ((dx / 2.0).cos(), 1.0),
+                -0.5 * (dx / 2.0).sin(),
+            ),
+            (
+                3.0 * (dx / 2.0 - 1.0).sin() - dy,
+                (1.5 * (dx / 2.0 - 1.0).cos(), -1.0),
+                -0.75 * (dx / 2.0 - 1.0).sin(),
+            ),
+            (dx - 1.0, (1.0, 0.0), 0.0),
+            (8.0 - dx, (-1.0, 0.0), 0.0),
+        ]
+    }
+}
+
+impl Obstacle for Nlr2 {
+    fn penalty(&self, x: &[f64]) -> f64 {
+        let dx = x[0] - self.centre.0;
+        let dy = x[1] - self.centre.1;
+        let h1 = (dy - 2.0 * (-dx / 2.0).sin()).max(0.0);
+        let h2_1 = (3.0 * (dx / 2.0 - 1.0).sin() - dy).max(0.0);
+        let h2_2 = (dx - 1.0).max(0.0);
+        let h2_3 = (8.0 - dx).max(0.0);
+        h1 * h2_1 * h2_2 * h2_3
+    }
+
+    fn gradient(&self, x: &[f64], grad: &mut [f64]) {
+        let dx = x[0] - self.centre.0;
+        let dy = x[1] - self.centre.1;
+        // Factors and their gradients (d/dx, d/dy)
+        let factors = [
+            (dy - 2.0 * (-dx / 2.0).sin(), ((dx / 2.0).cos(), 1.0)),
+            (
+                3.0 * (dx / 2.0 - 1.0).sin() - dy,
+                (1.5 * (dx / 2.0 - 1.0).cos(), -1.0),
+            ),
+            (dx - 1.0, (1.0, 0.0)),
+            (8.0 - dx, (-1.0, 0.0)),
+        ];
+        grad[0] = 0.0;
+        grad[1] = 0.0;
+        if factors.iter().any(|&(t, _)| t <= 0.0) {
+            return;
+        }
+        // For h = prod_i t_i with all t_i > 0: dh = h * sum_i (dt_i / t_i)
+        let h: f64 = factors.iter().map(|&(t, _)| t).product();
+        for &(t, (dt_x, dt_y)) in &factors {
+            grad[0] += h * dt_x / t;
+            grad[1] += h * dt_y / t;
+        }
+    }
+
+    fn signed_distance(&self, x: &[f64]) -> f64 {
+        first_order_distance(&self.terms(x), None)
+    }
+
+    fn signed_distance_gradient(&self, x: &[f64], grad: &mut [f64]) {
+        first_order_distance(&self.terms(x), Some(grad));
+    }
+
+    fn bounding_box(&self) -> BoundingBox {
+        // -2 sin(dx/2) >= -2 from below, 3 sin(dx/2 - 1) <= 3 from above
+        BoundingBox::new(
+            (self.centre.0 + 1.0, self.centre.0 + 8.0),
+            (self.centre.1 - 2.0, self.centre.1 + 3.0),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::assert_gradient;
+
+    /// `h_nlr_1` of Examples 10 and 11 before the shapes were extracted
+    fn h_nlr_1(x: &[f64], centre: (f64, f64)) -> f64 {
+        let h1 = ((x[1] - centre.1) - (x[0] - centre.0).powi(2)).max(0.0);
+        let h2 = (1.0 + (x[0] - centre.0).powi(2) / 2.0 - (x[1] - centre.1)).max(0.0);
+        h1 * h2
+    }
+
+    /// `h_nlr_2` of Examples 10 and 11 before the shapes were extracted
+    fn h_nlr_2(x: &[f64], centre: (f64, f64)) -> f64 {
+        let h1 = ((x[1] - centre.1) - 2.0 * (-(x[0] - centre.0) / 2.0).sin()).max(0.0);
+        let h2_1 = (3.0 * (((x[0] - centre.0) / 2.0) - 1.0).sin() - (x[1] - centre.1)).max(0.0);
+        let h2_2 = (x[0] - centre.0 - 1.0).max(0.0);
+        let h2_3 = (8.0 - (x[0] - centre.0)).max(0.0);
+        h1 * h2_1 * h2_2 * h2_3
+    }
+
+    const NLR1_POINTS: [[f64; 2]; 5] = [
+        [-5.0, 0.5],
+        [-4.7, 0.6],
+        [-5.9, 1.3],
+        [-5.0, -1.0],
+        [-3.0, 1.0],
+    ];
+    const NLR2_POINTS: [[f64; 2]; 5] = [
+        [0.0, -4.0],
+        [-1.5, -3.5],
+        [1.0, -5.5],
+        [-4.5, -5.0],
+        [0.0, 0.0],
+    ];
+
+    #[test]
+    fn penalty_matches_baseline() {
+        let nlr1 = Nlr1::new((-5.0, 0.0));
+        for x in NLR1_POINTS.iter() {
+            let h = h_nlr_1(x, (-5.0, 0.0));
+            assert!((nlr1.penalty(x) - h).abs() <= 1e-12);
+            assert_eq!(nlr1.is_inside(x), h > 0.0);
+        }
+        let nlr2 = Nlr2::new((-5.0, -5.0));
+        for x in NLR2_POINTS.iter() {
+            let h = h_nlr_2(x, (-5.0, -5.0));
+            assert!((nlr2.penalty(x) - h).abs() <= 1e-12 * (1.0 + h));
+            assert_eq!(nlr2.is_inside(x), h > 0.0);
+        }
+    }
+
+    #[test]
+    fn gradient_matches_finite_differences() {
+        assert_gradient(&Nlr1::new((-5.0, 0.0)), &NLR1_POINTS);
+        assert_gradient(&Nlr2::new((-5.0, -5.0)), &NLR2_POINTS);
+    }
+}
@@ -0,0 +1,485 @@
+use crate::{BoundingBox, Footprint, Obstacle, ObstacleError, Polyhedron};
+
+/// Tolerance on the sine of the angle between consecutive edges
+const ANGLE_TOLERANCE: f64 = 1e-9;
+/// Largest overlap of neighbouring convex pieces across their common diagonal,
+/// relative to the size of the polygon
+const SEAM_OVERLAP: f64 = 1e-3;
+
+/// Vertex with its index in the list given by the user
+type IndexedVertex = (usize, (f64, f64));
+
+/// Simple (possibly non-convex) polygonal obstacle
+///
+/// The polygon is decomposed into convex pieces (ear clipping, then
+/// Hertel-Mehlhorn merging), each of which is a `Polyhedron`, and
+/// `h(x) = sum_j h_j(x)`. Neighbouring pieces overlap across their common
+/// diagonal, so that the penalty does not vanish along it: each piece is
+/// extended by a thin triangle on the diagonal, reaching at most
+/// `SEAM_OVERLAP` times the size of the polygon into the neighbouring piece,
+/// so the pieces stay inside the polygon. The signed distance
+/// `min_j sdf_j(x)` is exact outside the polygon, and underestimates the
+/// depth inside.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Polygon {
+    vertices: Vec<(f64, f64)>, // Relative to the centre, counter-clockwise
+    centre: (f64, f64),
+    pieces: Vec<Polyhedron>,
+}
+
+impl Polygon {
+    /// `vertices`: in order (either orientation), relative to `centre`
+    ///
+    /// Fails if the polygon is degenerate or not simple.
+    pub fn new(vertices: &[(f64, f64)], centre: (f64, f64)) -> Result<Self, ObstacleError> {
+        let vertices: Vec<_> = normalise(vertices)?.into_iter().map(|v| v.1).collect();
+        let (x_min, x_max, y_min, y_max) = vertices.iter().fold(
+            (
+                f64::INFINITY,
+                f64::NEG_INFINITY,
+                f64::INFINITY,
+                f64::NEG_INFINITY,
+            ),
+            |b, v| (b.0.min(v.0), b.1.max(v.0), b.2.min(v.1), b.3.max(v.1)),
+        );
+        let overlap = SEAM_OVERLAP * (x_max - x_min).hypot(y_max - y_min);
+
+        let n = vertices.len();
+        let pieces = merge(&vertices, triangulate(&vertices)?);
+        let edges = |piece: &[usize]| -> Vec<(usize, usize)> {
+            (0..piece.len())
+                .map(|k| (piece[k], piece[(k + 1) % piece.len()]))
+                .collect()
+        };
+        // Distance from `x` to the lines of the edges of `piece` other than
+        // `skip`, inside
+        let margin = |piece: &[usize], skip: (usize, usize), x: (f64, f64)| {
+            edges(piece)
+                .into_iter()
+                .filter(|&e| e != skip)
+                .map(|(i, j)| {
+                    let (a, b) = edge_row(vertices[i], vertices[j]);
+                    b - a.0 * x.0 - a.1 * x.1
+                })
+                .fold(f64::INFINITY, f64::min)
+        };
+        let pieces = pieces
+            .iter()
+            .map(|piece| {
+                // Each diagonal is replaced by a thin triangle reaching into
+                // the neighbouring piece, whose apex is close enough to the
+                // diagonal for the piece to stay convex and inside the polygon
+                let mut hull = Vec::with_capacity(2 * piece.len());
+                for (i, j) in edges(piece) {
+                    hull.push(vertices[i]);
+                    if j == (i + 1) % n {
+                        continue;
+                    }
+                    let neighbour = pieces
+                        .iter()
+                        .find(|other| edges(other).contains(&(j, i)))
+                        .expect("every diagonal is shared by two pieces");
+                    let (v, w) = (vertices[i], vertices[j]);
+                    let middle = (0.5 * (v.0 + w.0), 0.5 * (v.1 + w.1));
+                    let depth = overlap
+                        .min(0.25 * margin(piece, (i, j), middle))
+                        .min(0.25 * margin(neighbour, (j, i), middle));
+                    let (a, _) = edge_row(v, w);
+                    hull.push((middle.0 + depth * a.0, middle.1 + depth * a.1));
+                }
+                Polyhedron::new(halfspaces(&hull), centre)
+            })
+            .collect();
+        Ok(Polygon {
+            vertices,
+            centre,
+            pieces,
+        })
+    }
+
+    /// Vertices relative to the centre, counter-clockwise, without repeated
+    /// or collinear vertices
+    pub fn vertices(&self) -> &[(f64, f64)] {
+        &self.vertices
+    }
+
+    pub fn centre(&self) -> (f64, f64) {
+        self.centre
+    }
+
+    /// Convex pieces (a single one if the polygon is convex)
+    pub fn pieces(&self) -> &[Polyhedron] {
+        &self.pieces
+    }
+
+    /// Minkowski sum with `-F` for the robot footprint `F`, piece by piece
+    /// (see [`Polyhedron::inflate`])
+    pub fn inflate(&self, footprint: &Footprint) -> Polygon {
+        Polygon {
+            vertices: self.vertices.clone(),
+            centre: self.centre,
+            pieces: self
+                .pieces
+                .iter()
+                .map(|piece| piece.inflate(footprint))
+                .collect(),
+        }
+    }
+
+    /// Piece with the smallest signed distance at `x`, and that distance
+    fn closest_piece(&self, x: &[f64]) -> (usize, f64) {
+        let mut closest = (0, f64::INFINITY);
+        for (j, piece) in self.pieces.iter().enumerate() {
+            let d = piece.signed_distance(x);
+            if d < closest.1 {
+                closest = (j, d);
+            }
+        }
+        closest
+    }
+}
+
+impl Obstacle for Polygon {
+    fn penalty(&self, x: &[f64]) -> f64 {
+        self.pieces.iter().map(|piece| piece.penalty(x)).sum()
+    }
+
+    fn gradient(&self, x: &[f64], grad: &mut [f64]) {
+        grad[0] = 0.0;
+        grad[1] = 0.0;
+        let mut grad_j = [0.0; 2];
+        for piece in &self.pieces {
+            piece.gradient(x, &mut grad_j);
+            grad[0] += grad_j[0];
+            grad[1] += grad_j[1];
+        }
+    }
+
+    fn signed_distance(&self, x: &[f64]) -> f64 {
+        self.closest_piece(x).1
+    }
+
+    fn signed_distance_gradient(&self, x: &[f64], grad: &mut [f64]) {
+        let (j, _) = self.closest_piece(x);
+        self.pieces[j].signed_distance_gradient(x, grad);
+    }
+
+    fn bounding_box(&self) -> BoundingBox {
+        self.pieces[1..]
+            .iter()
+            .fold(self.pieces[0].bounding_box(), |bbox, piece| {
+                bbox.union(&piece.bounding_box())
+            })
+    }
+}
+
+/// `(b - a) x (c - a)`: positive if `a, b, c` turn left
+fn cross(a: (f64, f64), b: (f64, f64), c: (f64, f64)) -> f64 {
+    (b.0 - a.0) * (c.1 - a.1) - (b.1 - a.1) * (c.0 - a.0)
+}
+
+/// Whether `a, b, c` turn left by more than the angle tolerance
+fn is_left_turn(a: (f64, f64), b: (f64, f64), c: (f64, f64)) -> bool {
+    let lengths = (b.0 - a.0).hypot(b.1 - a.1) * (c.0 - b.0).hypot(c.1 - b.1);
+    cross(a, b, c) > ANGLE_TOLERANCE * lengths
+}
+
+/// Row `(a, b)` of the edge `v -> w` of a counter-clockwise polygon, whose
+/// interior is `a^T x < b`, with a unit normal `a`
+fn edge_row(v: (f64, f64), w: (f64, f64)) -> ((f64, f64), f64) {
+    let length = (w.0 - v.0).hypot(w.1 - v.1);
+    let a = ((w.1 - v.1) / length, (v.0 - w.0) / length);
+    (a, a.0 * v.0 + a.1 * v.1)
+}
+
+/// Half-space rows of a convex polygon, counter-clockwise
+pub(crate) fn halfspaces(vertices: &[(f64, f64)]) -> Vec<((f64, f64), f64)> {
+    let n = vertices.len();
+    (0..n)
+        .map(|k| edge_row(vertices[k], vertices[(k + 1) % n]))
+        .collect()
+}
+
+/// Whether the closed segments `p` and `q` have a common point
+fn segments_intersect(p: ((f64, f64), (f64, f64)), q: ((f64, f64), (f64, f64))) -> bool {
+    let d1 = cross(q.0, q.1, p.0);
+    let d2 = cross(q.0, q.1, p.1);
+    let d3 = cross(p.0, p.1, q.0);
+    let d4 = cross(p.0, p.1, q.1);
+    if d1 * d2 < 0.0 && d3 * d4 < 0.0 {
+        return true;
+    }
+    // Collinear end point lying on the other segment
+    let on_segment = |s: ((f64, f64), (f64, f64)), x: (f64, f64)| {
+        x.0 >= s.0 .0.min(s.1 .0)
+            && x.0 <= s.0 .0.max(s.1 .0)
+            && x.1 >= s.0 .1.min(s.1 .1)
+            && x.1 <= s.0 .1.max(s.1 .1)
+    };
+    (d1 == 0.0 && on_segment(q, p.0))
+        || (d2 == 0.0 && on_segment(q, p.1))
+        || (d3 == 0.0 && on_segment(p, q.0))
+        || (d4 == 0.0 && on_segment(p, q.1))
+}
+
+/// Vertices of a simple polygon, counter-clockwise, without repeated or
+/// collinear vertices, each with its index in `vertices`
+pub(crate) fn normalise(vertices: &[(f64, f64)]) -> Result<Vec<IndexedVertex>, ObstacleError> {
+    if vertices
+        .iter()
+        .any(|v| !v.0.is_finite() || !v.1.is_finite())
+    {
+        return Err(ObstacleError::NotFinite);
+    }
+    let mut polygon: Vec<IndexedVertex> = vertices.iter().copied().enumerate().collect();
+    polygon.dedup_by(|v, w| v.1 == w.1);
+    while polygon.len() > 1 && polygon[0].1 == polygon[polygon.len() - 1].1 {
+        polygon.pop();
+    }
+
+    // Collinear vertices are dropped, edges that fold back are degenerate
+    let mut k = 0;
+    while polygon.len() >= 3 && k < polygon.len() {
+        let n = polygon.len();
+        let (u, v, w) = (
+            polygon[(k + n - 1) % n].1,
+            polygon[k].1,
+            polygon[(k + 1) % n].1,
+        );
+        if !is_left_turn(u, v, w) && !is_left_turn(w, v, u) {
+            if (v.0 - u.0) * (w.0 - v.0) + (v.1 - u.1) * (w.1 - v.1) < 0.0 {
+                return Err(ObstacleError::DegeneratePolygon);
+            }
+            polygon.remove(k);
+            k = 0;
+        } else {
+            k += 1;
+        }
+    }
+    let n = polygon.len();
+    if n < 3 {
+        return Err(ObstacleError::DegeneratePolygon);
+    }
+
+    for i in 0..n {
+        // Edges sharing a vertex only meet there (no fold-back)
+        for j in i + 2..n {
+            if i == 0 && j == n - 1 {
+                continue;
+            }
+            let p = (polygon[i].1, polygon[i + 1].1);
+            let q = (polygon[j].1, polygon[(j + 1) % n].1);
+            if segments_intersect(p, q) {
+                return Err(ObstacleError::SelfIntersectingPolygon(
+                    polygon[i].0,
+                    polygon[j].0,
+                ));
+            }
+        }
+    }
+
+    let area: f64 = (0..n)
+        .map(|k| cross((0.0, 0.0), polygon[k].1, polygon[(k + 1) % n].1))
+        .sum();
+    if area < 0.0 {
+        polygon.reverse();
+    }
+    Ok(polygon)
+}
+
+/// Triangles (counter-clockwise vertex indices) of a simple counter-clockwise
+/// polygon, by ear clipping
+fn triangulate(vertices: &[(f64, f64)]) -> Result<Vec<Vec<usize>>, ObstacleError> {
+    let mut remaining: Vec<usize> = (0..vertices.len()).collect();
+    let mut triangles = Vec::with_capacity(vertices.len() - 2);
+    while remaining.len() > 3 {
+        let n = remaining.len();
+        let ear = (0..n).find(|&k| {
+            let (a, b, c) = (
+                remaining[(k + n - 1) % n],
+                remaining[k],
+                remaining[(k + 1) % n],
+            );
+            let (va, vb, vc) = (vertices[a], vertices[b], vertices[c]);
+            is_left_turn(va, vb, vc)
+                && remaining.iter().all(|&i| {
+                    let p = vertices[i];
+                    i == a
+                        || i == b
+                        || i == c
+                        || cross(va, vb, p) < 0.0
+                        || cross(vb, vc, p) < 0.0
+                        || cross(vc, va, p) < 0.0
+                })
+        });
+        // A simple polygon always has an ear, up to rounding
+        let k = ear.ok_or(ObstacleError::DegeneratePolygon)?;
+        triangles.push(vec![
+            remaining[(k + n - 1) % n],
+            remaining[k],
+            remaining[(k + 1) % n],
+        ]);
+        remaining.remove(k);
+    }
+    triangles.push(remaining);
+    Ok(triangles)
+}
+
+/// Merges neighbouring pieces as long as the union stays strictly convex
+/// (Hertel-Mehlhorn)
+fn merge(vertices: &[(f64, f64)], mut pieces: Vec<Vec<usize>>) -> Vec<Vec<usize>> {
+    let is_convex = |piece: &[usize]| {
+        let n = piece.len();
+        (0..n).all(|k| {
+            is_left_turn(
+                vertices[piece[(k + n - 1) % n]],
+                vertices[piece[k]],
+                vertices[piece[(k + 1) % n]],
+            )
+        })
+    };
+    // Union of `a` and `b` if `a` has the edge u -> v and `b` the edge v -> u
+    let union = |a: &[usize], b: &[usize]| -> Option<Vec<usize>> {
+        for k in 0..a.len() {
+            let (u, v) = (a[k], a[(k + 1) % a.len()]);
+            let Some(l) = b.iter().position(|&i| i == v) else {
+                continue;
+            };
+            if b[(l + 1) % b.len()] != u {
+                continue;
+            }
+            // v, ..., u along `a`, then u, ..., v along `b` without the ends
+            let mut merged: Vec<usize> = (0..a.len()).map(|j| a[(k + 1 + j) % a.len()]).collect();
+            merged.extend((1..b.len() - 1).map(|j| b[(l + 1 + j) % b.len()]));
+            return Some(merged);
+        }
+        None
+    };
+
+    let mut merged_any = true;
+    while merged_any {
+        merged_any = false;
+        'search: for i in 0..pieces.len() {
+            for j in i + 1..pieces.len() {
+                if let Some(merged) = union(&pieces[i], &pieces[j]) {
+                    if is_convex(&merged) {
+                        pieces[i] = merged;
+                        pieces.remove(j);
+                        merged_any = true;
+                        break 'search;
+                    }
+                }
+            }
+        }
+    }
+    pieces
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::PI;
+
+    /// Even-odd rule, and the distance from `p` to the boundary
+    fn reference(vertices: &[(f64, f64)], p: (f64, f64)) -> (bool, f64) {
+        let n = vertices.len();
+        let mut inside = false;
+        let mut distance = f64::INFINITY;
+        for k in 0..n {
+            let (v, w) = (vertices[k], vertices[(k + 1) % n]);
+            if (v.1 > p.1) != (w.1 > p.1) && p.0 < v.0 + (p.1 - v.1) * (w.0 - v.0) / (w.1 - v.1) {
+                inside = !inside;
+            }
+            let e = (w.0 - v.0, w.1 - v.1);
+            let t =
+                (((p.0 - v.0) * e.0 + (p.1 - v.1) * e.1) / (e.0 * e.0 + e.1 * e.1)).clamp(0.0, 1.0);
+            distance = distance.min((p.0 - v.0 - t * e.0).hypot(p.1 - v.1 - t * e.1));
+        }
+        (inside, distance)
+    }
+
+    fn assert_matches_polygon(vertices: &[(f64, f64)]) {
+        let centre = (1.0, -2.0);
+        let polygon = Polygon::new(vertices, centre).unwrap();
+        assert!(
+            polygon.pieces().len() > 1,
+            "the test needs a non-convex polygon"
+        );
+        let check = |p: (f64, f64)| {
+            let (inside, distance) = reference(vertices, p);
+            if distance > 1e-9 {
+                let x = [p.0 + centre.0, p.1 + centre.1];
+                assert_eq!(polygon.is_inside(&x), inside, "at {:?}", p);
+            }
+        };
+
+        // On a grid, across the seams
+        let (mut x, step) = (-3.0, 0.0123);
+        while x < 3.0 {
+            let mut y = -3.0;
+            while y < 3.0 {
+                check((x, y));
+                y += step;
+            }
+            x += step;
+        }
+        // Just off the boundary around the vertices (the ends of the
+        // diagonals), and on the boundary
+        let n = vertices.len();
+        for k in 0..n {
+            let (v, w) = (vertices[k], vertices[(k + 1) % n]);
+            for &r in &[1e-6, 1e-4, 1e-3, 1e-2] {
+                for j in 0..72 {
+                    let angle = 2.0 * PI * j as f64 / 72.0;
+                    check((v.0 + r * angle.cos(), v.1 + r * angle.sin()));
+                }
+            }
+            for j in 0..=20 {
+                let t = j as f64 / 20.0;
+                let p = [
+                    centre.0 + v.0 + t * (w.0 - v.0),
+                    centre.1 + v.1 + t * (w.1 - v.1),
+                ];
+                // Rounding puts `p` on either side of the edge
+                assert!(polygon.penalty(&p) < 1e-12, "boundary point {:?}", p);
+            }
+        }
+    }
+
+    #[test]
+    fn is_inside_matches_the_polygon() {
+        // L shape: one reflex vertex
+        assert_matches_polygon(&[
+            (0.0, 0.0),
+            (2.0, 0.0),
+            (2.0, 1.0),
+            (1.0, 1.0),
+            (1.0, 2.0),
+            (0.0, 2.0),
+        ]);
+        // Star: convex tips and reflex inner vertices
+        let star: Vec<_> = (0..10)
+            .map(|k| {
+                let r = if k % 2 == 0 { 2.0 } else { 0.8 };
+                let angle = PI * k as f64 / 5.0;
+                (r * angle.cos(), r * angle.sin())
+            })
+            .collect();
+        assert_matches_polygon(&star);
+        // Comb with thin teeth
+        assert_matches_polygon(&[
+            (-2.0, -1.0),
+            (2.0, -1.0),
+            (2.0, 1.5),
+            (1.5, 1.5),
+            (1.5, 0.0),
+            (0.5, 0.0),
+            (0.5, 1.5),
+            (-0.5, 1.5),
+            (-0.5, 0.0),
+            (-1.5, 0.0),
+            (-1.5, 1.5),
+            (-2.0, 1.5),
+        ]);
+    }
+}
@@ -0,0 +1,339 @@
+use crate::polygon::{halfspaces, normalise};
+use crate::{BoundingBox, Footprint, Obstacle, ObstacleError};
+
+/// Tolerance used for degenerate angles and areas
+const VERTEX_TOLERANCE: f64 = 1e-9;
+/// Half-width of the square that is clipped to obtain the vertices
+const CLIP_BOX: f64 = 1e6;
+
+/// Polyhedral obstacle `{x : a_i^T (x - c) < b_i for all i}`
+///
+/// `h(x) = prod_i max(0, b_i - a_i^T (x - c))`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Polyhedron {
+    halfspaces: Vec<((f64, f64), f64)>,
+    centre: (f64, f64),
+    vertices: Vec<(f64, f64)>, // Relative to the centre, counter-clockwise
+}
+
+impl Polyhedron {
+    /// `halfspaces`: rows `(a_i, b_i)`, `centre`: shift applied to all rows
+    pub fn new(halfspaces: Vec<((f64, f64), f64)>, centre: (f64, f64)) -> Self {
+        let vertices = clip_vertices(&halfspaces);
+        Polyhedron {
+            halfspaces,
+            centre,
+            vertices,
+        }
+    }
+
+    /// Convex polygon given by its vertices in order (either orientation),
+    /// relative to `centre`
+    ///
+    /// Fails if the polygon is degenerate, not simple or not convex (see
+    /// [`Polygon`](crate::Polygon) for non-convex polygons).
+    pub fn from_vertices(
+        vertices: &[(f64, f64)],
+        centre: (f64, f64),
+    ) -> Result<Self, ObstacleError> {
+        let polygon = normalise(vertices)?;
+        let n = polygon.len();
+        for k in 0..n {
+            let (u, v, w) = (
+                polygon[(k + n - 1) % n].1,
+                polygon[k].1,
+                polygon[(k + 1) % n].1,
+            );
+            if (v.0 - u.0) * (w.1 - v.1) - (v.1 - u.1) * (w.0 - v.0) < 0.0 {
+                return Err(ObstacleError::NonConvexPolygon(polygon[k].0));
+            }
+        }
+        let vertices: Vec<_> = polygon.into_iter().map(|v| v.1).collect();
+        Ok(Polyhedron::new(halfspaces(&vertices), centre))
+    }
+
+    pub fn halfspaces(&self) -> &[((f64, f64), f64)] {
+        &self.halfspaces
+    }
+
+    pub fn centre(&self) -> (f64, f64) {
+        self.centre
+    }
+
+    /// Minkowski sum with `-F` for the robot footprint `F`
+    ///
+    /// Every row is moved out by the support of `-F` along its normal; for a
+    /// bounded polyhedron and a polygonal footprint the edges of the footprint
+    /// are added as rows too, which makes the sum exact. The circle and the
+    /// clearance move every edge out by the radius of the disc, which is
+    /// conservative at the corners.
+    pub fn inflate(&self, footprint: &Footprint) -> Polyhedron {
+        let footprint_vertices = footprint.vertices();
+        let disc = footprint.disc_radius();
+        // Support of -F (plus the disc) along n
+        let support = |n: (f64, f64)| {
+            footprint_vertices
+                .iter()
+                .map(|v| -(n.0 * v.0 + n.1 * v.1))
+                .fold(f64::NEG_INFINITY, f64::max)
+                + disc * (n.0 * n.0 + n.1 * n.1).sqrt()
+        };
+        let mut halfspaces: Vec<_> = self
+            .halfspaces
+            .iter()
+            .map(|&(a, b)| (a, b + support(a)))
+            .collect();
+
+        let n = footprint_vertices.len();
+        if n >= 3 && self.is_bounded() {
+            let vertices = &self.vertices;
+            let mut area = 0.0;
+            for (k, v) in footprint_vertices.iter().enumerate() {
+                let w = footprint_vertices[(k + 1) % n];
+                area += v.0 * w.1 - w.0 * v.1;
+            }
+            for (k, v) in footprint_vertices.iter().enumerate() {
+                let w = footprint_vertices[(k + 1) % n];
+                // The edge (v, w) of F is the edge (-v, -w) of -F, whose outward
+                // normal is (e_1, -e_0) for e = v - w if F is counter-clockwise
+                let e = (v.0 - w.0, v.1 - w.1);
+                let a = if area > 0.0 { (e.1, -e.0) } else { (-e.1, e.0) };
+                if a == (0.0, 0.0) {
+                    continue;
+                }
+                let b = vertices
+                    .iter()
+                    .map(|o| a.0 * o.0 + a.1 * o.1)
+                    .fold(f64::NEG_INFINITY, f64::max);
+                halfspaces.push((a, b + support(a)));
+            }
+        }
+        Polyhedron::new(halfspaces, self.centre)
+    }
+
+    /// Row closest to `x` and the signed distance to its line, for `x` in the
+    /// closed polyhedron: `max_i (a_i^T (x - c) - b_i) / |a_i|`
+    fn deepest_row(&self, x: &[f64]) -> (usize, f64) {
+        let mut deepest = (0, f64::NEG_INFINITY);
+        for (i, &(a_i, b_i)) in self.halfspaces.iter().enumerate() {
+            let norm = (a_i.0 * a_i.0 + a_i.1 * a_i.1).sqrt();
+            let d_i =
+                (a_i.0 * (x[0] - self.centre.0) + a_i.1 * (x[1] - self.centre.1) - b_i) / norm;
+            if d_i > deepest.1 {
+                deepest = (i, d_i);
+            }
+        }
+        deepest
+    }
+
+    /// For `x` strictly outside: `x` and the closest point of the boundary
+    /// (relative to the centre)
+    fn closest_point(&self, x: &[f64]) -> Option<((f64, f64), (f64, f64))> {
+        if self.deepest_row(x).1 <= 0.0 {
+            return None;
+        }
+        let p = (x[0] - self.centre.0, x[1] - self.centre.1);
+        let n = self.vertices.len();
+        let mut closest = (f64::INFINITY, (0.0, 0.0));
+        for (k, &v) in self.vertices.iter().enumerate() {
+            let w = self.vertices[(k + 1) % n];
+            let e = (w.0 - v.0, w.1 - v.1);
+            let length2 = e.0 * e.0 + e.1 * e.1;
+            let t = if length2 > 0.0 {
+                (((p.0 - v.0) * e.0 + (p.1 - v.1) * e.1) / length2).clamp(0.0, 1.0)
+            } else {
+                0.0
+            };
+            let q = (v.0 + t * e.0, v.1 + t * e.1);
+            let distance2 = (p.0 - q.0).powi(2) + (p.1 - q.1).powi(2);
+            if distance2 < closest.0 {
+                closest = (distance2, q);
+            }
+        }
+        Some((p, closest.1))
+    }
+
+    /// Whether the half-spaces have no common interior
+    pub(crate) fn is_empty(&self) -> bool {
+        let vertices = &self.vertices;
+        let mut area = 0.0;
+        for (k, v) in vertices.iter().enumerate() {
+            let w = vertices[(k + 1) % vertices.len()];
+            area += v.0 * w.1 - w.0 * v.1;
+        }
+        area.abs() / 2.0 <= VERTEX_TOLERANCE
+    }
+
+    /// Whether the half-spaces enclose a bounded region
+    ///
+    /// Bounded iff the normals `a_i` are not all contained in a closed half-plane,
+    /// i.e., no angular gap between consecutive normals reaches `pi`.
+    fn is_bounded(&self) -> bool {
+        let mut angles: Vec<f64> = self
+            .halfspaces
+            .iter()
+            .map(|&((a0, a1), _)| a1.atan2(a0))
+            .collect();
+        if angles.len() < 3 {
+            return false;
+        }
+        angles.sort_by(|p, q| p.partial_cmp(q).unwrap());
+        let wrap_gap = angles[0] + 2.0 * std::f64::consts::PI - angles[angles.len() - 1];
+        let max_gap = angles
+            .windows(2)
+            .map(|w| w[1] - w[0])
+            .fold(wrap_gap, f64::max);
+        max_gap < std::f64::consts::PI - VERTEX_TOLERANCE
+    }
+}
+
+impl Obstacle for Polyhedron {
+    fn penalty(&self, x: &[f64]) -> f64 {
+        let mut h: f64 = 1.0;
+        for &(a_i, b_i) in &self.halfspaces {
+            h *= (b_i - (a_i.0 * (x[0] - self.centre.0) + a_i.1 * (x[1] - self.centre.1))).max(0.0);
+        }
+        h
+    }
+
+    fn gradient(&self, x: &[f64], grad: &mut [f64]) {
+        // For h = prod_i s_i with all s_i > 0: dh = h * sum_i (ds_i / s_i), ds_i = -a_i
+        grad[0] = 0.0;
+        grad[1] = 0.0;
+        let h = self.penalty(x);
+        if h <= 0.0 {
+            return;
+        }
+        for &(a_i, b_i) in &self.halfspaces {
+            let s_i = b_i - (a_i.0 * (x[0] - self.centre.0) + a_i.1 * (x[1] - self.centre.1));
+            grad[0] -= h * a_i.0 / s_i;
+            grad[1] -= h * a_i.1 / s_i;
+        }
+    }
+
+    fn signed_distance(&self, x: &[f64]) -> f64 {
+        match self.closest_point(x) {
+            Some((p, closest)) => ((p.0 - closest.0).powi(2) + (p.1 - closest.1).powi(2)).sqrt(),
+            None => self.deepest_row(x).1,
+        }
+    }
+
+    fn signed_distance_gradient(&self, x: &[f64], grad: &mut [f64]) {
+        match self.closest_point(x) {
+            Some((p, closest)) => {
+                let (d0, d1) = (p.0 - closest.0, p.1 - closest.1);
+                let norm = (d0 * d0 + d1 * d1).sqrt();
+                grad[0] = d0 / norm;
+                grad[1] = d1 / norm;
+            }
+            None => {
+                let ((a0, a1), _) = self.halfspaces[self.deepest_row(x).0];
+                let norm = (a0 * a0 + a1 * a1).sqrt();
+                grad[0] = a0 / norm;
+                grad[1] = a1 / norm;
+            }
+        }
+    }
+
+    fn bounding_box(&self) -> BoundingBox {
+        if !self.is_bounded() {
+            return BoundingBox::unbounded();
+        }
+        let vertices = &self.vertices;
+        if vertices.is_empty() {
+            // Bounded but empty: the obstacle has no interior
+            return BoundingBox::new(
+                (self.centre.0, self.centre.0),
+                (self.centre.1, self.centre.1),
+            );
+        }
+        let (mut x_min, mut x_max) = (f64::INFINITY, f64::NEG_INFINITY);
+        let (mut y_min, mut y_max) = (f64::INFINITY, f64::NEG_INFINITY);
+        for &(vx, vy) in vertices {
+            x_min = x_min.min(vx);
+            x_max = x_max.max(vx);
+            y_min = y_min.min(vy);
+            y_max = y_max.max(vy);
+        }
+        BoundingBox::new(
+            (self.centre.0 + x_min, self.centre.0 + x_max),
+            (self.centre.1 + y_min, self.centre.1 + y_max),
+        )
+    }
+}
+
+/// Vertices of the polyhedron (relative to the centre), counter-clockwise
+///
+/// Obtained by clipping a large square (half-width `CLIP_BOX`) with every
+/// half-space, so unbounded polyhedra get vertices on the square.
+fn clip_vertices(halfspaces: &[((f64, f64), f64)]) -> Vec<(f64, f64)> {
+    let mut polygon = vec![
+        (-CLIP_BOX, -CLIP_BOX),
+        (CLIP_BOX, -CLIP_BOX),
+        (CLIP_BOX, CLIP_BOX),
+        (-CLIP_BOX, CLIP_BOX),
+    ];
+    for &((a0, a1), b) in halfspaces {
+        let slack = |v: (f64, f64)| b - (a0 * v.0 + a1 * v.1);
+        let mut clipped = Vec::with_capacity(polygon.len() + 1);
+        for (k, &v) in polygon.iter().enumerate() {
+            let w = polygon[(k + 1) % polygon.len()];
+            let (s_v, s_w) = (slack(v), slack(w));
+            if s_v >= 0.0 {
+                clipped.push(v);
+            }
+            if (s_v >= 0.0) != (s_w >= 0.0) {
+                let t = s_v / (s_v - s_w);
+                clipped.push((v.0 + t * (w.0 - v.0), v.1 + t * (w.1 - v.1)));
+            }
+        }
+        polygon = clipped;
+        if polygon.is_empty() {
+            break;
+        }
+    }
+    polygon
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::assert_gradient;
+
+    /// `h_poly` of Example 10 before the shapes were extracted
+    fn h_poly(x: &[f64], poly: &[((f64, f64), f64)], centre: (f64, f64)) -> f64 {
+        let mut h: f64 = 1.0;
+        for &(a_i, b_i) in poly {
+            h *= (b_i - (a_i.0 * (x[0] - centre.0) + a_i.1 * (x[1] - centre.1))).max(0.0);
+        }
+        h
+    }
+
+    /// `poly_B` of Example 10
+    fn poly_b() -> Vec<((f64, f64), f64)> {
+        vec![
+            ((1.0, 2.0), 2.0),
+            ((-2.0, 1.0), 2.0),
+            ((-1.0, -1.0), 2.0),
+            ((1.0, -1.0), 2.0),
+            ((0.0, -1.0), 1.5),
+        ]
+    }
+
+    const POINTS: [[f64; 2]; 5] = [[0.0, 5.0], [0.3, 4.6], [-0.4, 5.2], [3.0, 5.0], [0.0, 2.0]];
+
+    #[test]
+    fn penalty_matches_baseline() {
+        let polyhedron = Polyhedron::new(poly_b(), (0.0, 5.0));
+        for x in POINTS.iter() {
+            let h = h_poly(x, &poly_b(), (0.0, 5.0));
+            assert_eq!(polyhedron.penalty(x), h);
+            assert_eq!(polyhedron.is_inside(x), h > 0.0);
+        }
+    }
+
+    #[test]
+    fn gradient_matches_finite_differences() {
+        assert_gradient(&Polyhedron::new(poly_b(), (0.0, 5.0)), &POINTS);
+    }
+}
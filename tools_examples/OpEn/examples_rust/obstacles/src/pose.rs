@@ -0,0 +1,275 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{BoundingBox, Obstacle};
+
+/// Rigid 2D transform: rotation by `angle` (rad, counter-clockwise) about the
+/// origin, then translation by `position`
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Pose {
+    pub position: (f64, f64),
+    pub angle: f64,
+}
+
+impl Pose {
+    pub fn new(position: (f64, f64), angle: f64) -> Self {
+        Pose { position, angle }
+    }
+
+    /// No rotation and no translation
+    pub fn identity() -> Self {
+        Pose::new((0.0, 0.0), 0.0)
+    }
+
+    /// Point `x` of the shape's frame in the world frame
+    pub fn apply(&self, x: (f64, f64)) -> (f64, f64) {
+        let (x0, x1) = self.rotate(x);
+        (x0 + self.position.0, x1 + self.position.1)
+    }
+
+    /// Point `x` of the world frame in the shape's frame
+    pub fn apply_inverse(&self, x: (f64, f64)) -> (f64, f64) {
+        let (s, c) = self.angle.sin_cos();
+        let (d0, d1) = (x.0 - self.position.0, x.1 - self.position.1);
+        (c * d0 + s * d1, -s * d0 + c * d1)
+    }
+
+    /// Vector `v` of the shape's frame in the world frame (rotation only)
+    pub fn rotate(&self, v: (f64, f64)) -> (f64, f64) {
+        let (s, c) = self.angle.sin_cos();
+        (c * v.0 - s * v.1, s * v.0 + c * v.1)
+    }
+}
+
+impl Default for Pose {
+    fn default() -> Self {
+        Pose::identity()
+    }
+}
+
+/// Shape placed by a `Pose`
+///
+/// The shape is described in its own frame; at `x` the penalty is `h(T^-1 x)`
+/// and the gradients are rotated back into the world frame. Rigid transforms
+/// preserve distances, so the signed distance stays exact.
+pub struct PosedObstacle {
+    shape: Box<dyn Obstacle>,
+    pose: Pose,
+}
+
+impl PosedObstacle {
+    pub fn new(shape: Box<dyn Obstacle>, pose: Pose) -> Self {
+        PosedObstacle { shape, pose }
+    }
+
+    /// Shape in its own frame
+    pub fn shape(&self) -> &dyn Obstacle {
+        self.shape.as_ref()
+    }
+
+    pub fn pose(&self) -> Pose {
+        self.pose
+    }
+
+    fn relative(&self, x: &[f64]) -> [f64; 2] {
+        let p = self.pose.apply_inverse((x[0], x[1]));
+        [p.0, p.1]
+    }
+
+    /// Gradient in the shape's frame -> gradient in the world frame
+    fn rotate_gradient(&self, grad: &mut [f64]) {
+        let g = self.pose.rotate((grad[0], grad[1]));
+        grad[0] = g.0;
+        grad[1] = g.1;
+    }
+}
+
+impl Obstacle for PosedObstacle {
+    fn penalty(&self, x: &[f64]) -> f64 {
+        self.shape.penalty(&self.relative(x))
+    }
+
+    fn gradient(&self, x: &[f64], grad: &mut [f64]) {
+        self.shape.gradient(&self.relative(x), grad);
+        self.rotate_gradient(grad);
+    }
+
+    fn signed_distance(&self, x: &[f64]) -> f64 {
+        self.shape.signed_distance(&self.relative(x))
+    }
+
+    fn signed_distance_gradient(&self, x: &[f64], grad: &mut [f64]) {
+        self.shape.signed_distance_gradient(&self.relative(x), grad);
+        self.rotate_gradient(grad);
+    }
+
+    fn is_inside(&self, x: &[f64]) -> bool {
+        self.shape.is_inside(&self.relative(x))
+    }
+
+    // Box around the corners of the shape's box after the transform
+    fn bounding_box(&self) -> BoundingBox {
+        let bbox = self.shape.bounding_box();
+        let (x_range, y_range) = (bbox.x_range(), bbox.y_range());
+        let bounded = [x_range.0, x_range.1, y_range.0, y_range.1]
+            .iter()
+            .all(|v| v.is_finite());
+        if !bounded && self.pose.angle.sin() != 0.0 {
+            return BoundingBox::unbounded();
+        }
+        let corners = [
+            (x_range.0, y_range.0),
+            (x_range.1, y_range.0),
+            (x_range.1, y_range.1),
+            (x_range.0, y_range.1),
+        ];
+        let mut world = BoundingBox::new(
+            (f64::INFINITY, f64::NEG_INFINITY),
+            (f64::INFINITY, f64::NEG_INFINITY),
+        );
+        for &corner in &corners {
+            let (x0, x1) = if bounded {
+                self.pose.apply(corner)
+            } else {
+                // No rotation (or a half-turn): keep the infinite bounds out of
+                // the products
+                let (c, p) = (self.pose.angle.cos(), self.pose.position);
+                (c * corner.0 + p.0, c * corner.1 + p.1)
+            };
+            world = world.union(&BoundingBox::new((x0, x0), (x1, x1)));
+        }
+        world
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::assert_gradient;
+    use crate::{Ellipse, Polyhedron};
+    use std::f64::consts::{FRAC_PI_2, FRAC_PI_4, PI};
+
+    fn square() -> Polyhedron {
+        Polyhedron::new(
+            vec![
+                ((1.0, 0.0), 1.0),
+                ((-1.0, 0.0), 1.0),
+                ((0.0, 1.0), 1.0),
+                ((0.0, -1.0), 1.0),
+            ],
+            (0.0, 0.0),
+        )
+    }
+
+    fn assert_range(actual: (f64, f64), expected: (f64, f64)) {
+        assert!(
+            (actual.0 - expected.0).abs() < 1e-9 && (actual.1 - expected.1).abs() < 1e-9,
+            "{:?} != {:?}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn pose_round_trips() {
+        let pose = Pose::new((1.0, -2.0), 0.7);
+        let x = (0.3, 1.5);
+        let back = pose.apply_inverse(pose.apply(x));
+        assert!((back.0 - x.0).abs() < 1e-12 && (back.1 - x.1).abs() < 1e-12);
+        assert_eq!(Pose::default(), Pose::identity());
+    }
+
+    #[test]
+    fn rotated_ellipse_matches_the_rotated_query() {
+        let ellipse = Ellipse::new((0.0, 0.0), (2.0, 1.0));
+        let posed = PosedObstacle::new(Box::new(ellipse.clone()), Pose::new((1.0, 2.0), FRAC_PI_2));
+        // A quarter turn maps the long axis onto y: (1, 3.5) is (1.5, 0) in
+        // the frame of the ellipse
+        assert!((posed.penalty(&[1.0, 3.5]) - 0.4375).abs() < 1e-12);
+        assert!((posed.penalty(&[1.0, 3.5]) - ellipse.penalty(&[1.5, 0.0])).abs() < 1e-12);
+        assert_eq!(posed.penalty(&[2.5, 2.0]), 0.0);
+        assert!(posed.is_inside(&[1.0, 3.5]));
+        assert!(!posed.is_inside(&[2.5, 2.0]));
+
+        let (s, c) = 0.7_f64.sin_cos();
+        let posed = PosedObstacle::new(Box::new(ellipse.clone()), Pose::new((1.0, 2.0), 0.7));
+        for &(p0, p1) in &[(0.5, 0.3), (-1.2, 0.4), (2.5, -1.0)] {
+            let q = [1.0 + c * p0 - s * p1, 2.0 + s * p0 + c * p1];
+            assert!((posed.penalty(&q) - ellipse.penalty(&[p0, p1])).abs() < 1e-12);
+            assert!((posed.signed_distance(&q) - ellipse.signed_distance(&[p0, p1])).abs() < 1e-9);
+        }
+        assert_gradient(&posed, &[[1.3, 2.4], [0.2, 1.5], [2.0, 2.9]]);
+    }
+
+    #[test]
+    fn rotated_polyhedron_matches_the_rotated_query() {
+        let (s, c) = FRAC_PI_4.sin_cos();
+        let posed = PosedObstacle::new(Box::new(square()), Pose::new((3.0, 0.0), FRAC_PI_4));
+        // (0.5, 0.25) in the frame of the square
+        let inside = [3.0 + 0.5 * c - 0.25 * s, 0.5 * s + 0.25 * c];
+        assert!((posed.penalty(&inside) - 0.5 * 1.5 * 0.75 * 1.25).abs() < 1e-12);
+        // (2, 0) is one away from the face x = 1
+        let outside = [3.0 + 2.0 * c, 2.0 * s];
+        assert!((posed.signed_distance(&outside) - 1.0).abs() < 1e-9);
+        let mut grad = [0.0; 2];
+        posed.signed_distance_gradient(&outside, &mut grad);
+        assert!((grad[0] - c).abs() < 1e-9 && (grad[1] - s).abs() < 1e-9);
+        assert_gradient(&posed, &[inside, [3.2, 0.5], [2.6, -0.4]]);
+    }
+
+    #[test]
+    fn bounding_box_encloses_the_rotated_shape() {
+        let ellipse = Box::new(Ellipse::new((0.0, 0.0), (2.0, 1.0)));
+        let bbox = PosedObstacle::new(ellipse, Pose::new((1.0, 2.0), FRAC_PI_2)).bounding_box();
+        assert_range(bbox.x_range(), (0.0, 2.0));
+        assert_range(bbox.y_range(), (0.0, 4.0));
+
+        // The corners of the box of the square, turned by 45 degrees
+        let bbox =
+            PosedObstacle::new(Box::new(square()), Pose::new((3.0, 0.0), FRAC_PI_4)).bounding_box();
+        let half_diagonal = 2.0_f64.sqrt();
+        assert_range(bbox.x_range(), (3.0 - half_diagonal, 3.0 + half_diagonal));
+        assert_range(bbox.y_range(), (-half_diagonal, half_diagonal));
+    }
+
+    /// Strip `|y| < 1`, unbounded along `x`
+    struct Strip;
+
+    impl Obstacle for Strip {
+        fn penalty(&self, x: &[f64]) -> f64 {
+            (1.0 - x[1] * x[1]).max(0.0)
+        }
+
+        fn gradient(&self, x: &[f64], grad: &mut [f64]) {
+            grad[0] = 0.0;
+            grad[1] = if self.penalty(x) > 0.0 {
+                -2.0 * x[1]
+            } else {
+                0.0
+            };
+        }
+
+        fn signed_distance(&self, x: &[f64]) -> f64 {
+            x[1].abs() - 1.0
+        }
+
+        fn signed_distance_gradient(&self, x: &[f64], grad: &mut [f64]) {
+            grad[0] = 0.0;
+            grad[1] = x[1].signum();
+        }
+
+        fn bounding_box(&self) -> BoundingBox {
+            BoundingBox::new((f64::NEG_INFINITY, f64::INFINITY), (-1.0, 1.0))
+        }
+    }
+
+    #[test]
+    fn unbounded_shapes_stay_unbounded_when_rotated() {
+        let bbox = PosedObstacle::new(Box::new(Strip), Pose::new((2.0, 3.0), 0.0)).bounding_box();
+        assert_eq!(bbox.x_range(), (f64::NEG_INFINITY, f64::INFINITY));
+        assert_eq!(bbox.y_range(), (2.0, 4.0));
+        for &angle in &[0.3, FRAC_PI_2, PI] {
+            let posed = PosedObstacle::new(Box::new(Strip), Pose::new((2.0, 3.0), angle));
+            assert_eq!(posed.bounding_box(), BoundingBox::unbounded());
+        }
+    }
+}
@@ -0,0 +1,765 @@
+//! Shapes in any dimension (spheres, ellipsoids, boxes and polytopes), e.g.
+//! for 3D planning
+
+use nalgebra::{DMatrix, DVector, SVector};
+
+use crate::{BoundingBox, Obstacle};
+
+/// Half-width of the box that clips a polytope to obtain its vertices
+const CLIP_BOX: f64 = 1e6;
+/// Tolerance used for degenerate polytopes
+const VERTEX_TOLERANCE: f64 = 1e-9;
+
+fn vector<const D: usize>(x: &[f64]) -> SVector<f64, D> {
+    SVector::from_column_slice(x)
+}
+
+fn write_vector<const D: usize>(v: &SVector<f64, D>, grad: &mut [f64]) {
+    grad.copy_from_slice(v.as_slice());
+}
+
+/// Ball `{x : |x - c| < r}`
+///
+/// `h(x) = max(0, r^2 - |x - c|^2)`, the circle of Example 09 in `D` dimensions.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sphere<const D: usize> {
+    centre: SVector<f64, D>,
+    radius: f64,
+}
+
+impl<const D: usize> Sphere<D> {
+    pub fn new(centre: SVector<f64, D>, radius: f64) -> Self {
+        Sphere { centre, radius }
+    }
+
+    pub fn centre(&self) -> &SVector<f64, D> {
+        &self.centre
+    }
+
+    pub fn radius(&self) -> f64 {
+        self.radius
+    }
+}
+
+impl<const D: usize> Obstacle<D> for Sphere<D> {
+    fn penalty(&self, x: &[f64]) -> f64 {
+        (self.radius.powi(2) - (vector(x) - self.centre).norm_squared()).max(0.0)
+    }
+
+    fn gradient(&self, x: &[f64], grad: &mut [f64]) {
+        let d = vector(x) - self.centre;
+        if self.radius.powi(2) - d.norm_squared() > 0.0 {
+            write_vector(&(-2.0 * d), grad);
+        } else {
+            grad.fill(0.0);
+        }
+    }
+
+    fn signed_distance(&self, x: &[f64]) -> f64 {
+        (vector(x) - self.centre).norm() - self.radius
+    }
+
+    fn signed_distance_gradient(&self, x: &[f64], grad: &mut [f64]) {
+        let d = vector(x) - self.centre;
+        let norm = d.norm();
+        if norm > 0.0 {
+            write_vector(&(d / norm), grad);
+        } else {
+            // Any direction at the centre
+            grad.fill(0.0);
+            grad[0] = 1.0;
+        }
+    }
+
+    fn bounding_box(&self) -> BoundingBox<D> {
+        let mut ranges = [(0.0, 0.0); D];
+        for (i, range) in ranges.iter_mut().enumerate() {
+            *range = (self.centre[i] - self.radius, self.centre[i] + self.radius);
+        }
+        BoundingBox::from_ranges(ranges)
+    }
+}
+
+/// Axis-aligned ellipsoid
+///
+/// `h(x) = max(0, 1 - sum_i ((x_i - c_i) / r_i)^2)`, the ellipse of Example 11
+/// in `D` dimensions (rotate it with the planner's frame if needed).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Ellipsoid<const D: usize> {
+    centre: SVector<f64, D>,
+    radii: SVector<f64, D>,
+}
+
+impl<const D: usize> Ellipsoid<D> {
+    pub fn new(centre: SVector<f64, D>, radii: SVector<f64, D>) -> Self {
+        Ellipsoid { centre, radii }
+    }
+
+    pub fn centre(&self) -> &SVector<f64, D> {
+        &self.centre
+    }
+
+    pub fn radii(&self) -> &SVector<f64, D> {
+        &self.radii
+    }
+
+    /// `x` relative to the centre, and the closest point of the boundary
+    /// (relative to the centre)
+    ///
+    /// Eberly's bisection on the Lagrange multiplier `t`, with the closest
+    /// point `y_i = r_i^2 x_i / (t + r_i^2)` on the ellipsoid, in the first
+    /// orthant. If `x` has no component along the shortest axes, the closest
+    /// point may leave the plane they span.
+    fn closest_point(&self, x: &[f64]) -> (SVector<f64, D>, SVector<f64, D>) {
+        let p = vector(x) - self.centre;
+        let e = &self.radii;
+        let y = p.abs();
+        let e_min = e.min();
+        let shortest = |i: usize| e[i] == e_min;
+        let level = |t: f64| -> f64 {
+            (0..D)
+                .filter(|&i| y[i] > 0.0)
+                .map(|i| (e[i] * y[i] / (t + e[i] * e[i])).powi(2))
+                .sum()
+        };
+
+        let mut closest = SVector::<f64, D>::zeros();
+        if (0..D).all(|i| !shortest(i) || y[i] == 0.0) {
+            // Closest point off the plane of the longer axes, if that is where x is
+            let mut s = 0.0;
+            for i in (0..D).filter(|&i| !shortest(i)) {
+                closest[i] = e[i] * e[i] * y[i] / (e[i] * e[i] - e_min * e_min);
+                s += (closest[i] / e[i]).powi(2);
+            }
+            if s < 1.0 {
+                let k = (0..D).find(|&i| shortest(i)).unwrap();
+                closest[k] = e_min * (1.0 - s).sqrt();
+                return (p, closest.zip_map(&p, |c, p_i| c.copysign(p_i)));
+            }
+        }
+
+        // level is decreasing on (-e_min^2, inf), and level(t) = 1 at the root
+        let mut t0 = -e_min * e_min;
+        let mut t1 = if level(0.0) <= 1.0 {
+            0.0
+        } else {
+            e.max() * y.norm()
+        };
+        let mut t = t1;
+        // The interval halves at every iteration, so this reaches machine precision
+        for _ in 0..1100 {
+            t = (t0 + t1) / 2.0;
+            if t == t0 || t == t1 {
+                break;
+            }
+            let g = level(t) - 1.0;
+            if g > 0.0 {
+                t0 = t;
+            } else if g < 0.0 {
+                t1 = t;
+            } else {
+                break;
+            }
+        }
+        for i in 0..D {
+            closest[i] = if y[i] > 0.0 {
+                e[i] * e[i] * y[i] / (t + e[i] * e[i])
+            } else {
+                0.0
+            };
+        }
+        (p, closest.zip_map(&p, |c, p_i| c.copysign(p_i)))
+    }
+}
+
+impl<const D: usize> Obstacle<D> for Ellipsoid<D> {
+    fn penalty(&self, x: &[f64]) -> f64 {
+        let d = (vector(x) - self.centre).component_div(&self.radii);
+        (1.0 - d.norm_squared()).max(0.0)
+    }
+
+    fn gradient(&self, x: &[f64], grad: &mut [f64]) {
+        if self.penalty(x) > 0.0 {
+            let d = vector(x) - self.centre;
+            let r2 = self.radii.component_mul(&self.radii);
+            write_vector(&(-2.0 * d.component_div(&r2)), grad);
+        } else {
+            grad.fill(0.0);
+        }
+    }
+
+    fn signed_distance(&self, x: &[f64]) -> f64 {
+        let (p, closest) = self.closest_point(x);
+        let distance = (p - closest).norm();
+        if self.is_inside(x) {
+            -distance
+        } else {
+            distance
+        }
+    }
+
+    fn signed_distance_gradient(&self, x: &[f64], grad: &mut [f64]) {
+        // Unit normal at the closest point, pointing out of the ellipsoid
+        let (p, closest) = self.closest_point(x);
+        let mut n = p - closest;
+        if self.is_inside(x) {
+            n = -n;
+        }
+        if n.norm() == 0.0 {
+            n = closest.component_div(&self.radii.component_mul(&self.radii));
+        }
+        write_vector(&n.normalize(), grad);
+    }
+
+    fn bounding_box(&self) -> BoundingBox<D> {
+        let mut ranges = [(0.0, 0.0); D];
+        for (i, range) in ranges.iter_mut().enumerate() {
+            *range = (
+                self.centre[i] - self.radii[i],
+                self.centre[i] + self.radii[i],
+            );
+        }
+        BoundingBox::from_ranges(ranges)
+    }
+}
+
+/// Axis-aligned box `{x : |x_i - c_i| < w_i}` with half-widths `w`
+///
+/// `h(x) = prod_i max(0, w_i^2 - (x_i - c_i)^2)`, i.e. the polytope of its
+/// `2 D` faces.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cuboid<const D: usize> {
+    centre: SVector<f64, D>,
+    half_widths: SVector<f64, D>,
+}
+
+impl<const D: usize> Cuboid<D> {
+    pub fn new(centre: SVector<f64, D>, half_widths: SVector<f64, D>) -> Self {
+        Cuboid {
+            centre,
+            half_widths,
+        }
+    }
+
+    pub fn centre(&self) -> &SVector<f64, D> {
+        &self.centre
+    }
+
+    pub fn half_widths(&self) -> &SVector<f64, D> {
+        &self.half_widths
+    }
+
+    /// Factors `w_i^2 - (x_i - c_i)^2` of the penalty
+    fn factors(&self, x: &[f64]) -> SVector<f64, D> {
+        let d = vector(x) - self.centre;
+        self.half_widths.zip_map(&d, |w, d_i| w * w - d_i * d_i)
+    }
+}
+
+impl<const D: usize> Obstacle<D> for Cuboid<D> {
+    fn penalty(&self, x: &[f64]) -> f64 {
+        self.factors(x).map(|f| f.max(0.0)).product()
+    }
+
+    fn gradient(&self, x: &[f64], grad: &mut [f64]) {
+        let f = self.factors(x);
+        if f.iter().all(|&f_i| f_i > 0.0) {
+            let d = vector(x) - self.centre;
+            let product = f.product();
+            for i in 0..D {
+                grad[i] = -2.0 * d[i] * product / f[i];
+            }
+        } else {
+            grad.fill(0.0);
+        }
+    }
+
+    fn signed_distance(&self, x: &[f64]) -> f64 {
+        // q_i > 0 along the axes where x is beyond the faces
+        let q = (vector(x) - self.centre).abs() - self.half_widths;
+        let outside = q.map(|q_i| q_i.max(0.0)).norm();
+        if outside > 0.0 {
+            outside
+        } else {
+            q.max()
+        }
+    }
+
+    fn signed_distance_gradient(&self, x: &[f64], grad: &mut [f64]) {
+        let d = vector(x) - self.centre;
+        let q = d.abs() - self.half_widths;
+        let outside = q.map(|q_i| q_i.max(0.0));
+        let norm = outside.norm();
+        if norm > 0.0 {
+            write_vector(
+                &outside.zip_map(&d, |o, d_i| (o / norm).copysign(d_i)),
+                grad,
+            );
+        } else {
+            // Normal of the closest face
+            let k = q.imax();
+            grad.fill(0.0);
+            grad[k] = 1.0_f64.copysign(d[k]);
+        }
+    }
+
+    fn bounding_box(&self) -> BoundingBox<D> {
+        let mut ranges = [(0.0, 0.0); D];
+        for (i, range) in ranges.iter_mut().enumerate() {
+            *range = (
+                self.centre[i] - self.half_widths[i],
+                self.centre[i] + self.half_widths[i],
+            );
+        }
+        BoundingBox::from_ranges(ranges)
+    }
+}
+
+/// Polytope `{x : a_i^T (x - c) < b_i for all i}`, the polyhedron of Example
+/// 11 in `D` dimensions
+///
+/// `h(x) = prod_i max(0, b_i - a_i^T (x - c))`. The signed distance
+/// `max_i (a_i^T (x - c) - b_i) / |a_i|` is exact inside and wherever the
+/// closest point lies on a face, and underestimates the distance to edges and
+/// vertices.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Polytope<const D: usize> {
+    halfspaces: Vec<(SVector<f64, D>, f64)>,
+    centre: SVector<f64, D>,
+    bounding_box: BoundingBox<D>, // Empty ranges if the polytope is empty
+    has_interior: bool,
+}
+
+impl<const D: usize> Polytope<D> {
+    /// `halfspaces`: rows `(a_i, b_i)`, `centre`: shift applied to all rows
+    pub fn new(halfspaces: Vec<(SVector<f64, D>, f64)>, centre: SVector<f64, D>) -> Self {
+        let (bounding_box, has_interior) = clip(&halfspaces, &centre);
+        Polytope {
+            halfspaces,
+            centre,
+            bounding_box,
+            has_interior,
+        }
+    }
+
+    pub fn halfspaces(&self) -> &[(SVector<f64, D>, f64)] {
+        &self.halfspaces
+    }
+
+    pub fn centre(&self) -> &SVector<f64, D> {
+        &self.centre
+    }
+
+    /// Whether the half-spaces have no common interior
+    pub fn is_empty(&self) -> bool {
+        !self.has_interior
+    }
+
+    /// Values `b_i - a_i^T (x - c)`, positive inside
+    fn factors(&self, x: &[f64]) -> Vec<f64> {
+        let p = vector(x) - self.centre;
+        self.halfspaces
+            .iter()
+            .map(|(a_i, b_i)| b_i - a_i.dot(&p))
+            .collect()
+    }
+
+    /// Row with the largest scaled violation, and that violation
+    fn deepest_row(&self, x: &[f64]) -> (usize, f64) {
+        let mut deepest = (0, f64::NEG_INFINITY);
+        for (i, f_i) in self.factors(x).iter().enumerate() {
+            let d_i = -f_i / self.halfspaces[i].0.norm();
+            if d_i > deepest.1 {
+                deepest = (i, d_i);
+            }
+        }
+        deepest
+    }
+}
+
+impl<const D: usize> Obstacle<D> for Polytope<D> {
+    fn penalty(&self, x: &[f64]) -> f64 {
+        self.factors(x).iter().map(|f_i| f_i.max(0.0)).product()
+    }
+
+    fn gradient(&self, x: &[f64], grad: &mut [f64]) {
+        let f = self.factors(x);
+        grad.fill(0.0);
+        if f.iter().all(|&f_i| f_i > 0.0) {
+            for (i, (a_i, _)) in self.halfspaces.iter().enumerate() {
+                let others: f64 = f
+                    .iter()
+                    .enumerate()
+                    .filter(|&(j, _)| j != i)
+                    .map(|(_, f_j)| f_j)
+                    .product();
+                for (g, a_ik) in grad.iter_mut().zip(a_i.iter()) {
+                    *g -= others * a_ik;
+                }
+            }
+        }
+    }
+
+    fn signed_distance(&self, x: &[f64]) -> f64 {
+        self.deepest_row(x).1
+    }
+
+    fn signed_distance_gradient(&self, x: &[f64], grad: &mut [f64]) {
+        let a = &self.halfspaces[self.deepest_row(x).0].0;
+        write_vector(&a.normalize(), grad);
+    }
+
+    fn bounding_box(&self) -> BoundingBox<D> {
+        self.bounding_box
+    }
+}
+
+/// Bounding box of the polytope (infinite where it is unbounded) and whether
+/// it has an interior, from its vertices after clipping it with a large box
+fn clip<const D: usize>(
+    halfspaces: &[(SVector<f64, D>, f64)],
+    centre: &SVector<f64, D>,
+) -> (BoundingBox<D>, bool) {
+    let mut rows = halfspaces.to_vec();
+    for k in 0..D {
+        let e_k = SVector::<f64, D>::from_fn(|r, _| if r == k { 1.0 } else { 0.0 });
+        rows.push((e_k, CLIP_BOX));
+        rows.push((-e_k, CLIP_BOX));
+    }
+    let scale: f64 = rows
+        .iter()
+        .map(|(a, b)| b.abs() / a.norm())
+        .fold(1.0, f64::max);
+
+    // Every choice of D rows whose lines meet at a point inside all rows
+    let mut vertices: Vec<SVector<f64, D>> = Vec::new();
+    let mut chosen = Vec::with_capacity(D);
+    choose(rows.len(), D, 0, &mut chosen, &mut |chosen: &[usize]| {
+        // Dynamic matrices, as nalgebra only decomposes square matrices of known size
+        let a = DMatrix::<f64>::from_fn(D, D, |r, c| rows[chosen[r]].0[c]);
+        let b = DVector::<f64>::from_fn(D, |r, _| rows[chosen[r]].1);
+        if let Some(v) = a.lu().solve(&b).map(|v| vector::<D>(v.as_slice())) {
+            if rows
+                .iter()
+                .all(|(a_i, b_i)| a_i.dot(&v) <= b_i + VERTEX_TOLERANCE * scale * a_i.norm())
+            {
+                vertices.push(v);
+            }
+        }
+    });
+
+    if vertices.is_empty() {
+        return (BoundingBox::from_ranges([(0.0, -1.0); D]), false);
+    }
+    // The centroid of the vertices is interior unless the polytope is flat
+    let centroid = vertices.iter().sum::<SVector<f64, D>>() / vertices.len() as f64;
+    let has_interior = rows
+        .iter()
+        .all(|(a_i, b_i)| a_i.dot(&centroid) < b_i - VERTEX_TOLERANCE * scale * a_i.norm());
+
+    let mut ranges = [(f64::INFINITY, f64::NEG_INFINITY); D];
+    for v in &vertices {
+        for (k, range) in ranges.iter_mut().enumerate() {
+            *range = (range.0.min(v[k]), range.1.max(v[k]));
+        }
+    }
+    for (k, range) in ranges.iter_mut().enumerate() {
+        // Vertices on the clipping box belong to unbounded directions
+        if range.0 <= -CLIP_BOX * (1.0 - VERTEX_TOLERANCE) {
+            range.0 = f64::NEG_INFINITY;
+        } else {
+            range.0 += centre[k];
+        }
+        if range.1 >= CLIP_BOX * (1.0 - VERTEX_TOLERANCE) {
+            range.1 = f64::INFINITY;
+        } else {
+            range.1 += centre[k];
+        }
+    }
+    (BoundingBox::from_ranges(ranges), has_interior)
+}
+
+/// Calls `f` with every increasing choice of `k` indices out of `0..n`
+fn choose(n: usize, k: usize, start: usize, chosen: &mut Vec<usize>, f: &mut impl FnMut(&[usize])) {
+    if chosen.len() == k {
+        f(chosen);
+        return;
+    }
+    for i in start..n {
+        chosen.push(i);
+        choose(n, k, i + 1, chosen, f);
+        chosen.pop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::assert_gradient;
+    use nalgebra::Vector3;
+
+    fn assert_close(actual: f64, expected: f64, tolerance: f64) {
+        assert!(
+            (actual - expected).abs() <= tolerance,
+            "{} != {}",
+            actual,
+            expected
+        );
+    }
+
+    /// Asserts that the gradient of the signed distance matches central
+    /// differences at `points` (away from its kinks)
+    fn assert_distance_gradient<O: Obstacle<3>>(obstacle: &O, points: &[[f64; 3]]) {
+        let step = 1e-6;
+        for x in points {
+            let mut grad = [0.0; 3];
+            obstacle.signed_distance_gradient(x, &mut grad);
+            for i in 0..3 {
+                let (mut x_plus, mut x_minus) = (*x, *x);
+                x_plus[i] += step;
+                x_minus[i] -= step;
+                let numeric = (obstacle.signed_distance(&x_plus)
+                    - obstacle.signed_distance(&x_minus))
+                    / (2.0 * step);
+                assert_close(grad[i], numeric, 1e-5);
+            }
+        }
+    }
+
+    #[test]
+    fn sphere() {
+        let sphere = Sphere::new(Vector3::new(1.0, 2.0, 3.0), 2.0);
+        assert_eq!(sphere.penalty(&[2.0, 2.0, 3.0]), 3.0);
+        assert_eq!(sphere.penalty(&[1.0, 2.0, 6.0]), 0.0);
+        assert_eq!(sphere.signed_distance(&[1.0, 2.0, 6.0]), 1.0);
+        assert_eq!(sphere.signed_distance(&[2.0, 2.0, 3.0]), -1.0);
+        assert_eq!(sphere.signed_distance(&[1.0, 2.0, 3.0]), -2.0);
+
+        assert_gradient(
+            &sphere,
+            &[[1.5, 2.5, 3.2], [0.0, 2.0, 3.0], [4.0, 2.0, 3.0]],
+        );
+        assert_distance_gradient(&sphere, &[[1.5, 2.5, 3.2], [4.0, 0.0, 3.0]]);
+        assert_eq!(
+            sphere.bounding_box().ranges,
+            [(-1.0, 3.0), (0.0, 4.0), (1.0, 5.0)]
+        );
+    }
+
+    #[test]
+    fn ellipsoid_penalty_and_box() {
+        let ellipsoid = Ellipsoid::new(Vector3::new(1.0, 0.0, 0.0), Vector3::new(3.0, 2.0, 1.0));
+        assert_eq!(ellipsoid.penalty(&[2.5, 0.0, 0.0]), 0.75);
+        assert_eq!(ellipsoid.penalty(&[1.0, 0.0, 1.5]), 0.0);
+        assert!(ellipsoid.is_inside(&[1.0, 1.9, 0.0]));
+
+        assert_gradient(
+            &ellipsoid,
+            &[[2.0, 0.5, 0.3], [0.0, -1.0, 0.2], [5.0, 0.0, 0.0]],
+        );
+        assert_eq!(
+            ellipsoid.bounding_box().ranges,
+            [(-2.0, 4.0), (-2.0, 2.0), (-1.0, 1.0)]
+        );
+    }
+
+    #[test]
+    fn ellipsoid_distance_on_the_axes() {
+        let ellipsoid = Ellipsoid::new(Vector3::zeros(), Vector3::new(3.0, 2.0, 1.0));
+        // Outside, along each axis
+        assert_close(ellipsoid.signed_distance(&[5.0, 0.0, 0.0]), 2.0, 1e-9);
+        assert_close(ellipsoid.signed_distance(&[0.0, -4.0, 0.0]), 2.0, 1e-9);
+        assert_close(ellipsoid.signed_distance(&[0.0, 0.0, 3.0]), 2.0, 1e-9);
+        // Inside, along the shortest axis
+        assert_close(ellipsoid.signed_distance(&[0.0, 0.0, 0.5]), -0.5, 1e-9);
+        // At the centre, the closest points are the ends of the shortest axis
+        assert_close(ellipsoid.signed_distance(&[0.0, 0.0, 0.0]), -1.0, 1e-9);
+        let mut grad = [0.0; 3];
+        ellipsoid.signed_distance_gradient(&[0.0, 0.0, 0.0], &mut grad);
+        assert_eq!(grad, [0.0, 0.0, 1.0]);
+
+        // Inside, on the longest axis: the closest point (9/8, 0, z) leaves
+        // the plane z = 0, with (3/8)^2 + z^2 = 1
+        let distance = (0.125_f64.powi(2) + 1.0 - 0.375_f64.powi(2)).sqrt();
+        assert_close(ellipsoid.signed_distance(&[1.0, 0.0, 0.0]), -distance, 1e-9);
+        ellipsoid.signed_distance_gradient(&[1.0, 0.0, 0.0], &mut grad);
+        assert_close(grad[0], 0.125 / distance, 1e-9);
+        assert_eq!(grad[1], 0.0);
+        assert_close(grad[2], (1.0 - 0.375_f64.powi(2)).sqrt() / distance, 1e-9);
+    }
+
+    #[test]
+    fn ellipsoid_distance_matches_a_sampled_surface() {
+        let radii = Vector3::new(3.0, 2.0, 1.0);
+        let ellipsoid = Ellipsoid::new(Vector3::zeros(), radii);
+        let (n_theta, n_phi) = (300, 600);
+        let surface: Vec<Vector3<f64>> = (0..=n_theta)
+            .flat_map(|i| {
+                let theta = std::f64::consts::PI * i as f64 / n_theta as f64;
+                (0..n_phi).map(move |j| {
+                    let phi = 2.0 * std::f64::consts::PI * j as f64 / n_phi as f64;
+                    Vector3::new(
+                        radii[0] * theta.sin() * phi.cos(),
+                        radii[1] * theta.sin() * phi.sin(),
+                        radii[2] * theta.cos(),
+                    )
+                })
+            })
+            .collect();
+
+        let points = [
+            [4.0, 1.0, 0.5],
+            [-1.0, 2.5, -1.0],
+            [0.5, 0.5, 1.5],
+            [1.0, -0.5, 0.2],
+            [-2.0, 0.3, -0.1],
+        ];
+        for x in &points {
+            let p = Vector3::from_column_slice(x);
+            let sampled = surface
+                .iter()
+                .map(|s| (p - s).norm())
+                .fold(f64::INFINITY, f64::min);
+            let distance = ellipsoid.signed_distance(x);
+            assert_close(distance.abs(), sampled, 1e-2);
+            assert_eq!(distance < 0.0, ellipsoid.is_inside(x));
+        }
+        assert_distance_gradient(&ellipsoid, &points);
+    }
+
+    #[test]
+    fn cuboid() {
+        let cuboid = Cuboid::new(Vector3::zeros(), Vector3::new(1.0, 2.0, 3.0));
+        assert_eq!(cuboid.penalty(&[0.5, 1.0, 1.0]), 0.75 * 3.0 * 8.0);
+        assert_eq!(cuboid.penalty(&[2.0, 0.0, 0.0]), 0.0);
+
+        assert_eq!(cuboid.signed_distance(&[2.0, 0.0, 0.0]), 1.0);
+        assert_eq!(cuboid.signed_distance(&[2.0, 3.0, 0.0]), 2.0_f64.sqrt());
+        assert_eq!(cuboid.signed_distance(&[0.5, 0.0, 0.0]), -0.5);
+        assert_eq!(cuboid.signed_distance(&[0.0, 0.0, -2.5]), -0.5);
+        let mut grad = [0.0; 3];
+        cuboid.signed_distance_gradient(&[0.0, 0.0, -2.9], &mut grad);
+        assert_eq!(grad, [0.0, 0.0, -1.0]);
+
+        assert_gradient(
+            &cuboid,
+            &[[0.5, 1.0, 1.0], [-0.2, 1.5, -2.0], [2.0, 0.0, 0.0]],
+        );
+        assert_distance_gradient(
+            &cuboid,
+            &[[2.0, 3.0, 0.5], [0.5, -3.0, 4.0], [0.1, 0.2, 2.5]],
+        );
+        assert_eq!(
+            cuboid.bounding_box().ranges,
+            [(-1.0, 1.0), (-2.0, 2.0), (-3.0, 3.0)]
+        );
+    }
+
+    /// `x_i > 0` and `x_0 + x_1 + x_2 < 1`, shifted by `centre`
+    fn tetrahedron(centre: Vector3<f64>) -> Polytope<3> {
+        Polytope::new(
+            vec![
+                (-Vector3::x(), 0.0),
+                (-Vector3::y(), 0.0),
+                (-Vector3::z(), 0.0),
+                (Vector3::new(1.0, 1.0, 1.0), 1.0),
+            ],
+            centre,
+        )
+    }
+
+    #[test]
+    fn polytope_penalty_and_distance() {
+        let tetrahedron = tetrahedron(Vector3::zeros());
+        assert_close(
+            tetrahedron.penalty(&[0.1, 0.2, 0.3]),
+            0.1 * 0.2 * 0.3 * 0.4,
+            1e-15,
+        );
+        assert_eq!(tetrahedron.penalty(&[0.5, 0.5, 0.5]), 0.0);
+        assert_eq!(tetrahedron.penalty(&[-0.1, 0.2, 0.3]), 0.0);
+
+        assert_close(tetrahedron.signed_distance(&[0.1, 0.2, 0.3]), -0.1, 1e-15);
+        let outside = 1.5 / 3.0_f64.sqrt();
+        assert_close(
+            tetrahedron.signed_distance(&[2.0, 0.2, 0.3]),
+            outside,
+            1e-12,
+        );
+        let mut grad = [0.0; 3];
+        tetrahedron.signed_distance_gradient(&[2.0, 0.2, 0.3], &mut grad);
+        for g in grad {
+            assert_close(g, 1.0 / 3.0_f64.sqrt(), 1e-15);
+        }
+
+        assert_gradient(
+            &tetrahedron,
+            &[[0.1, 0.2, 0.3], [0.3, 0.3, 0.1], [2.0, 0.2, 0.3]],
+        );
+    }
+
+    #[test]
+    fn polytope_box_from_its_vertices() {
+        let tetrahedron = tetrahedron(Vector3::new(1.0, -1.0, 2.0));
+        assert!(!tetrahedron.is_empty());
+        let bbox = tetrahedron.bounding_box().ranges;
+        let expected = [(1.0, 2.0), (-1.0, 0.0), (2.0, 3.0)];
+        for (range, expected) in bbox.iter().zip(expected.iter()) {
+            assert_close(range.0, expected.0, 1e-9);
+            assert_close(range.1, expected.1, 1e-9);
+        }
+
+        // The cube of the faces of a `Cuboid`
+        let mut halfspaces = Vec::new();
+        for k in 0..3 {
+            let e_k = Vector3::from_fn(|r, _| if r == k { 1.0 } else { 0.0 });
+            halfspaces.push((e_k, 0.5));
+            halfspaces.push((-e_k, 0.5));
+        }
+        let cube = Polytope::new(halfspaces, Vector3::new(0.0, 1.0, 2.0));
+        let bbox = cube.bounding_box().ranges;
+        let expected = [(-0.5, 0.5), (0.5, 1.5), (1.5, 2.5)];
+        for (range, expected) in bbox.iter().zip(expected.iter()) {
+            assert_close(range.0, expected.0, 1e-9);
+            assert_close(range.1, expected.1, 1e-9);
+        }
+    }
+
+    #[test]
+    fn unbounded_polytopes_have_infinite_ranges() {
+        // The slab 0 < x_0 < 1
+        let slab = Polytope::new(
+            vec![(Vector3::x(), 1.0), (-Vector3::x(), 0.0)],
+            Vector3::zeros(),
+        );
+        assert!(!slab.is_empty());
+        let bbox = slab.bounding_box().ranges;
+        assert_close(bbox[0].0, 0.0, 1e-9);
+        assert_close(bbox[0].1, 1.0, 1e-9);
+        assert_eq!(bbox[1], (f64::NEG_INFINITY, f64::INFINITY));
+        assert_eq!(bbox[2], (f64::NEG_INFINITY, f64::INFINITY));
+
+        // The half-space x_2 < 0, shifted up by 2
+        let below = Polytope::new(vec![(Vector3::z(), 0.0)], Vector3::new(0.0, 0.0, 2.0));
+        let bbox = below.bounding_box().ranges;
+        assert_eq!(bbox[0], (f64::NEG_INFINITY, f64::INFINITY));
+        assert_eq!(bbox[2].0, f64::NEG_INFINITY);
+        assert_close(bbox[2].1, 2.0, 1e-9);
+    }
+
+    #[test]
+    fn empty_polytopes_are_detected() {
+        // x_0 < 0 and x_0 > 1
+        let disjoint = Polytope::new(
+            vec![(Vector3::x(), 0.0), (-Vector3::x(), -1.0)],
+            Vector3::zeros(),
+        );
+        assert!(disjoint.is_empty());
+        // The plane x_0 = 0 has no interior
+        let flat = Polytope::new(
+            vec![(Vector3::x(), 0.0), (-Vector3::x(), 0.0)],
+            Vector3::zeros(),
+        );
+        assert!(flat.is_empty());
+    }
+}
@@ -0,0 +1,656 @@
+use std::fmt;
+
+use nalgebra::Vector3;
+use serde::{Deserialize, Serialize};
+
+use crate::footprint::Swept;
+use crate::{
+    Circle, Cuboid, Difference, DistanceField, Ellipse, Ellipsoid, Footprint, Intersection,
+    MovingObstacle, Nlr1, Nlr2, Obstacle, Polygon, Polyhedron, Polytope, Pose, PosedObstacle,
+    Sphere, Trajectory, Union,
+};
+
+/// Typed description of an obstacle, as accepted by the planners
+///
+/// Replaces the `(kind, c_x, c_y, r_x, r_y)` tuples of Example 11. Call
+/// [`ObstacleSpec::build`] to validate the parameters and obtain the shape.
+///
+/// In scenario files the kind is the `type` field, in snake case (maps are
+/// listed separately, as files).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ObstacleSpec {
+    Circle {
+        centre: (f64, f64),
+        radius: f64,
+    },
+    Ellipse {
+        centre: (f64, f64),
+        radii: (f64, f64),
+    },
+    Polyhedron {
+        centre: (f64, f64),
+        halfspaces: Vec<((f64, f64), f64)>,
+    },
+    /// Simple polygon, vertices in order relative to `centre`; non-convex
+    /// polygons are decomposed into convex pieces (see [`Polygon`])
+    Polygon {
+        centre: (f64, f64),
+        vertices: Vec<(f64, f64)>,
+    },
+    Nlr1 {
+        centre: (f64, f64),
+    },
+    Nlr2 {
+        centre: (f64, f64),
+    },
+    /// The occupied cells of an occupancy grid (see
+    /// [`OccupancyGrid::distance_field`](crate::OccupancyGrid::distance_field))
+    #[serde(skip)]
+    Map {
+        field: DistanceField,
+    },
+    /// `shape`, described in its own frame, rotated and translated by `pose`
+    Posed {
+        shape: Box<ObstacleSpec>,
+        pose: Pose,
+    },
+    /// Points inside any of `shapes` (see [`Union`])
+    Union {
+        shapes: Vec<ObstacleSpec>,
+    },
+    /// Points inside all of `shapes` (see [`Intersection`])
+    Intersection {
+        shapes: Vec<ObstacleSpec>,
+    },
+    /// Points inside `shape` but not inside `minus` (see [`Difference`])
+    Difference {
+        shape: Box<ObstacleSpec>,
+        minus: Box<ObstacleSpec>,
+    },
+    /// A static shape moving along `trajectory`
+    Moving {
+        shape: Box<ObstacleSpec>,
+        trajectory: Trajectory,
+    },
+}
+
+/// Typed description of an obstacle in three dimensions
+///
+/// The counterpart of [`ObstacleSpec`] for planning in space; call
+/// [`ObstacleSpec3::build`] to validate the parameters and obtain the shape.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ObstacleSpec3 {
+    Sphere {
+        centre: (f64, f64, f64),
+        radius: f64,
+    },
+    /// Axis-aligned ellipsoid (see [`Ellipsoid`])
+    Ellipsoid {
+        centre: (f64, f64, f64),
+        radii: (f64, f64, f64),
+    },
+    /// Axis-aligned box (see [`Cuboid`])
+    Cuboid {
+        centre: (f64, f64, f64),
+        half_widths: (f64, f64, f64),
+    },
+    /// `{x : a_i^T (x - centre) < b_i}` (see [`Polytope`])
+    Polytope {
+        centre: (f64, f64, f64),
+        halfspaces: Vec<((f64, f64, f64), f64)>,
+    },
+    /// A static shape moving along `trajectory`, in the `(x, y)` plane
+    Moving {
+        shape: Box<ObstacleSpec3>,
+        trajectory: Trajectory,
+    },
+}
+
+/// Reasons for rejecting an [`ObstacleSpec`] or an [`ObstacleSpec3`]
+#[derive(Debug, Clone, PartialEq)]
+pub enum ObstacleError {
+    /// A coordinate or parameter is NaN or infinite
+    NotFinite,
+    /// A radius is zero or negative
+    NonPositiveRadius(f64),
+    /// A polyhedron without half-spaces, or whose half-spaces have no common interior
+    EmptyPolyhedron,
+    /// A half-space row `(a_i, b_i)` with `a_i = 0`
+    DegenerateHalfspace(usize),
+    /// A polygon with less than three distinct vertices, a zero area, or an
+    /// edge that folds back onto the previous one
+    DegeneratePolygon,
+    /// Edges `i` and `j` of the polygon (from vertex `i` and `j`) intersect
+    SelfIntersectingPolygon(usize, usize),
+    /// Vertex `i` of a polygon required to be convex is reflex
+    NonConvexPolygon(usize),
+    /// A waypoint trajectory without waypoints
+    EmptyTrajectory,
+    /// Waypoint `i` is not later than waypoint `i - 1`
+    UnorderedWaypoints(usize),
+    /// A union or intersection of no shapes
+    EmptyCombination,
+    /// A moving obstacle inside another moving, posed or combined obstacle
+    /// (only the outermost shape can move)
+    NestedMoving,
+    /// A negative clearance margin
+    NegativeClearance(f64),
+    /// A footprint polygon that is not convex, or has less than three vertices
+    InvalidFootprint,
+}
+
+impl fmt::Display for ObstacleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ObstacleError::NotFinite => write!(f, "obstacle parameters must be finite"),
+            ObstacleError::NonPositiveRadius(r) => {
+                write!(f, "obstacle radius must be positive (got {})", r)
+            }
+            ObstacleError::EmptyPolyhedron => write!(f, "polyhedron has an empty interior"),
+            ObstacleError::DegenerateHalfspace(i) => {
+                write!(f, "half-space {} of the polyhedron has a zero normal", i)
+            }
+            ObstacleError::DegeneratePolygon => write!(f, "polygon is degenerate"),
+            ObstacleError::SelfIntersectingPolygon(i, j) => {
+                write!(f, "edges {} and {} of the polygon intersect", i, j)
+            }
+            ObstacleError::NonConvexPolygon(i) => {
+                write!(f, "polygon is not convex at vertex {}", i)
+            }
+            ObstacleError::EmptyTrajectory => write!(f, "trajectory has no waypoints"),
+            ObstacleError::UnorderedWaypoints(i) => {
+                write!(
+                    f,
+                    "waypoint {} of the trajectory is not after the previous one",
+                    i
+                )
+            }
+            ObstacleError::EmptyCombination => write!(f, "obstacle combines no shapes"),
+            ObstacleError::NestedMoving => {
+                write!(f, "only the outermost shape of an obstacle can move")
+            }
+            ObstacleError::NegativeClearance(c) => {
+                write!(f, "clearance must not be negative (got {})", c)
+            }
+            ObstacleError::InvalidFootprint => {
+                write!(f, "footprint must be a convex polygon with a nonzero area")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ObstacleError {}
+
+fn check_finite(values: &[f64]) -> Result<(), ObstacleError> {
+    if values.iter().all(|v| v.is_finite()) {
+        Ok(())
+    } else {
+        Err(ObstacleError::NotFinite)
+    }
+}
+
+fn check_radius(radius: f64) -> Result<(), ObstacleError> {
+    if radius > 0.0 {
+        Ok(())
+    } else {
+        Err(ObstacleError::NonPositiveRadius(radius))
+    }
+}
+
+/// Validates a shape nested in another one, which must not move
+fn check_nested(shape: &ObstacleSpec) -> Result<(), ObstacleError> {
+    if let ObstacleSpec::Moving { .. } = shape {
+        return Err(ObstacleError::NestedMoving);
+    }
+    shape.validate()
+}
+
+impl ObstacleSpec {
+    /// Checks the parameters of the obstacle
+    pub fn validate(&self) -> Result<(), ObstacleError> {
+        match self {
+            ObstacleSpec::Circle { centre, radius } => {
+                check_finite(&[centre.0, centre.1, *radius])?;
+                check_radius(*radius)
+            }
+            ObstacleSpec::Ellipse { centre, radii } => {
+                check_finite(&[centre.0, centre.1, radii.0, radii.1])?;
+                check_radius(radii.0)?;
+                check_radius(radii.1)
+            }
+            ObstacleSpec::Polyhedron { centre, halfspaces } => {
+                check_finite(&[centre.0, centre.1])?;
+                if halfspaces.is_empty() {
+                    return Err(ObstacleError::EmptyPolyhedron);
+                }
+                for (i, &((a0, a1), b)) in halfspaces.iter().enumerate() {
+                    check_finite(&[a0, a1, b])?;
+                    if a0 == 0.0 && a1 == 0.0 {
+                        return Err(ObstacleError::DegenerateHalfspace(i));
+                    }
+                }
+                if Polyhedron::new(halfspaces.clone(), *centre).is_empty() {
+                    return Err(ObstacleError::EmptyPolyhedron);
+                }
+                Ok(())
+            }
+            ObstacleSpec::Polygon { centre, vertices } => {
+                check_finite(&[centre.0, centre.1])?;
+                Polygon::new(vertices, *centre).map(|_| ())
+            }
+            ObstacleSpec::Nlr1 { centre } | ObstacleSpec::Nlr2 { centre } => {
+                check_finite(&[centre.0, centre.1])
+            }
+            // Checked when the grid is loaded
+            ObstacleSpec::Map { .. } => Ok(()),
+            ObstacleSpec::Posed { shape, pose } => {
+                check_nested(shape)?;
+                check_finite(&[pose.position.0, pose.position.1, pose.angle])
+            }
+            ObstacleSpec::Union { shapes } | ObstacleSpec::Intersection { shapes } => {
+                if shapes.is_empty() {
+                    return Err(ObstacleError::EmptyCombination);
+                }
+                shapes.iter().try_for_each(check_nested)
+            }
+            ObstacleSpec::Difference { shape, minus } => {
+                check_nested(shape)?;
+                check_nested(minus)
+            }
+            ObstacleSpec::Moving { shape, trajectory } => {
+                check_nested(shape)?;
+                trajectory.validate()
+            }
+        }
+    }
+
+    /// Validates the parameters and returns the corresponding shape
+    ///
+    /// A moving obstacle is returned at its initial position (see
+    /// [`ObstacleSpec::build_moving`]).
+    pub fn build(&self) -> Result<Box<dyn Obstacle>, ObstacleError> {
+        self.validate()?;
+        let obstacle: Box<dyn Obstacle> = match self {
+            ObstacleSpec::Circle { centre, radius } => Box::new(Circle::new(*centre, *radius)),
+            ObstacleSpec::Ellipse { centre, radii } => Box::new(Ellipse::new(*centre, *radii)),
+            ObstacleSpec::Polyhedron { centre, halfspaces } => {
+                Box::new(Polyhedron::new(halfspaces.clone(), *centre))
+            }
+            ObstacleSpec::Polygon { centre, vertices } => {
+                let polygon = Polygon::new(vertices, *centre)?;
+                match polygon.pieces() {
+                    [convex] => Box::new(convex.clone()),
+                    _ => Box::new(polygon),
+                }
+            }
+            ObstacleSpec::Nlr1 { centre } => Box::new(Nlr1::new(*centre)),
+            ObstacleSpec::Nlr2 { centre } => Box::new(Nlr2::new(*centre)),
+            ObstacleSpec::Map { field } => Box::new(field.clone()),
+            ObstacleSpec::Posed { shape, pose } => {
+                Box::new(PosedObstacle::new(shape.build()?, *pose))
+            }
+            ObstacleSpec::Union { shapes } => Box::new(Union::new(
+                shapes
+                    .iter()
+                    .map(|shape| shape.build())
+                    .collect::<Result<_, _>>()?,
+            )?),
+            ObstacleSpec::Intersection { shapes } => Box::new(Intersection::new(
+                shapes
+                    .iter()
+                    .map(|shape| shape.build())
+                    .collect::<Result<_, _>>()?,
+            )?),
+            ObstacleSpec::Difference { shape, minus } => {
+                Box::new(Difference::new(shape.build()?, minus.build()?))
+            }
+            ObstacleSpec::Moving { shape, .. } => shape.build()?,
+        };
+        Ok(obstacle)
+    }
+
+    /// Validates the parameters and returns the shape inflated by the
+    /// robot footprint (see [`Footprint`])
+    ///
+    /// Circles are inflated exactly for a circular footprint, by the largest
+    /// extent of the footprint otherwise. Ellipses are scaled about their
+    /// centre by `1 + R / min(r_0, r_1)`, where `R` is that extent, which
+    /// encloses the Minkowski sum. Polyhedra and polygons use
+    /// [`Polyhedron::inflate`] (piece by piece for non-convex polygons).
+    /// The nonlinear shapes are evaluated at sample points of the footprint,
+    /// and the distance field of a map is shifted by the largest extent.
+    /// A posed shape is inflated in its own frame by the footprint rotated
+    /// by `-angle`. The shapes of a union are inflated one by one, which is
+    /// exact, and so are those of an intersection, which encloses the
+    /// Minkowski sum; differences are evaluated at sample points.
+    pub fn build_inflated(
+        &self,
+        footprint: &Footprint,
+    ) -> Result<Box<dyn Obstacle>, ObstacleError> {
+        footprint.validate()?;
+        if footprint.is_point() {
+            return self.build();
+        }
+        self.validate()?;
+        let radius = footprint.radius();
+        let obstacle: Box<dyn Obstacle> = match self {
+            ObstacleSpec::Circle { centre, radius: r } => {
+                Box::new(Circle::new(*centre, r + radius))
+            }
+            ObstacleSpec::Ellipse { centre, radii } => {
+                let scale = 1.0 + radius / radii.0.min(radii.1);
+                Box::new(Ellipse::new(*centre, (scale * radii.0, scale * radii.1)))
+            }
+            ObstacleSpec::Polyhedron { centre, halfspaces } => {
+                Box::new(Polyhedron::new(halfspaces.clone(), *centre).inflate(footprint))
+            }
+            ObstacleSpec::Polygon { centre, vertices } => {
+                let polygon = Polygon::new(vertices, *centre)?;
+                match polygon.pieces() {
+                    [convex] => Box::new(convex.inflate(footprint)),
+                    _ => Box::new(polygon.inflate(footprint)),
+                }
+            }
+            ObstacleSpec::Nlr1 { .. } | ObstacleSpec::Nlr2 { .. } => {
+                Box::new(Swept::new(self.build()?, footprint))
+            }
+            ObstacleSpec::Map { field } => Box::new(field.inflate(radius)),
+            ObstacleSpec::Posed { shape, pose } => Box::new(PosedObstacle::new(
+                shape.build_inflated(&footprint.rotated(-pose.angle))?,
+                *pose,
+            )),
+            ObstacleSpec::Union { shapes } => Box::new(Union::new(
+                shapes
+                    .iter()
+                    .map(|shape| shape.build_inflated(footprint))
+                    .collect::<Result<_, _>>()?,
+            )?),
+            ObstacleSpec::Intersection { shapes } => Box::new(Intersection::new(
+                shapes
+                    .iter()
+                    .map(|shape| shape.build_inflated(footprint))
+                    .collect::<Result<_, _>>()?,
+            )?),
+            ObstacleSpec::Difference { .. } => Box::new(Swept::new(self.build()?, footprint)),
+            ObstacleSpec::Moving { shape, .. } => shape.build_inflated(footprint)?,
+        };
+        Ok(obstacle)
+    }
+
+    /// Validates the parameters and returns the shape, inflated by the robot
+    /// footprint, with its trajectory (`Trajectory::Static` for the static
+    /// shapes)
+    pub fn build_moving(&self, footprint: &Footprint) -> Result<MovingObstacle, ObstacleError> {
+        let trajectory = match self {
+            ObstacleSpec::Moving { trajectory, .. } => trajectory.clone(),
+            _ => Trajectory::Static,
+        };
+        MovingObstacle::new(self.build_inflated(footprint)?, trajectory)
+    }
+}
+
+fn vector3(v: (f64, f64, f64)) -> Vector3<f64> {
+    Vector3::new(v.0, v.1, v.2)
+}
+
+impl ObstacleSpec3 {
+    /// Checks the parameters of the obstacle
+    pub fn validate(&self) -> Result<(), ObstacleError> {
+        match self {
+            ObstacleSpec3::Sphere { centre, radius } => {
+                check_finite(&[centre.0, centre.1, centre.2, *radius])?;
+                check_radius(*radius)
+            }
+            ObstacleSpec3::Ellipsoid {
+                centre,
+                radii: widths,
+            }
+            | ObstacleSpec3::Cuboid {
+                centre,
+                half_widths: widths,
+            } => {
+                check_finite(&[centre.0, centre.1, centre.2, widths.0, widths.1, widths.2])?;
+                check_radius(widths.0)?;
+                check_radius(widths.1)?;
+                check_radius(widths.2)
+            }
+            ObstacleSpec3::Polytope { centre, halfspaces } => {
+                check_finite(&[centre.0, centre.1, centre.2])?;
+                if halfspaces.is_empty() {
+                    return Err(ObstacleError::EmptyPolyhedron);
+                }
+                for (i, &(a, b)) in halfspaces.iter().enumerate() {
+                    check_finite(&[a.0, a.1, a.2, b])?;
+                    if a == (0.0, 0.0, 0.0) {
+                        return Err(ObstacleError::DegenerateHalfspace(i));
+                    }
+                }
+                if self.polytope(0.0).is_empty() {
+                    return Err(ObstacleError::EmptyPolyhedron);
+                }
+                Ok(())
+            }
+            ObstacleSpec3::Moving { shape, trajectory } => {
+                if let ObstacleSpec3::Moving { .. } = **shape {
+                    return Err(ObstacleError::NestedMoving);
+                }
+                shape.validate()?;
+                trajectory.validate()
+            }
+        }
+    }
+
+    /// Validates the parameters and returns the corresponding shape
+    ///
+    /// A moving obstacle is returned at its initial position.
+    pub fn build(&self) -> Result<Box<dyn Obstacle<3>>, ObstacleError> {
+        self.build_inflated(&Footprint::point())
+    }
+
+    /// Validates the parameters and returns the shape inflated by a ball of
+    /// radius [`Footprint::radius`]
+    ///
+    /// Spheres and polytope faces are inflated exactly; ellipsoids are
+    /// scaled about their centre by `1 + R / min_i r_i` and boxes are
+    /// widened by `R`, both of which enclose the Minkowski sum.
+    pub fn build_inflated(
+        &self,
+        footprint: &Footprint,
+    ) -> Result<Box<dyn Obstacle<3>>, ObstacleError> {
+        footprint.validate()?;
+        self.validate()?;
+        let radius = footprint.radius();
+        let obstacle: Box<dyn Obstacle<3>> = match self {
+            ObstacleSpec3::Sphere { centre, radius: r } => {
+                Box::new(Sphere::new(vector3(*centre), r + radius))
+            }
+            ObstacleSpec3::Ellipsoid { centre, radii } => {
+                let radii = vector3(*radii);
+                Box::new(Ellipsoid::new(
+                    vector3(*centre),
+                    radii * (1.0 + radius / radii.min()),
+                ))
+            }
+            ObstacleSpec3::Cuboid {
+                centre,
+                half_widths,
+            } => Box::new(Cuboid::new(
+                vector3(*centre),
+                vector3(*half_widths).add_scalar(radius),
+            )),
+            ObstacleSpec3::Polytope { .. } => Box::new(self.polytope(radius)),
+            ObstacleSpec3::Moving { shape, .. } => shape.build_inflated(footprint)?,
+        };
+        Ok(obstacle)
+    }
+
+    /// Validates the parameters and returns the shape, inflated by the robot
+    /// footprint, with its trajectory (`Trajectory::Static` for the static
+    /// shapes)
+    pub fn build_moving(&self, footprint: &Footprint) -> Result<MovingObstacle<3>, ObstacleError> {
+        let trajectory = match self {
+            ObstacleSpec3::Moving { trajectory, .. } => trajectory.clone(),
+            _ => Trajectory::Static,
+        };
+        MovingObstacle::new(self.build_inflated(footprint)?, trajectory)
+    }
+
+    /// The polytope with its faces pushed out by `margin` (unchecked)
+    fn polytope(&self, margin: f64) -> Polytope<3> {
+        match self {
+            ObstacleSpec3::Polytope { centre, halfspaces } => Polytope::new(
+                halfspaces
+                    .iter()
+                    .map(|&(a, b)| {
+                        let a = vector3(a);
+                        (a, b + margin * a.norm())
+                    })
+                    .collect(),
+                vector3(*centre),
+            ),
+            _ => unreachable!("not a polytope"),
+        }
+    }
+}
+
+/// Obstacle descriptions from which the planners build obstacles in `D`
+/// dimensions: [`ObstacleSpec`] in the plane and [`ObstacleSpec3`] in space
+pub trait BuildObstacle<const D: usize = 2> {
+    /// The obstacle inflated by the robot footprint, with its trajectory
+    fn build_moving(&self, footprint: &Footprint) -> Result<MovingObstacle<D>, ObstacleError>;
+}
+
+impl BuildObstacle for ObstacleSpec {
+    fn build_moving(&self, footprint: &Footprint) -> Result<MovingObstacle, ObstacleError> {
+        ObstacleSpec::build_moving(self, footprint)
+    }
+}
+
+impl BuildObstacle<3> for ObstacleSpec3 {
+    fn build_moving(&self, footprint: &Footprint) -> Result<MovingObstacle<3>, ObstacleError> {
+        ObstacleSpec3::build_moving(self, footprint)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sphere(radius: f64) -> ObstacleSpec3 {
+        ObstacleSpec3::Sphere {
+            centre: (1.0, 2.0, 3.0),
+            radius,
+        }
+    }
+
+    fn polytope(halfspaces: Vec<((f64, f64, f64), f64)>) -> ObstacleSpec3 {
+        ObstacleSpec3::Polytope {
+            centre: (0.0, 0.0, 0.0),
+            halfspaces,
+        }
+    }
+
+    #[test]
+    fn spec3_parameters_are_validated() {
+        assert_eq!(sphere(1.0).validate(), Ok(()));
+        assert_eq!(
+            sphere(0.0).validate(),
+            Err(ObstacleError::NonPositiveRadius(0.0))
+        );
+        assert_eq!(sphere(f64::NAN).validate(), Err(ObstacleError::NotFinite));
+        let ellipsoid = ObstacleSpec3::Ellipsoid {
+            centre: (0.0, 0.0, 0.0),
+            radii: (1.0, -2.0, 1.0),
+        };
+        assert_eq!(
+            ellipsoid.validate(),
+            Err(ObstacleError::NonPositiveRadius(-2.0))
+        );
+        let cuboid = ObstacleSpec3::Cuboid {
+            centre: (0.0, f64::INFINITY, 0.0),
+            half_widths: (1.0, 1.0, 1.0),
+        };
+        assert_eq!(cuboid.validate(), Err(ObstacleError::NotFinite));
+
+        assert_eq!(
+            polytope(vec![]).validate(),
+            Err(ObstacleError::EmptyPolyhedron)
+        );
+        assert_eq!(
+            polytope(vec![((1.0, 0.0, 0.0), 1.0), ((0.0, 0.0, 0.0), 1.0)]).validate(),
+            Err(ObstacleError::DegenerateHalfspace(1))
+        );
+        // x_0 < 0 and x_0 > 1
+        assert_eq!(
+            polytope(vec![((1.0, 0.0, 0.0), 0.0), ((-1.0, 0.0, 0.0), -1.0)]).validate(),
+            Err(ObstacleError::EmptyPolyhedron)
+        );
+        // Unbounded polytopes (half-spaces, slabs) are allowed
+        assert_eq!(polytope(vec![((0.0, 0.0, 1.0), 0.0)]).validate(), Ok(()));
+
+        let nested = ObstacleSpec3::Moving {
+            shape: Box::new(ObstacleSpec3::Moving {
+                shape: Box::new(sphere(1.0)),
+                trajectory: Trajectory::Static,
+            }),
+            trajectory: Trajectory::Static,
+        };
+        assert_eq!(nested.validate(), Err(ObstacleError::NestedMoving));
+        let unordered = ObstacleSpec3::Moving {
+            shape: Box::new(sphere(1.0)),
+            trajectory: Trajectory::Waypoints(vec![(1.0, (0.0, 0.0)), (0.5, (1.0, 0.0))]),
+        };
+        assert_eq!(
+            unordered.validate(),
+            Err(ObstacleError::UnorderedWaypoints(1))
+        );
+    }
+
+    #[test]
+    fn spec3_builds_the_inflated_shapes() {
+        assert!(sphere(-1.0).build().is_err());
+        let obstacle = sphere(1.0).build().unwrap();
+        assert_eq!(obstacle.signed_distance(&[1.0, 2.0, 5.0]), 1.0);
+
+        let footprint = Footprint::circle(0.5);
+        let obstacle = sphere(1.0).build_inflated(&footprint).unwrap();
+        assert_eq!(
+            obstacle.bounding_box().ranges,
+            [(-0.5, 2.5), (0.5, 3.5), (1.5, 4.5)]
+        );
+        let cuboid = ObstacleSpec3::Cuboid {
+            centre: (0.0, 0.0, 0.0),
+            half_widths: (1.0, 2.0, 3.0),
+        };
+        let obstacle = cuboid.build_inflated(&footprint).unwrap();
+        assert_eq!(
+            obstacle.bounding_box().ranges,
+            [(-1.5, 1.5), (-2.5, 2.5), (-3.5, 3.5)]
+        );
+        // Ellipsoids are scaled by 1 + 0.5 / 1
+        let ellipsoid = ObstacleSpec3::Ellipsoid {
+            centre: (0.0, 0.0, 0.0),
+            radii: (2.0, 1.0, 4.0),
+        };
+        let obstacle = ellipsoid.build_inflated(&footprint).unwrap();
+        assert_eq!(
+            obstacle.bounding_box().ranges,
+            [(-3.0, 3.0), (-1.5, 1.5), (-6.0, 6.0)]
+        );
+        // Faces pushed out by 0.5 |a_i|
+        let slab = polytope(vec![((2.0, 0.0, 0.0), 2.0), ((-1.0, 0.0, 0.0), 0.0)]);
+        let obstacle = slab.build_inflated(&footprint).unwrap();
+        let x_range = obstacle.bounding_box().ranges[0];
+        assert!((x_range.0 + 0.5).abs() < 1e-9 && (x_range.1 - 1.5).abs() < 1e-9);
+
+        let moving = ObstacleSpec3::Moving {
+            shape: Box::new(sphere(1.0)),
+            trajectory: Trajectory::ConstantVelocity((1.0, 0.0)),
+        };
+        assert_eq!(moving.build().unwrap().penalty(&[1.0, 2.0, 3.0]), 1.0);
+        let obstacle = moving.build_moving(&Footprint::point()).unwrap();
+        assert_eq!(obstacle.penalty(2.0, &[3.0, 2.0, 3.0]), 1.0);
+    }
+}
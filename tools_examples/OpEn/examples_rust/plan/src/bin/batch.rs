@@ -0,0 +1,171 @@
+//! # batch
+//!
+//! Monte-Carlo runs of the planner over scenario files and random scenarios:
+//!
+//! ```text
+//! cargo run --release -p plan --bin batch -- example_09_multple_obstacles/scenario.toml \
+//!     --random 200 --seed 1 --max-wall-time 60 --summary batch_summary.csv
+//! ```
+//!
+//! Every scenario is planned in parallel (rayon), without printing the
+//! positions. The statistics of every run (outcome, steps, path length,
+//! minimum clearance, solver iterations and wall time) are written to the
+//! summary CSV file and aggregated in a table on the console.
+
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::time::Instant;
+
+use clap::Parser;
+// For Obstacles
+use obstacles::Footprint;
+// For Path Planning
+use planner::{run_batch, RobotModel, Scenario, ScenarioGenerator};
+
+/// Runs the planner over many scenarios and summarises the results
+#[derive(Debug, Parser)]
+#[command(name = "batch")]
+struct Args {
+    /// Scenario files (.toml or .json), or directories holding them
+    scenarios: Vec<PathBuf>,
+    /// Number of random scenarios to add (see `ScenarioGenerator`)
+    #[arg(long, default_value_t = 0)]
+    random: usize,
+    /// Seed of the random scenarios
+    #[arg(long, default_value_t = 1)]
+    seed: usize,
+    /// Number of obstacles of the random scenarios
+    #[arg(long, default_value_t = 8)]
+    obstacles: usize,
+    /// Area of the random scenarios
+    #[arg(long, num_args = 4, value_names = ["X_MIN", "X_MAX", "Y_MIN", "Y_MAX"], allow_negative_numbers = true)]
+    area: Option<Vec<f64>>,
+    /// Robot, footprint, horizon, limits and solver settings of the random
+    /// scenarios (by default, the greedy planner for a holonomic robot of
+    /// radius 0.2 moving 0.1 per step)
+    #[arg(long)]
+    template: Option<PathBuf>,
+    /// Maximum number of time steps of every run
+    #[arg(long)]
+    max_steps: Option<usize>,
+    /// Wall time (s) after which every run stops
+    #[arg(long)]
+    max_wall_time: Option<f64>,
+    /// Number of threads (by default, one per CPU)
+    #[arg(long)]
+    threads: Option<usize>,
+    /// Statistics of every run (CSV)
+    #[arg(long, default_value = "batch_summary.csv")]
+    summary: PathBuf,
+}
+
+/// The scenario files given on the command line, with those in the directories
+fn scenario_files(paths: &[PathBuf]) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+    let mut files = Vec::new();
+    for path in paths {
+        if path.is_dir() {
+            let mut entries: Vec<PathBuf> = fs::read_dir(path)?
+                .map(|entry| entry.map(|e| e.path()))
+                .collect::<Result<_, _>>()?;
+            entries.retain(|file| is_scenario(file));
+            entries.sort();
+            files.extend(entries);
+        } else {
+            files.push(path.clone());
+        }
+    }
+    Ok(files)
+}
+
+fn is_scenario(file: &Path) -> bool {
+    matches!(
+        file.extension().and_then(|e| e.to_str()),
+        Some("toml") | Some("json")
+    )
+}
+
+fn template(args: &Args) -> Result<Scenario, Box<dyn Error>> {
+    if let Some(file) = &args.template {
+        return Ok(Scenario::load(file)?);
+    }
+    let mut template = Scenario::new(
+        vec![0.0, 0.0],
+        vec![0.0, 0.0],
+        RobotModel::Holonomic { u_max: 0.1 },
+    );
+    template.footprint = Footprint::circle(0.2);
+    Ok(template)
+}
+
+fn run(args: &Args) -> Result<(), Box<dyn Error>> {
+    if let Some(threads) = args.threads {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build_global()?;
+    }
+
+    // Scenarios: files first, then the random ones
+    let mut scenarios: Vec<(String, Result<Scenario, String>)> = scenario_files(&args.scenarios)?
+        .into_iter()
+        .map(|file| {
+            let scenario = Scenario::load(&file).map_err(|e| e.to_string());
+            (file.display().to_string(), scenario)
+        })
+        .collect();
+    if args.random > 0 {
+        let area = match &args.area {
+            Some(area) => ((area[0], area[1]), (area[2], area[3])),
+            None => ((0.0, 20.0), (0.0, 20.0)),
+        };
+        if !(area.0 .0 < area.0 .1 && area.1 .0 < area.1 .1) {
+            return Err("the area needs X_MIN < X_MAX and Y_MIN < Y_MAX".into());
+        }
+        let generator = ScenarioGenerator::new(template(args)?, area)
+            .with_num_obstacles(args.obstacles)
+            .with_seed(args.seed);
+        for index in 0..args.random {
+            let scenario = generator.generate(index).map_err(|e| e.to_string());
+            scenarios.push((format!("random_{}", index), scenario));
+        }
+    }
+    if scenarios.is_empty() {
+        return Err("no scenarios (give scenario files or --random N)".into());
+    }
+    for (_, scenario) in scenarios.iter_mut() {
+        if let Ok(scenario) = scenario {
+            if let Some(max_steps) = args.max_steps {
+                scenario.limits.max_steps = max_steps;
+            }
+            if let Some(max_wall_time) = args.max_wall_time {
+                scenario.limits.max_wall_time = Some(max_wall_time);
+            }
+        }
+    }
+
+    println!(
+        "Planning {} scenarios on {} threads",
+        scenarios.len(),
+        rayon::current_num_threads()
+    );
+    let tic = Instant::now();
+    let report = run_batch(scenarios);
+    println!("Done in {:.2} s\n", tic.elapsed().as_secs_f64());
+
+    fs::write(&args.summary, report.to_csv())?;
+    print!("{}", report);
+    println!(
+        "\nStatistics of every run written to {}",
+        args.summary.display()
+    );
+    Ok(())
+}
+
+fn main() {
+    let args = Args::parse();
+    if let Err(e) = run(&args) {
+        eprintln!("batch: {}", e);
+        process::exit(1);
+    }
+}
@@ -0,0 +1,378 @@
+//! # plan
+//!
+//! Command-line path planner driven by scenario files (see
+//! `planner::Scenario`):
+//!
+//! ```text
+//! cargo run --release -p plan -- example_09_multple_obstacles/scenario.toml \
+//!     --goal 12 8 --step 0.2 --output path.json --svg path.svg
+//! ```
+//!
+//! Loads the scenario, applies the overrides given on the command line, runs
+//! the planner (printing every position with `--verbose`), prints the
+//! outcome and writes the waypoints (CSV, JSON or
+//! GPX-like, by the file extension, see `planner::PlannedPath`) and the
+//! figure (SVG). The exit status is 0 if the goal is
+//! reached, 1 on an invalid scenario or output file and 2 otherwise.
+
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::process;
+
+use clap::Parser;
+// For Obstacles
+use obstacles::{get_points, ObstacleSpec};
+// For Path Planning
+use planner::{Outcome, PathFormat, Plan, PlotSettings, RobotModel, Scenario};
+// For plot
+use plotlib::page::Page;
+use plotlib::repr::Plot;
+use plotlib::style::{PointMarker, PointStyle};
+use plotlib::view::ContinuousView;
+
+/// Plans a path for a scenario file (TOML or JSON)
+#[derive(Debug, Parser)]
+#[command(name = "plan")]
+struct Args {
+    /// Scenario file (.toml or .json)
+    scenario: PathBuf,
+    /// Goal position, instead of the one of the scenario
+    #[arg(long, num_args = 2, value_names = ["X", "Y"], allow_negative_numbers = true)]
+    goal: Option<Vec<f64>>,
+    /// Step size: `u_max` of the holonomic model, the time step `dt` of the others
+    #[arg(long)]
+    step: Option<f64>,
+    /// Tolerance of the inner (PANOC) problems
+    #[arg(long)]
+    tolerance: Option<f64>,
+    /// Tolerance on the infeasibility of the obstacle and input constraints
+    #[arg(long)]
+    delta_tolerance: Option<f64>,
+    /// Tolerance of the last inner problem
+    #[arg(long)]
+    epsilon_tolerance: Option<f64>,
+    /// Maximum number of time steps
+    #[arg(long)]
+    max_steps: Option<usize>,
+    /// Waypoint file (.csv, .json or GPX-like .gpx)
+    #[arg(short, long, default_value = "path.csv")]
+    output: PathBuf,
+    /// Figure (SVG); by default the plot output of the scenario, or path_result.svg
+    #[arg(long)]
+    svg: Option<PathBuf>,
+    /// Print the position reached at every step
+    #[arg(short, long)]
+    verbose: bool,
+}
+
+impl Args {
+    /// The command-line overrides, applied to the scenario
+    fn apply(&self, scenario: &mut Scenario) {
+        if let Some(goal) = &self.goal {
+            scenario.goal = goal.clone();
+        }
+        if let Some(step) = self.step {
+            scenario.robot = with_step(scenario.robot, step);
+        }
+        let solver = &mut scenario.solver;
+        if let Some(tolerance) = self.tolerance {
+            solver.tolerance = tolerance;
+        }
+        if let Some(delta_tolerance) = self.delta_tolerance {
+            solver.delta_tolerance = delta_tolerance;
+        }
+        if let Some(epsilon_tolerance) = self.epsilon_tolerance {
+            solver.epsilon_tolerance = epsilon_tolerance;
+        }
+        if let Some(max_steps) = self.max_steps {
+            scenario.limits.max_steps = max_steps;
+        }
+    }
+}
+
+/// The robot model with the step size `step`
+fn with_step(robot: RobotModel, step: f64) -> RobotModel {
+    match robot {
+        RobotModel::Holonomic { .. } => RobotModel::Holonomic { u_max: step },
+        RobotModel::Unicycle {
+            v_max, omega_max, ..
+        } => RobotModel::Unicycle {
+            dt: step,
+            v_max,
+            omega_max,
+        },
+        RobotModel::DifferentialDrive {
+            wheel_radius,
+            track_width,
+            wheel_speed_max,
+            ..
+        } => RobotModel::DifferentialDrive {
+            dt: step,
+            wheel_radius,
+            track_width,
+            wheel_speed_max,
+        },
+        RobotModel::DoubleIntegrator { v_max, a_max, .. } => RobotModel::DoubleIntegrator {
+            dt: step,
+            v_max,
+            a_max,
+        },
+        RobotModel::KinematicBicycle {
+            wheelbase,
+            v_max,
+            a_max,
+            steering_max,
+            ..
+        } => RobotModel::KinematicBicycle {
+            dt: step,
+            wheelbase,
+            v_max,
+            a_max,
+            steering_max,
+        },
+    }
+}
+
+/// Plotted area: that of the scenario, or the start, goal and path with a margin
+fn plot_settings(scenario: &Scenario, plan: &Plan) -> PlotSettings {
+    if let Some(plot) = &scenario.plot {
+        return plot.clone();
+    }
+    let goal = (scenario.goal[0], scenario.goal[1]);
+    let points = plan.path.iter().chain(std::iter::once(&goal));
+    let (mut x_range, mut y_range) = (
+        (f64::INFINITY, f64::NEG_INFINITY),
+        (f64::INFINITY, f64::NEG_INFINITY),
+    );
+    for &(x, y) in points {
+        x_range = (x_range.0.min(x), x_range.1.max(x));
+        y_range = (y_range.0.min(y), y_range.1.max(y));
+    }
+    let margin = 0.2 * (x_range.1 - x_range.0).max(y_range.1 - y_range.0).max(1.0);
+    PlotSettings {
+        x_range: (x_range.0 - margin, x_range.1 + margin),
+        y_range: (y_range.0 - margin, y_range.1 + margin),
+        resolution: 0.05,
+        output: PathBuf::from("path_result.svg"),
+    }
+}
+
+/// Path, obstacles (moving ones where they start), start and goal
+fn write_figure(
+    scenario: &Scenario,
+    obstacles: &[ObstacleSpec],
+    plan: &Plan,
+    plot: &PlotSettings,
+    file: &Path,
+) -> Result<(), Box<dyn Error>> {
+    // Path Plot
+    let s1: Plot = Plot::new(plan.path.clone()).point_style(
+        PointStyle::new()
+            .marker(PointMarker::Square) // setting the marker to be a square
+            .size(1.0)
+            .colour("#DD3355"),
+    );
+
+    // Obstacle Plot
+    let mut obstacle_points = Vec::new();
+    for spec in obstacles {
+        let obstacle = spec.build()?;
+        obstacle_points.extend(get_points(obstacle.as_ref(), plot.search_area()));
+    }
+    let s2: Plot = Plot::new(obstacle_points).point_style(
+        PointStyle::new() // uses the default marker
+            .size(0.5)
+            .colour("#bb33dd"),
+    );
+
+    // Starting point & Goal point
+    let point_start = vec![(scenario.start[0], scenario.start[1])];
+    let s3: Plot = Plot::new(point_start).point_style(PointStyle::new().colour("#35C788"));
+    let point_goal = vec![(scenario.goal[0], scenario.goal[1])];
+    let s4: Plot = Plot::new(point_goal).point_style(PointStyle::new().colour("#35C788"));
+
+    // Plot: The 'view' describes what set of data is drawn
+    let v = ContinuousView::new()
+        .add(s2)
+        .add(s1)
+        .add(s3)
+        .add(s4)
+        .x_range(plot.x_range.0, plot.x_range.1)
+        .y_range(plot.y_range.0, plot.y_range.1)
+        .x_label("X (m)")
+        .y_label("Y (m)");
+
+    // A page with a single view is then saved to an SVG file
+    Page::single(&v)
+        .save(file)
+        .map_err(|e| format!("cannot write the figure: {}", e))?;
+    Ok(())
+}
+
+fn run(args: &Args) -> Result<Outcome, Box<dyn Error>> {
+    // Check the output before planning
+    let format = PathFormat::of(&args.output)?;
+    let mut scenario = Scenario::load(&args.scenario)?;
+    args.apply(&mut scenario);
+    scenario.validate()?;
+
+    let plan = if args.verbose {
+        scenario.run()?
+    } else {
+        scenario.run_quietly()?
+    };
+    println!(
+        "Planning result: {} after {} steps",
+        plan.outcome,
+        plan.path.len() - 1
+    );
+
+    scenario.waypoints(&plan).write(&args.output, format)?;
+    println!("Path written to {}", args.output.display());
+
+    let plot = plot_settings(&scenario, &plan);
+    let svg = args.svg.clone().unwrap_or_else(|| plot.output.clone());
+    write_figure(&scenario, &scenario.obstacle_specs()?, &plan, &plot, &svg)?;
+    println!("Figure written to {}", svg.display());
+    Ok(plan.outcome)
+}
+
+fn main() {
+    let args = Args::parse();
+    match run(&args) {
+        Ok(Outcome::Reached) => {}
+        Ok(_) => process::exit(2),
+        Err(e) => {
+            eprintln!("plan: {}", e);
+            process::exit(1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Args {
+        Args::try_parse_from([&["plan", "scenario.toml"], args].concat()).unwrap()
+    }
+
+    #[test]
+    fn overrides_replace_the_scenario_settings() {
+        let args = parse(&[
+            "--goal",
+            "12",
+            "-8",
+            "--step",
+            "0.2",
+            "--tolerance",
+            "1e-4",
+            "--delta-tolerance",
+            "1e-3",
+            "--epsilon-tolerance",
+            "1e-2",
+            "--max-steps",
+            "50",
+            "--verbose",
+        ]);
+        assert!(args.verbose);
+        let mut scenario = Scenario::new(
+            vec![0.0, 0.0],
+            vec![10.0, 10.0],
+            RobotModel::Holonomic { u_max: 0.1 },
+        );
+        args.apply(&mut scenario);
+        assert_eq!(scenario.goal, vec![12.0, -8.0]);
+        assert_eq!(scenario.robot, RobotModel::Holonomic { u_max: 0.2 });
+        assert_eq!(
+            (
+                scenario.solver.tolerance,
+                scenario.solver.delta_tolerance,
+                scenario.solver.epsilon_tolerance
+            ),
+            (1e-4, 1e-3, 1e-2)
+        );
+        assert_eq!(scenario.limits.max_steps, 50);
+    }
+
+    #[test]
+    fn no_overrides_keep_the_scenario() {
+        let args = parse(&[]);
+        assert!(!args.verbose);
+        assert_eq!(args.output, PathBuf::from("path.csv"));
+        let scenario = Scenario::new(
+            vec![0.0, 0.0, 0.0],
+            vec![10.0, 10.0],
+            RobotModel::Unicycle {
+                dt: 0.1,
+                v_max: 1.0,
+                omega_max: 2.0,
+            },
+        );
+        let mut applied = scenario.clone();
+        args.apply(&mut applied);
+        assert_eq!(applied, scenario);
+
+        // The goal takes exactly two values
+        assert!(Args::try_parse_from(["plan", "scenario.toml", "--goal", "1"]).is_err());
+    }
+
+    #[test]
+    fn step_sets_the_time_step_of_the_dynamic_models() {
+        let step = |robot| with_step(robot, 0.5);
+        assert_eq!(
+            step(RobotModel::Unicycle {
+                dt: 0.1,
+                v_max: 1.0,
+                omega_max: 2.0
+            }),
+            RobotModel::Unicycle {
+                dt: 0.5,
+                v_max: 1.0,
+                omega_max: 2.0
+            }
+        );
+        assert_eq!(
+            step(RobotModel::DifferentialDrive {
+                dt: 0.1,
+                wheel_radius: 0.05,
+                track_width: 0.3,
+                wheel_speed_max: 10.0
+            }),
+            RobotModel::DifferentialDrive {
+                dt: 0.5,
+                wheel_radius: 0.05,
+                track_width: 0.3,
+                wheel_speed_max: 10.0
+            }
+        );
+        assert_eq!(
+            step(RobotModel::DoubleIntegrator {
+                dt: 0.1,
+                v_max: 1.0,
+                a_max: 0.5
+            }),
+            RobotModel::DoubleIntegrator {
+                dt: 0.5,
+                v_max: 1.0,
+                a_max: 0.5
+            }
+        );
+        assert_eq!(
+            step(RobotModel::KinematicBicycle {
+                dt: 0.1,
+                wheelbase: 1.0,
+                v_max: 2.0,
+                a_max: 1.0,
+                steering_max: 0.5
+            }),
+            RobotModel::KinematicBicycle {
+                dt: 0.5,
+                wheelbase: 1.0,
+                v_max: 2.0,
+                a_max: 1.0,
+                steering_max: 0.5
+            }
+        );
+    }
+}
@@ -0,0 +1,97 @@
+//! Per-step time of the planner, rebuilding the solver at every step
+//! (`main_loop`) versus reusing it (`Planner`) with every `WarmStart`
+//!
+//! `cargo run --release -p planner --example benchmark`
+
+use obstacles::{Footprint, ObstacleSpec};
+use planner::{main_loop, Holonomic, Horizon, Planner, WarmStart};
+use std::time::{Duration, Instant};
+
+const STEPS: usize = 100;
+
+fn main() {
+    // Scenario of Example 09
+    let pos_start = &[0.0, 0.0];
+    let pos_goal = &[10.0, 10.0];
+    let pos_obstacle = vec![
+        ObstacleSpec::Circle {
+            centre: (5.0, 4.0),
+            radius: 1.5,
+        },
+        ObstacleSpec::Circle {
+            centre: (1.0, 2.0),
+            radius: 1.0,
+        },
+        ObstacleSpec::Circle {
+            centre: (8.0, 7.0),
+            radius: 2.0,
+        },
+    ];
+    let dynamics = Holonomic::new(0.1).unwrap();
+
+    for &steps in &[1, 5] {
+        let horizon = Horizon::new(steps).unwrap();
+
+        // Before: caches and problem rebuilt, cold start at every step
+        let mut x_now = pos_start.to_vec();
+        let tic = Instant::now();
+        for _ in 0..STEPS {
+            let u = main_loop(
+                &x_now,
+                pos_goal,
+                &pos_obstacle,
+                dynamics,
+                &Footprint::point(),
+                horizon,
+            )
+            .unwrap();
+            x_now = u[..2].to_vec();
+        }
+        let rebuilt = tic.elapsed();
+
+        // After: one planner, updated in place at every step
+        let warm_starts = [
+            WarmStart::Cold,
+            WarmStart::Inputs,
+            WarmStart::InputsAndMultipliers,
+        ];
+        let mut reused = [Duration::default(); 3];
+        for (k, &warm_start) in warm_starts.iter().enumerate() {
+            let mut planner = Planner::new(
+                pos_start,
+                pos_goal,
+                &pos_obstacle,
+                dynamics,
+                &Footprint::point(),
+                horizon,
+            )
+            .unwrap()
+            .with_warm_start(warm_start);
+            let tic = Instant::now();
+            for _ in 0..STEPS {
+                planner.solve().unwrap();
+                let x_now = planner.next_position().to_vec();
+                planner.set_x_now(&x_now);
+            }
+            reused[k] = tic.elapsed();
+        }
+
+        println!("Horizon N = {} ({} steps)", steps, STEPS);
+        println!(
+            "  {:<34} : {:>10.3?} per step",
+            "main_loop (rebuilt)",
+            per_step(rebuilt)
+        );
+        for (warm_start, &total) in warm_starts.iter().zip(&reused) {
+            println!(
+                "  Planner, {:<25} : {:>10.3?} per step",
+                format!("{:?}", warm_start),
+                per_step(total)
+            );
+        }
+    }
+}
+
+fn per_step(total: Duration) -> Duration {
+    total / STEPS as u32
+}
@@ -0,0 +1,39 @@
+//! Random scenario files for the `plan` command-line planner
+//!
+//! `cargo run --release -p planner --example random_scenarios -- [COUNT] [SEED] [DIR]`
+//! writes `DIR/scenario_<i>.toml` (10 scenarios with seed 1 in the working
+//! directory by default). The same seed gives the same files.
+
+use obstacles::Footprint;
+use planner::{RobotModel, Scenario, ScenarioGenerator};
+use std::env;
+use std::path::PathBuf;
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let count: usize = args.get(1).map_or(10, |a| a.parse().expect("COUNT"));
+    let seed: usize = args.get(2).map_or(1, |a| a.parse().expect("SEED"));
+    let dir = PathBuf::from(args.get(3).map_or(".", |a| a.as_str()));
+
+    // Greedy planner for a circular holonomic robot, as in Example 09
+    let mut template = Scenario::new(
+        vec![0.0, 0.0],
+        vec![0.0, 0.0],
+        RobotModel::Holonomic { u_max: 0.1 },
+    );
+    template.footprint = Footprint::circle(0.2);
+    let generator = ScenarioGenerator::new(template, ((0.0, 20.0), (0.0, 20.0)))
+        .with_num_obstacles(8)
+        .with_seed(seed);
+
+    for index in 0..count {
+        match generator.generate(index) {
+            Ok(scenario) => {
+                let file = dir.join(format!("scenario_{}.toml", index));
+                scenario.save(&file).unwrap();
+                println!("{}: {} obstacles", file.display(), scenario.obstacles.len());
+            }
+            Err(e) => println!("scenario {}: {}", index, e),
+        }
+    }
+}
@@ -0,0 +1,322 @@
+use std::fmt;
+use std::time::{Duration, Instant};
+
+// For Parallel Runs
+use rayon::prelude::*;
+
+use crate::{Outcome, Scenario};
+
+/// Statistics of one planning run
+#[derive(Debug, Clone, PartialEq)]
+pub struct RunStats {
+    pub outcome: Outcome,
+    pub steps: usize,
+    pub path_length: f64,
+    /// See `Scenario::min_clearance`
+    pub min_clearance: f64,
+    /// Totals over all the time steps
+    pub outer_iterations: usize,
+    pub inner_iterations: usize,
+    /// Planning time, without loading the scenario
+    pub wall_time: Duration,
+}
+
+/// One scenario of a batch, with its statistics or the reason it did not run
+#[derive(Debug, Clone, PartialEq)]
+pub struct BatchRun {
+    pub name: String,
+    pub result: Result<RunStats, String>,
+}
+
+/// Results of `run_batch`, in the order of the scenarios
+///
+/// `Display` prints the summary table.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BatchReport {
+    pub runs: Vec<BatchRun>,
+}
+
+/// Plans every scenario, in parallel on the rayon thread pool
+///
+/// Scenarios that could not be loaded or generated are passed as `Err` and
+/// reported as errors, as are the invalid ones.
+pub fn run_batch(scenarios: Vec<(String, Result<Scenario, String>)>) -> BatchReport {
+    let runs = scenarios
+        .into_par_iter()
+        .map(|(name, scenario)| BatchRun {
+            name,
+            result: scenario.and_then(|scenario| run_stats(&scenario)),
+        })
+        .collect();
+    BatchReport { runs }
+}
+
+fn run_stats(scenario: &Scenario) -> Result<RunStats, String> {
+    let tic = Instant::now();
+    let plan = scenario.run_quietly().map_err(|e| e.to_string())?;
+    let wall_time = tic.elapsed();
+    Ok(RunStats {
+        outcome: plan.outcome,
+        steps: plan.steps(),
+        path_length: plan.length(),
+        min_clearance: scenario.min_clearance(&plan).map_err(|e| e.to_string())?,
+        outer_iterations: plan.solves.iter().map(|s| s.outer_iterations).sum(),
+        inner_iterations: plan.solves.iter().map(|s| s.inner_iterations).sum(),
+        wall_time,
+    })
+}
+
+impl BatchReport {
+    /// Runs that produced statistics (whatever their outcome)
+    pub fn completed(&self) -> impl Iterator<Item = &RunStats> {
+        self.runs.iter().filter_map(|run| run.result.as_ref().ok())
+    }
+
+    /// Fraction of all the runs (errors included) that reached the goal
+    pub fn success_rate(&self) -> f64 {
+        let reached = self
+            .completed()
+            .filter(|stats| stats.outcome == Outcome::Reached)
+            .count();
+        reached as f64 / self.runs.len().max(1) as f64
+    }
+
+    /// One row per run: `name,outcome,steps,path_length,min_clearance,
+    /// outer_iterations,inner_iterations,wall_time_s,error`
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from(
+            "name,outcome,steps,path_length,min_clearance,outer_iterations,inner_iterations,wall_time_s,error\n",
+        );
+        for run in &self.runs {
+            let name = quote(&run.name);
+            match &run.result {
+                Ok(stats) => csv.push_str(&format!(
+                    "{},{},{},{},{},{},{},{},\n",
+                    name,
+                    stats.outcome.code(),
+                    stats.steps,
+                    stats.path_length,
+                    stats.min_clearance,
+                    stats.outer_iterations,
+                    stats.inner_iterations,
+                    stats.wall_time.as_secs_f64(),
+                )),
+                Err(e) => csv.push_str(&format!("{},error,,,,,,,{}\n", name, quote(e))),
+            }
+        }
+        csv
+    }
+}
+
+/// CSV field, quoted if needed
+fn quote(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// `(mean, median, min, max)`, or `None` without values
+fn statistics(mut values: Vec<f64>) -> Option<(f64, f64, f64, f64)> {
+    if values.is_empty() {
+        return None;
+    }
+    values.sort_by(|a, b| a.total_cmp(b));
+    let n = values.len();
+    let mean = values.iter().sum::<f64>() / n as f64;
+    let median = if n % 2 == 1 {
+        values[n / 2]
+    } else {
+        0.5 * (values[n / 2 - 1] + values[n / 2])
+    };
+    Some((mean, median, values[0], values[n - 1]))
+}
+
+/// Row of the summary table: label and value of a run
+type Metric = (&'static str, fn(&RunStats) -> f64);
+
+fn per_step(total: usize, stats: &RunStats) -> f64 {
+    total as f64 / stats.steps.max(1) as f64
+}
+
+/// Outcome counts, then statistics over the completed runs
+impl fmt::Display for BatchReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let count = |code: &str| {
+            self.completed()
+                .filter(|stats| stats.outcome.code() == code)
+                .count()
+        };
+        let errors = self.runs.iter().filter(|run| run.result.is_err()).count();
+        writeln!(
+            f,
+            "runs: {}   reached: {} ({:.1} %)   max steps exceeded: {}   stalled: {}   timed out: {}   solver failed: {}   errors: {}",
+            self.runs.len(),
+            count("reached"),
+            100.0 * self.success_rate(),
+            count("max_steps_exceeded"),
+            count("stalled"),
+            count("timed_out"),
+            count("solver_failed"),
+            errors
+        )?;
+
+        let rows: [Metric; 6] = [
+            ("steps", |s| s.steps as f64),
+            ("path length", |s| s.path_length),
+            ("min clearance", |s| s.min_clearance),
+            ("outer iterations / step", |s| {
+                per_step(s.outer_iterations, s)
+            }),
+            ("inner iterations / step", |s| {
+                per_step(s.inner_iterations, s)
+            }),
+            ("wall time (s)", |s| s.wall_time.as_secs_f64()),
+        ];
+        writeln!(
+            f,
+            "{:<24} {:>12} {:>12} {:>12} {:>12}",
+            "", "mean", "median", "min", "max"
+        )?;
+        for (label, value) in rows.iter() {
+            match statistics(self.completed().map(value).collect()) {
+                Some((mean, median, min, max)) => writeln!(
+                    f,
+                    "{:<24} {:>12.4} {:>12.4} {:>12.4} {:>12.4}",
+                    label, mean, median, min, max
+                )?,
+                None => writeln!(f, "{:<24} {:>12}", label, "-")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use optimization_engine::SolverError;
+
+    fn stats(outcome: Outcome, steps: usize) -> RunStats {
+        RunStats {
+            outcome,
+            steps,
+            path_length: 2.5,
+            min_clearance: 0.25,
+            outer_iterations: 3 * steps,
+            inner_iterations: 40 * steps,
+            wall_time: Duration::from_millis(1500),
+        }
+    }
+
+    fn report() -> BatchReport {
+        let run = |name: &str, result| BatchRun {
+            name: name.to_string(),
+            result,
+        };
+        BatchReport {
+            runs: vec![
+                run("a.toml", Ok(stats(Outcome::Reached, 10))),
+                run("b, c.toml", Ok(stats(Outcome::Stalled, 20))),
+                run("random_0", Err("invalid scenario: \"goal\"".to_string())),
+                run("random_1", Ok(stats(Outcome::Reached, 30))),
+                run(
+                    "random_2",
+                    Ok(stats(
+                        Outcome::SolverFailed(SolverError::NotFiniteComputation),
+                        0,
+                    )),
+                ),
+            ],
+        }
+    }
+
+    #[test]
+    fn statistics_of_odd_and_even_counts() {
+        assert_eq!(statistics(vec![3.0, 1.0, 2.0]), Some((2.0, 2.0, 1.0, 3.0)));
+        assert_eq!(
+            statistics(vec![4.0, 1.0, 3.0, 2.0]),
+            Some((2.5, 2.5, 1.0, 4.0))
+        );
+        assert_eq!(statistics(vec![5.0]), Some((5.0, 5.0, 5.0, 5.0)));
+        assert_eq!(statistics(Vec::new()), None);
+    }
+
+    #[test]
+    fn fields_are_quoted_when_needed() {
+        assert_eq!(quote("random_0"), "random_0");
+        assert_eq!(quote("b, c.toml"), "\"b, c.toml\"");
+        assert_eq!(quote("a \"b\""), "\"a \"\"b\"\"\"");
+        assert_eq!(quote("two\nlines"), "\"two\nlines\"");
+    }
+
+    #[test]
+    fn success_rate_counts_the_errors() {
+        // 2 of the 5 runs, one of which did not run
+        assert_eq!(report().success_rate(), 0.4);
+        assert_eq!(report().completed().count(), 4);
+        assert_eq!(BatchReport { runs: Vec::new() }.success_rate(), 0.0);
+    }
+
+    #[test]
+    fn csv_has_a_row_per_run() {
+        let csv = report().to_csv();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 6);
+        assert_eq!(
+            lines[0],
+            "name,outcome,steps,path_length,min_clearance,outer_iterations,inner_iterations,wall_time_s,error"
+        );
+        assert_eq!(lines[1], "a.toml,reached,10,2.5,0.25,30,400,1.5,");
+        assert_eq!(lines[2], "\"b, c.toml\",stalled,20,2.5,0.25,60,800,1.5,");
+        assert_eq!(
+            lines[3],
+            "random_0,error,,,,,,,\"invalid scenario: \"\"goal\"\"\""
+        );
+        assert_eq!(lines[5], "random_2,solver_failed,0,2.5,0.25,0,0,1.5,");
+    }
+
+    #[test]
+    fn summary_counts_every_outcome() {
+        let summary = report().to_string();
+        assert!(summary.starts_with(
+            "runs: 5   reached: 2 (40.0 %)   max steps exceeded: 0   stalled: 1   timed out: 0   solver failed: 1   errors: 1"
+        ));
+        // Over the 4 completed runs
+        let steps = summary.lines().find(|l| l.starts_with("steps")).unwrap();
+        let values: Vec<f64> = steps
+            .split_whitespace()
+            .skip(1)
+            .map(|v| v.parse().unwrap())
+            .collect();
+        assert_eq!(values, vec![15.0, 15.0, 0.0, 30.0]);
+    }
+
+    #[test]
+    fn runs_in_error_are_reported() {
+        let report = run_batch(vec![
+            ("missing".to_string(), Err("cannot read".to_string())),
+            (
+                "short_start".to_string(),
+                Ok(Scenario::new(
+                    vec![0.0],
+                    vec![1.0, 1.0],
+                    crate::RobotModel::Holonomic { u_max: 0.1 },
+                )),
+            ),
+            (
+                "reached".to_string(),
+                Ok(Scenario::new(
+                    vec![0.0, 0.0],
+                    vec![0.3, 0.4],
+                    crate::RobotModel::Holonomic { u_max: 0.1 },
+                )),
+            ),
+        ]);
+        assert_eq!(report.runs[0].result, Err("cannot read".to_string()));
+        assert!(report.runs[1].result.is_err());
+        let stats = report.runs[2].result.as_ref().unwrap();
+        assert_eq!((stats.outcome, stats.steps), (Outcome::Reached, 5));
+    }
+}
@@ -0,0 +1,512 @@
+// For Automatic Differentiation
+use derivatives::Scalar;
+
+/// Discrete-time robot model `x_{k+1} = f(x_k, u_k)` used by the planner
+///
+/// The first `P` entries of the state are the position (`P = 2` in the plane,
+/// `3` in space). The inputs are the
+/// decision variables of the planner: their box bounds (or a ball, see
+/// `input_radius`) become the set `U` of the ALM problem, and any other
+/// input constraint is imposed through
+/// `F1` (one row each, zero when satisfied). The box bounds on the states
+/// are part of `U` with multiple shooting and an `F1` row otherwise (see
+/// `Shooting`).
+pub trait Dynamics<const P: usize = 2> {
+    /// Dimension of the state
+    fn state_dim(&self) -> usize;
+
+    /// Dimension of the inputs
+    fn input_dim(&self) -> usize;
+
+    /// State after one time step from `x` with input `u`
+    fn step<S: Scalar>(&self, x: &[S], u: &[S]) -> Vec<S>;
+
+    /// Duration of one time step (one unit of time unless the model has a
+    /// sampling time)
+    fn time_step(&self) -> f64 {
+        1.0
+    }
+
+    /// Lower and upper bounds on the inputs (infinite where unbounded)
+    fn input_bounds(&self) -> (Vec<f64>, Vec<f64>) {
+        let m = self.input_dim();
+        (vec![f64::NEG_INFINITY; m], vec![f64::INFINITY; m])
+    }
+
+    /// Lower and upper bounds on the states (infinite where unbounded)
+    fn state_bounds(&self) -> (Vec<f64>, Vec<f64>) {
+        let n = self.state_dim();
+        (vec![f64::NEG_INFINITY; n], vec![f64::INFINITY; n])
+    }
+
+    /// Radius `r` of the ball `|u| <= r` that replaces the box of
+    /// `input_bounds` in `U` (the inputs are projected onto it)
+    fn input_radius(&self) -> Option<f64> {
+        None
+    }
+
+    /// Number of input constraints imposed through `F1`
+    fn num_input_constraints(&self) -> usize {
+        0
+    }
+
+    /// Input constraints at `u`, written into `g`
+    fn input_constraints<S: Scalar>(&self, _u: &[S], _g: &mut [S]) {}
+}
+
+/// Fails on the first parameter that is not positive and finite
+fn check_positive(parameters: &[(&str, f64)]) -> Result<(), String> {
+    match parameters
+        .iter()
+        .find(|(_, v)| !(*v > 0.0 && v.is_finite()))
+    {
+        Some((name, value)) => Err(format!("{} must be positive (got {})", name, value)),
+        None => Ok(()),
+    }
+}
+
+/// Holonomic point robot: the input is the displacement, `|u| <= u_max`
+///
+/// This is the original model of the planner (state `(x, y)`), or its
+/// counterpart in `P` dimensions.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Holonomic<const P: usize = 2> {
+    u_max: f64, // Dynamics Radius
+}
+
+impl<const P: usize> Holonomic<P> {
+    /// Fails unless `u_max` is positive
+    pub fn new(u_max: f64) -> Result<Self, String> {
+        check_positive(&[("u_max", u_max)])?;
+        Ok(Holonomic { u_max })
+    }
+}
+
+impl<const P: usize> Dynamics<P> for Holonomic<P> {
+    fn state_dim(&self) -> usize {
+        P
+    }
+
+    fn input_dim(&self) -> usize {
+        P
+    }
+
+    fn step<S: Scalar>(&self, x: &[S], u: &[S]) -> Vec<S> {
+        x.iter()
+            .zip(u.iter())
+            .map(|(&x_i, &u_i)| x_i + u_i)
+            .collect()
+    }
+
+    fn input_bounds(&self) -> (Vec<f64>, Vec<f64>) {
+        (vec![-self.u_max; P], vec![self.u_max; P])
+    }
+
+    // Dynamics Constraint (C1)
+    fn input_radius(&self) -> Option<f64> {
+        Some(self.u_max)
+    }
+}
+
+/// Unicycle: state `(x, y, theta)`, inputs `(v, omega)`
+///
+/// `|v| <= v_max` and `|omega| <= omega_max`, imposed as box bounds.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Unicycle {
+    dt: f64,
+    v_max: f64,
+    omega_max: f64,
+}
+
+impl Unicycle {
+    /// Fails unless all the parameters are positive
+    pub fn new(dt: f64, v_max: f64, omega_max: f64) -> Result<Self, String> {
+        check_positive(&[("dt", dt), ("v_max", v_max), ("omega_max", omega_max)])?;
+        Ok(Unicycle {
+            dt,
+            v_max,
+            omega_max,
+        })
+    }
+}
+
+/// Unicycle step with the heading at the middle of the time step
+fn unicycle_step<S: Scalar>(dt: f64, x: &[S], v: S, omega: S) -> Vec<S> {
+    let heading = x[2] + omega * (dt / 2.0);
+    vec![
+        x[0] + v * heading.cos() * dt,
+        x[1] + v * heading.sin() * dt,
+        x[2] + omega * dt,
+    ]
+}
+
+impl Dynamics for Unicycle {
+    fn state_dim(&self) -> usize {
+        3
+    }
+
+    fn input_dim(&self) -> usize {
+        2
+    }
+
+    fn time_step(&self) -> f64 {
+        self.dt
+    }
+
+    fn step<S: Scalar>(&self, x: &[S], u: &[S]) -> Vec<S> {
+        unicycle_step(self.dt, x, u[0], u[1])
+    }
+
+    fn input_bounds(&self) -> (Vec<f64>, Vec<f64>) {
+        (
+            vec![-self.v_max, -self.omega_max],
+            vec![self.v_max, self.omega_max],
+        )
+    }
+}
+
+/// Differential-drive robot: state `(x, y, theta)`, inputs the wheel speeds
+/// `(omega_left, omega_right)` (rad/s), each bounded by `wheel_speed_max`
+///
+/// `v = r (omega_right + omega_left) / 2` and
+/// `omega = r (omega_right - omega_left) / track_width`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DifferentialDrive {
+    dt: f64,
+    wheel_radius: f64,
+    track_width: f64,
+    wheel_speed_max: f64,
+}
+
+impl DifferentialDrive {
+    /// Fails unless all the parameters are positive
+    pub fn new(
+        dt: f64,
+        wheel_radius: f64,
+        track_width: f64,
+        wheel_speed_max: f64,
+    ) -> Result<Self, String> {
+        check_positive(&[
+            ("dt", dt),
+            ("wheel_radius", wheel_radius),
+            ("track_width", track_width),
+            ("wheel_speed_max", wheel_speed_max),
+        ])?;
+        Ok(DifferentialDrive {
+            dt,
+            wheel_radius,
+            track_width,
+            wheel_speed_max,
+        })
+    }
+}
+
+impl Dynamics for DifferentialDrive {
+    fn state_dim(&self) -> usize {
+        3
+    }
+
+    fn input_dim(&self) -> usize {
+        2
+    }
+
+    fn time_step(&self) -> f64 {
+        self.dt
+    }
+
+    fn step<S: Scalar>(&self, x: &[S], u: &[S]) -> Vec<S> {
+        let v = (u[1] + u[0]) * (self.wheel_radius / 2.0);
+        let omega = (u[1] - u[0]) * (self.wheel_radius / self.track_width);
+        unicycle_step(self.dt, x, v, omega)
+    }
+
+    fn input_bounds(&self) -> (Vec<f64>, Vec<f64>) {
+        (
+            vec![-self.wheel_speed_max; 2],
+            vec![self.wheel_speed_max; 2],
+        )
+    }
+}
+
+/// Point mass with acceleration inputs: state `(x, y, v_x, v_y)`, inputs
+/// `(a_x, a_y)` (positions, then velocities, in `P` dimensions)
+///
+/// `|v_x|, |v_y| <= v_max` and `|a_x|, |a_y| <= a_max`, imposed as box bounds.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DoubleIntegrator<const P: usize = 2> {
+    dt: f64,
+    v_max: f64,
+    a_max: f64,
+}
+
+impl<const P: usize> DoubleIntegrator<P> {
+    /// Fails unless all the parameters are positive
+    pub fn new(dt: f64, v_max: f64, a_max: f64) -> Result<Self, String> {
+        check_positive(&[("dt", dt), ("v_max", v_max), ("a_max", a_max)])?;
+        Ok(DoubleIntegrator { dt, v_max, a_max })
+    }
+}
+
+impl<const P: usize> Dynamics<P> for DoubleIntegrator<P> {
+    fn state_dim(&self) -> usize {
+        2 * P
+    }
+
+    fn input_dim(&self) -> usize {
+        P
+    }
+
+    fn time_step(&self) -> f64 {
+        self.dt
+    }
+
+    // Exact discretisation for a constant acceleration over the time step
+    fn step<S: Scalar>(&self, x: &[S], u: &[S]) -> Vec<S> {
+        let dt = self.dt;
+        let positions = (0..P).map(|i| x[i] + x[P + i] * dt + u[i] * (dt * dt / 2.0));
+        let velocities = (0..P).map(|i| x[P + i] + u[i] * dt);
+        positions.chain(velocities).collect()
+    }
+
+    fn input_bounds(&self) -> (Vec<f64>, Vec<f64>) {
+        (vec![-self.a_max; P], vec![self.a_max; P])
+    }
+
+    fn state_bounds(&self) -> (Vec<f64>, Vec<f64>) {
+        let inf = f64::INFINITY;
+        let mut lower = vec![-inf; P];
+        let mut upper = vec![inf; P];
+        lower.extend(vec![-self.v_max; P]);
+        upper.extend(vec![self.v_max; P]);
+        (lower, upper)
+    }
+}
+
+/// Kinematic bicycle: state `(x, y, theta, v)`, inputs the acceleration and
+/// the steering angle `(a, delta)`
+///
+/// `omega = v tan(delta) / wheelbase`. `|v| <= v_max`, `|a| <= a_max` and
+/// `|delta| <= steering_max`, imposed as box bounds.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct KinematicBicycle {
+    dt: f64,
+    wheelbase: f64,
+    v_max: f64,
+    a_max: f64,
+    steering_max: f64,
+}
+
+impl KinematicBicycle {
+    /// Fails unless all the parameters are positive and the steering angle
+    /// stays below pi/2
+    pub fn new(
+        dt: f64,
+        wheelbase: f64,
+        v_max: f64,
+        a_max: f64,
+        steering_max: f64,
+    ) -> Result<Self, String> {
+        check_positive(&[
+            ("dt", dt),
+            ("wheelbase", wheelbase),
+            ("v_max", v_max),
+            ("a_max", a_max),
+            ("steering_max", steering_max),
+        ])?;
+        if steering_max >= std::f64::consts::FRAC_PI_2 {
+            return Err("steering_max must stay below pi/2".to_string());
+        }
+        Ok(KinematicBicycle {
+            dt,
+            wheelbase,
+            v_max,
+            a_max,
+            steering_max,
+        })
+    }
+}
+
+impl Dynamics for KinematicBicycle {
+    fn state_dim(&self) -> usize {
+        4
+    }
+
+    fn input_dim(&self) -> usize {
+        2
+    }
+
+    fn time_step(&self) -> f64 {
+        self.dt
+    }
+
+    // Unicycle step with the speed at the middle of the time step
+    fn step<S: Scalar>(&self, x: &[S], u: &[S]) -> Vec<S> {
+        let v = x[3] + u[0] * (self.dt / 2.0);
+        let omega = v * u[1].sin() / (u[1].cos() * self.wheelbase);
+        let mut x_next = unicycle_step(self.dt, x, v, omega);
+        x_next.push(x[3] + u[0] * self.dt);
+        x_next
+    }
+
+    fn input_bounds(&self) -> (Vec<f64>, Vec<f64>) {
+        (
+            vec![-self.a_max, -self.steering_max],
+            vec![self.a_max, self.steering_max],
+        )
+    }
+
+    fn state_bounds(&self) -> (Vec<f64>, Vec<f64>) {
+        let inf = f64::INFINITY;
+        (
+            vec![-inf, -inf, -inf, -self.v_max],
+            vec![inf, inf, inf, self.v_max],
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn invalid_parameters_are_rejected() {
+        assert!(Holonomic::<2>::new(0.1).is_ok());
+        assert_eq!(
+            Holonomic::<3>::new(f64::NAN).err(),
+            Some("u_max must be positive (got NaN)".to_string())
+        );
+        assert!(Unicycle::new(0.2, -1.0, 1.0).is_err());
+        assert!(DifferentialDrive::new(0.2, 0.1, 0.0, 5.0).is_err());
+        assert!(DoubleIntegrator::<2>::new(0.0, 1.0, 1.0).is_err());
+        assert!(KinematicBicycle::new(0.2, 0.5, 1.0, f64::INFINITY, 0.5).is_err());
+        assert_eq!(
+            KinematicBicycle::new(0.2, 0.5, 1.0, 1.0, 2.0).err(),
+            Some("steering_max must stay below pi/2".to_string())
+        );
+        assert!(KinematicBicycle::new(0.2, 0.5, 1.0, 1.0, 0.5).is_ok());
+    }
+
+    fn assert_close(actual: &[f64], expected: &[f64]) {
+        assert_eq!(actual.len(), expected.len());
+        for (a, e) in actual.iter().zip(expected) {
+            assert!((a - e).abs() < 1e-12, "{:?} != {:?}", actual, expected);
+        }
+    }
+
+    #[test]
+    fn holonomic_step_adds_the_displacement() {
+        let holonomic = Holonomic::<3>::new(0.1).unwrap();
+        let x_next = holonomic.step(&[1.0, 2.0, 3.0], &[0.1, -0.2, 0.05]);
+        assert_close(&x_next, &[1.1, 1.8, 3.05]);
+        assert_eq!(holonomic.time_step(), 1.0);
+        assert_eq!(holonomic.input_bounds(), (vec![-0.1; 3], vec![0.1; 3]));
+        assert_eq!(holonomic.input_radius(), Some(0.1));
+        assert_eq!(
+            holonomic.state_bounds(),
+            (vec![f64::NEG_INFINITY; 3], vec![f64::INFINITY; 3])
+        );
+    }
+
+    #[test]
+    fn unicycle_step_uses_the_midpoint_heading() {
+        let unicycle = Unicycle::new(0.2, 1.0, 2.0).unwrap();
+        let (v, omega) = (0.5, 0.4);
+        let heading = 0.3 + omega * 0.1;
+        assert_close(
+            &unicycle.step(&[1.0, 2.0, 0.3], &[v, omega]),
+            &[
+                1.0 + v * heading.cos() * 0.2,
+                2.0 + v * heading.sin() * 0.2,
+                0.3 + omega * 0.2,
+            ],
+        );
+        // Straight line without turning
+        assert_close(
+            &unicycle.step(&[0.0, 0.0, 0.0], &[1.0, 0.0]),
+            &[0.2, 0.0, 0.0],
+        );
+        assert_eq!(unicycle.time_step(), 0.2);
+        assert_eq!(unicycle.input_bounds(), (vec![-1.0, -2.0], vec![1.0, 2.0]));
+        assert_eq!(unicycle.input_radius(), None);
+        assert_eq!(
+            unicycle.state_bounds(),
+            (vec![f64::NEG_INFINITY; 3], vec![f64::INFINITY; 3])
+        );
+    }
+
+    #[test]
+    fn differential_drive_step_matches_the_unicycle() {
+        let drive = DifferentialDrive::new(0.2, 0.1, 0.5, 5.0).unwrap();
+        // v = 0.1 (2 + 4) / 2 and omega = 0.1 (4 - 2) / 0.5
+        let (v, omega) = (0.3, 0.4);
+        let x = [1.0, 2.0, 0.3];
+        let unicycle = Unicycle::new(0.2, 1.0, 1.0).unwrap();
+        assert_close(
+            &drive.step(&x, &[2.0, 4.0]),
+            &unicycle.step(&x, &[v, omega]),
+        );
+        // Equal wheel speeds drive straight ahead
+        assert_close(
+            &drive.step(&[0.0, 0.0, 0.0], &[3.0, 3.0]),
+            &[0.06, 0.0, 0.0],
+        );
+        assert_eq!(drive.input_bounds(), (vec![-5.0; 2], vec![5.0; 2]));
+        assert_eq!(
+            drive.state_bounds(),
+            (vec![f64::NEG_INFINITY; 3], vec![f64::INFINITY; 3])
+        );
+    }
+
+    #[test]
+    fn double_integrator_step_is_exact_for_a_constant_acceleration() {
+        let integrator = DoubleIntegrator::<2>::new(0.5, 1.0, 2.0).unwrap();
+        // p + v dt + a dt^2 / 2 and v + a dt
+        assert_close(
+            &integrator.step(&[1.0, 2.0, 0.4, -0.2], &[1.0, -2.0]),
+            &[1.325, 1.65, 0.9, -1.2],
+        );
+        assert_eq!(integrator.time_step(), 0.5);
+        assert_eq!(integrator.input_bounds(), (vec![-2.0; 2], vec![2.0; 2]));
+        let inf = f64::INFINITY;
+        assert_eq!(
+            integrator.state_bounds(),
+            (vec![-inf, -inf, -1.0, -1.0], vec![inf, inf, 1.0, 1.0])
+        );
+
+        let integrator = DoubleIntegrator::<3>::new(0.5, 1.0, 2.0).unwrap();
+        assert_eq!((integrator.state_dim(), integrator.input_dim()), (6, 3));
+        assert_close(
+            &integrator.step(&[0.0, 0.0, 1.0, 0.0, 0.0, 0.0], &[0.0, 0.0, -2.0]),
+            &[0.0, 0.0, 0.75, 0.0, 0.0, -1.0],
+        );
+    }
+
+    #[test]
+    fn bicycle_step_uses_the_midpoint_speed() {
+        let bicycle = KinematicBicycle::new(0.2, 0.5, 2.0, 1.0, 0.5).unwrap();
+        let (a, delta) = (0.5, 0.2);
+        let v = 1.0 + a * 0.1;
+        let omega = v * f64::tan(delta) / 0.5;
+        let heading = 0.1 + omega * 0.1;
+        assert_close(
+            &bicycle.step(&[0.0, 0.0, 0.1, 1.0], &[a, delta]),
+            &[
+                v * heading.cos() * 0.2,
+                v * heading.sin() * 0.2,
+                0.1 + omega * 0.2,
+                1.1,
+            ],
+        );
+        // Straight ahead without steering
+        assert_close(
+            &bicycle.step(&[0.0, 0.0, 0.0, 1.0], &[0.0, 0.0]),
+            &[0.2, 0.0, 0.0, 1.0],
+        );
+        assert_eq!(bicycle.input_bounds(), (vec![-1.0, -0.5], vec![1.0, 0.5]));
+        let inf = f64::INFINITY;
+        assert_eq!(
+            bicycle.state_bounds(),
+            (vec![-inf, -inf, -inf, -2.0], vec![inf, inf, inf, 2.0])
+        );
+    }
+}
@@ -0,0 +1,507 @@
+use std::f64::consts::PI;
+use std::fmt;
+
+// For Obstacles
+use obstacles::{Footprint, ObstacleSpec};
+// For Random Scenarios
+use rand::{Rng, SeedableRng, StdRng};
+// For Scenario Files
+use serde::{Deserialize, Serialize};
+
+use crate::{PlotSettings, Scenario};
+
+/// Kind of the obstacles drawn by a `ScenarioGenerator`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ObstacleKind {
+    Circle,
+    /// Axis-aligned, with both radii in the size range
+    Ellipse,
+    /// Star-shaped polygon with 3 to 7 vertices, not necessarily convex
+    Polygon,
+    /// Fixed size (see [`obstacles::Nlr1`])
+    Nlr1,
+    /// Fixed size (see [`obstacles::Nlr2`])
+    Nlr2,
+}
+
+/// Random scenarios for stress testing and benchmarking the planner
+///
+/// Every scenario copies the robot, footprint, horizon, limits and solver
+/// settings of a template and draws a start, a goal at least `min_distance`
+/// away and `num_obstacles` obstacles inside the `search_area`. The
+/// obstacles do not overlap: their enclosing circles are at least the
+/// diameter of the footprint apart. The start and the goal lie in free space,
+/// at least `clearance` away from the obstacles inflated by the footprint.
+///
+/// Scenario `index` only depends on the seed and on `index`, so that batches
+/// are reproducible and can be drawn in any order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScenarioGenerator {
+    template: Scenario,
+    search_area: ((f64, f64), (f64, f64)),
+    num_obstacles: usize,
+    kinds: Vec<ObstacleKind>,
+    size: (f64, f64),
+    min_distance: f64,
+    clearance: f64,
+    max_attempts: usize,
+    seed: usize,
+}
+
+/// Reasons for not generating a scenario
+#[derive(Debug, Clone, PartialEq)]
+pub enum GeneratorError {
+    /// A parameter of the generator is out of range (see
+    /// [`ScenarioGenerator::validate`])
+    Invalid(String),
+    /// No start and goal `min_distance` apart were drawn
+    NoStartAndGoal { min_distance: f64 },
+    /// The obstacles could not all be placed (the area is too crowded)
+    Crowded { placed: usize, requested: usize },
+}
+
+impl fmt::Display for GeneratorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GeneratorError::Invalid(reason) => write!(f, "invalid generator: {}", reason),
+            GeneratorError::NoStartAndGoal { min_distance } => write!(
+                f,
+                "no start and goal {} apart in the search area",
+                min_distance
+            ),
+            GeneratorError::Crowded { placed, requested } => write!(
+                f,
+                "only {} of {} obstacles fit in the search area",
+                placed, requested
+            ),
+        }
+    }
+}
+
+impl std::error::Error for GeneratorError {}
+
+impl ScenarioGenerator {
+    /// `search_area`: `((x_min, x_max), (y_min, y_max))`; the plot settings of
+    /// the template default to this area
+    pub fn new(template: Scenario, search_area: ((f64, f64), (f64, f64))) -> Self {
+        let ((x_min, x_max), (y_min, y_max)) = search_area;
+        ScenarioGenerator {
+            template,
+            search_area,
+            num_obstacles: 5,
+            kinds: vec![
+                ObstacleKind::Circle,
+                ObstacleKind::Ellipse,
+                ObstacleKind::Polygon,
+                ObstacleKind::Nlr1,
+                ObstacleKind::Nlr2,
+            ],
+            size: (0.5, 2.0),
+            min_distance: 0.5 * (x_max - x_min).min(y_max - y_min),
+            clearance: 0.5,
+            max_attempts: 1000,
+            seed: 1,
+        }
+    }
+
+    pub fn with_num_obstacles(mut self, num_obstacles: usize) -> Self {
+        self.num_obstacles = num_obstacles;
+        self
+    }
+
+    /// Kinds of obstacles, drawn with equal probability
+    pub fn with_kinds(mut self, kinds: Vec<ObstacleKind>) -> Self {
+        self.kinds = kinds;
+        self
+    }
+
+    /// Range of the radii of circles, ellipses and polygons
+    pub fn with_size(mut self, lower: f64, upper: f64) -> Self {
+        self.size = (lower, upper);
+        self
+    }
+
+    /// Smallest distance between the start and the goal
+    pub fn with_min_distance(mut self, min_distance: f64) -> Self {
+        self.min_distance = min_distance;
+        self
+    }
+
+    /// Smallest distance from the start and the goal to the inflated obstacles
+    pub fn with_clearance(mut self, clearance: f64) -> Self {
+        self.clearance = clearance;
+        self
+    }
+
+    /// Number of draws for every position before giving up
+    pub fn with_max_attempts(mut self, max_attempts: usize) -> Self {
+        self.max_attempts = max_attempts;
+        self
+    }
+
+    pub fn with_seed(mut self, seed: usize) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Checks the search area, the kinds, the sizes and the distances
+    pub fn validate(&self) -> Result<(), String> {
+        let ((x_min, x_max), (y_min, y_max)) = self.search_area;
+        let (lower, upper) = self.size;
+        let finite = [x_min, x_max, y_min, y_max, lower, upper];
+        if !finite.iter().all(|v| v.is_finite()) {
+            Err("the search area and the sizes must be finite".to_string())
+        } else if !(x_min < x_max && y_min < y_max) {
+            Err("the search area needs ordered ranges".to_string())
+        } else if self.kinds.is_empty() {
+            Err("at least one kind of obstacle is needed".to_string())
+        } else if !(0.0 < lower && lower <= upper) {
+            Err("sizes must be positive and ordered".to_string())
+        } else if !(self.min_distance >= 0.0 && self.min_distance.is_finite()) {
+            Err("min_distance must be non-negative".to_string())
+        } else if !(self.clearance >= 0.0 && self.clearance.is_finite()) {
+            Err("clearance must be non-negative".to_string())
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Scenario number `index` of the sequence
+    ///
+    /// Fails if the generator is invalid, if no start and goal `min_distance`
+    /// apart are found or if the obstacles do not all fit.
+    pub fn generate(&self, index: usize) -> Result<Scenario, GeneratorError> {
+        self.validate().map_err(GeneratorError::Invalid)?;
+        let mut rng: StdRng = SeedableRng::from_seed(&[self.seed, index][..]);
+        let ((x_min, x_max), (y_min, y_max)) = self.search_area;
+
+        // Start and goal
+        let (start, goal) = (0..self.max_attempts)
+            .map(|_| {
+                (
+                    self.sample_point(&mut rng, 0.0),
+                    self.sample_point(&mut rng, 0.0),
+                )
+            })
+            .find(|(s, g)| distance(*s, *g) >= self.min_distance)
+            .ok_or(GeneratorError::NoStartAndGoal {
+                min_distance: self.min_distance,
+            })?;
+
+        // Obstacles, as long as they fit
+        let footprint = &self.template.footprint;
+        let gap = 2.0 * footprint.radius();
+        let mut obstacles = Vec::new();
+        let mut circles: Vec<((f64, f64), f64)> = Vec::new(); // Enclosing circles
+        for _ in 0..self.num_obstacles {
+            let placed = (0..self.max_attempts).find_map(|_| {
+                let kind = self.kinds[rng.gen_range(0, self.kinds.len())];
+                // The enclosing circle of the shape is at `(x, y)`
+                let (spec, offset, radius) = self.sample_shape(&mut rng, kind);
+                let (x, y) = self.sample_point(&mut rng, radius);
+                let centre = (x - offset.0, y - offset.1);
+                let spec = with_centre(spec, centre);
+                let separated = circles
+                    .iter()
+                    .all(|&(c, r)| distance(c, (x, y)) >= r + radius + gap);
+                (separated && self.is_clear(&spec, footprint, start, goal))
+                    .then_some((spec, ((x, y), radius)))
+            });
+            match placed {
+                Some((spec, circle)) => {
+                    obstacles.push(spec);
+                    circles.push(circle);
+                }
+                None => {
+                    return Err(GeneratorError::Crowded {
+                        placed: obstacles.len(),
+                        requested: self.num_obstacles,
+                    })
+                }
+            }
+        }
+
+        let mut scenario = self.template.clone();
+        let heading = (goal.1 - start.1).atan2(goal.0 - start.0);
+        scenario.start = scenario.robot.initial_state(start, heading);
+        scenario.goal = vec![goal.0, goal.1];
+        scenario.obstacles = obstacles;
+        if scenario.plot.is_none() {
+            scenario.plot = Some(PlotSettings {
+                x_range: (x_min, x_max),
+                y_range: (y_min, y_max),
+                resolution: 0.05,
+                output: format!("scenario_{}.svg", index).into(),
+            });
+        }
+        Ok(scenario)
+    }
+
+    /// Uniform point of the search area, at least `margin` from its sides
+    fn sample_point(&self, rng: &mut StdRng, margin: f64) -> (f64, f64) {
+        let ((x_min, x_max), (y_min, y_max)) = self.search_area;
+        let mut range = |lower: f64, upper: f64| {
+            let (lower, upper) = (lower + margin, upper - margin);
+            if lower < upper {
+                rng.gen_range(lower, upper)
+            } else {
+                0.5 * (lower + upper)
+            }
+        };
+        let x = range(x_min, x_max);
+        (x, range(y_min, y_max))
+    }
+
+    /// Shape centred at the origin, with the centre and the radius of its
+    /// enclosing circle
+    fn sample_shape(
+        &self,
+        rng: &mut StdRng,
+        kind: ObstacleKind,
+    ) -> (ObstacleSpec, (f64, f64), f64) {
+        let origin = (0.0, 0.0);
+        match kind {
+            ObstacleKind::Circle => {
+                let radius = self.sample_size(rng);
+                let spec = ObstacleSpec::Circle {
+                    centre: origin,
+                    radius,
+                };
+                (spec, origin, radius)
+            }
+            ObstacleKind::Ellipse => {
+                let radii = (self.sample_size(rng), self.sample_size(rng));
+                let spec = ObstacleSpec::Ellipse {
+                    centre: origin,
+                    radii,
+                };
+                (spec, origin, radii.0.max(radii.1))
+            }
+            ObstacleKind::Polygon => {
+                // Counter-clockwise vertices, one in every sector of the circle
+                let radius = self.sample_size(rng);
+                let n = rng.gen_range(3, 8);
+                let vertices = (0..n)
+                    .map(|k| {
+                        let angle = 2.0 * PI * (k as f64 + rng.gen_range(0.1, 0.9)) / n as f64;
+                        let r = radius * rng.gen_range(0.5, 1.0);
+                        (r * angle.cos(), r * angle.sin())
+                    })
+                    .collect();
+                let spec = ObstacleSpec::Polygon {
+                    centre: origin,
+                    vertices,
+                };
+                (spec, origin, radius)
+            }
+            ObstacleKind::Nlr1 | ObstacleKind::Nlr2 => {
+                let spec = match kind {
+                    ObstacleKind::Nlr1 => ObstacleSpec::Nlr1 { centre: origin },
+                    _ => ObstacleSpec::Nlr2 { centre: origin },
+                };
+                // The circle around the bounding box
+                let bbox = spec.build().expect("valid shape").bounding_box();
+                let ((x0, x1), (y0, y1)) = (bbox.x_range(), bbox.y_range());
+                let radius = 0.5 * (x1 - x0).hypot(y1 - y0);
+                (spec, (0.5 * (x0 + x1), 0.5 * (y0 + y1)), radius)
+            }
+        }
+    }
+
+    fn sample_size(&self, rng: &mut StdRng) -> f64 {
+        let (lower, upper) = self.size;
+        if lower < upper {
+            rng.gen_range(lower, upper)
+        } else {
+            lower
+        }
+    }
+
+    /// Whether the start and the goal are `clearance` away from the inflated obstacle
+    fn is_clear(
+        &self,
+        spec: &ObstacleSpec,
+        footprint: &Footprint,
+        start: (f64, f64),
+        goal: (f64, f64),
+    ) -> bool {
+        let inflated = match spec.build_inflated(footprint) {
+            Ok(inflated) => inflated,
+            Err(_) => return false,
+        };
+        [start, goal].iter().all(|&(x, y)| {
+            !inflated.is_inside(&[x, y]) && inflated.signed_distance(&[x, y]) >= self.clearance
+        })
+    }
+}
+
+fn distance(a: (f64, f64), b: (f64, f64)) -> f64 {
+    (a.0 - b.0).hypot(a.1 - b.1)
+}
+
+/// The shape, moved to `centre`
+fn with_centre(spec: ObstacleSpec, centre: (f64, f64)) -> ObstacleSpec {
+    match spec {
+        ObstacleSpec::Circle { radius, .. } => ObstacleSpec::Circle { centre, radius },
+        ObstacleSpec::Ellipse { radii, .. } => ObstacleSpec::Ellipse { centre, radii },
+        ObstacleSpec::Polygon { vertices, .. } => ObstacleSpec::Polygon { centre, vertices },
+        ObstacleSpec::Nlr1 { .. } => ObstacleSpec::Nlr1 { centre },
+        ObstacleSpec::Nlr2 { .. } => ObstacleSpec::Nlr2 { centre },
+        spec => spec,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RobotModel;
+
+    fn generator() -> ScenarioGenerator {
+        let template = Scenario::new(
+            vec![0.0, 0.0],
+            vec![0.0, 0.0],
+            RobotModel::Holonomic { u_max: 0.1 },
+        );
+        ScenarioGenerator::new(template, ((0.0, 10.0), (0.0, 10.0)))
+    }
+
+    #[test]
+    fn invalid_parameters_are_reported() {
+        let invalid = |generator: ScenarioGenerator| {
+            matches!(generator.generate(0), Err(GeneratorError::Invalid(_)))
+        };
+        assert!(invalid(generator().with_kinds(Vec::new())));
+        assert!(invalid(generator().with_size(2.0, 1.0)));
+        assert!(invalid(generator().with_size(0.0, 1.0)));
+        assert!(invalid(generator().with_clearance(-1.0)));
+        let template = generator().template;
+        assert!(invalid(ScenarioGenerator::new(
+            template,
+            ((1.0, 0.0), (0.0, 1.0))
+        )));
+    }
+
+    #[test]
+    fn unreachable_min_distance_is_reported() {
+        // The diagonal of the search area is about 14.1
+        assert_eq!(
+            generator().with_min_distance(15.0).generate(0).err(),
+            Some(GeneratorError::NoStartAndGoal { min_distance: 15.0 })
+        );
+        let scenario = generator().with_num_obstacles(2).generate(0).unwrap();
+        assert_eq!(scenario.obstacles.len(), 2);
+    }
+
+    /// Centre and radius of a circle enclosing the shape
+    fn enclosing_circle(spec: &ObstacleSpec) -> ((f64, f64), f64) {
+        match spec {
+            ObstacleSpec::Circle { centre, radius } => (*centre, *radius),
+            ObstacleSpec::Ellipse { centre, radii } => (*centre, radii.0.max(radii.1)),
+            ObstacleSpec::Polygon { centre, vertices } => {
+                let radius = vertices
+                    .iter()
+                    .map(|&v| distance(v, (0.0, 0.0)))
+                    .fold(0.0, f64::max);
+                (*centre, radius)
+            }
+            spec => {
+                let bbox = spec.build().unwrap().bounding_box();
+                let ((x0, x1), (y0, y1)) = (bbox.x_range(), bbox.y_range());
+                let centre = (0.5 * (x0 + x1), 0.5 * (y0 + y1));
+                (centre, 0.5 * (x1 - x0).hypot(y1 - y0))
+            }
+        }
+    }
+
+    /// Obstacles of all kinds in a 20 m square, with a robot of radius 0.3
+    /// (gap 0.6)
+    fn dense() -> ScenarioGenerator {
+        let mut template = generator().template;
+        template.footprint = Footprint::circle(0.2).with_clearance(0.1);
+        ScenarioGenerator::new(template, ((-10.0, 10.0), (0.0, 20.0)))
+            .with_num_obstacles(6)
+            .with_size(0.4, 1.2)
+            .with_min_distance(10.0)
+            .with_clearance(0.4)
+            .with_seed(7)
+    }
+
+    #[test]
+    fn scenarios_only_depend_on_the_seed_and_the_index() {
+        let generator = dense();
+        let scenario = generator.generate(3).unwrap();
+        assert_eq!(generator.generate(3).unwrap(), scenario);
+        // In any order, and from a copy of the generator
+        let _ = generator.generate(0).unwrap();
+        assert_eq!(generator.clone().generate(3).unwrap(), scenario);
+
+        assert_ne!(generator.generate(4).unwrap(), scenario);
+        assert_ne!(
+            generator.clone().with_seed(8).generate(3).unwrap(),
+            scenario
+        );
+    }
+
+    #[test]
+    fn obstacles_are_apart_and_start_and_goal_are_clear() {
+        let generator = dense();
+        let gap = 2.0 * generator.template.footprint.radius();
+        for index in 0..20 {
+            let scenario = generator.generate(index).unwrap();
+            assert_eq!(scenario.obstacles.len(), 6);
+            assert!(scenario.validate().is_ok());
+
+            let circles: Vec<_> = scenario.obstacles.iter().map(enclosing_circle).collect();
+            for (i, &(c_i, r_i)) in circles.iter().enumerate() {
+                for &(c_j, r_j) in &circles[i + 1..] {
+                    assert!(distance(c_i, c_j) >= r_i + r_j + gap - 1e-12);
+                }
+            }
+
+            let start = (scenario.start[0], scenario.start[1]);
+            let goal = (scenario.goal[0], scenario.goal[1]);
+            assert!(distance(start, goal) >= generator.min_distance);
+            for spec in &scenario.obstacles {
+                let inflated = spec.build_inflated(&scenario.footprint).unwrap();
+                for &(x, y) in &[start, goal] {
+                    assert!(inflated.signed_distance(&[x, y]) >= generator.clearance);
+                    assert!(!inflated.is_inside(&[x, y]));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn start_faces_the_goal() {
+        let mut generator = dense();
+        generator.template = Scenario::new(
+            vec![0.0, 0.0, 0.0],
+            vec![0.0, 0.0],
+            RobotModel::Unicycle {
+                dt: 0.1,
+                v_max: 1.0,
+                omega_max: 1.0,
+            },
+        );
+        let scenario = generator.generate(0).unwrap();
+        let (dx, dy) = (
+            scenario.goal[0] - scenario.start[0],
+            scenario.goal[1] - scenario.start[1],
+        );
+        assert!((scenario.start[2] - dy.atan2(dx)).abs() < 1e-12);
+    }
+
+    #[test]
+    fn crowded_areas_are_reported() {
+        let generator = generator()
+            .with_num_obstacles(50)
+            .with_kinds(vec![ObstacleKind::Circle])
+            .with_size(2.0, 2.0)
+            .with_max_attempts(100);
+        assert!(matches!(
+            generator.generate(0),
+            Err(GeneratorError::Crowded { requested: 50, .. })
+        ));
+    }
+}
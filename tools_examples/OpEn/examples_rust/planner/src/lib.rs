@@ -0,0 +1,187 @@
+//! # Planner
+//!
+//! Obstacle avoidance path planner shared by Examples 09, 11, 12, 13 and 14.
+//!
+//! At every time step the next `N` inputs `u = (u_0, ..., u_{N-1})` of the
+//! robot model (`Dynamics`) are obtained by solving
+//! `min sum_k |p_{k+1} - x_ref|^2 + w |u_k|^2` subject to `u in U` and
+//! `F1(u) in C` with OpEn's ALM solver, where `p_k` is the position of the
+//! predicted state `x_k`, `U` holds the input bounds (a ball for
+//! `Holonomic`) and `F1` stacks the obstacle penalties (C2) and the other
+//! input constraints (C1) of every stage, which must be zero. Instead of the penalties, the signed distances
+//! to the obstacles can be kept above a margin (`ObstacleConstraint`). The
+//! state bounds are imposed through `F1` and `C` as well, or through `U` when
+//! the states are decision variables too (`Shooting::Multiple`, with the
+//! dynamics as equality constraints in `F1`).
+//! Only the first input is applied (receding horizon). Moving obstacles are
+//! placed where they will be when each predicted state is reached, and all
+//! obstacles are inflated by the `Footprint` of the robot.
+//!
+//! With the `Holonomic` model (the input is the displacement, `|u_k| <= u_max`),
+//! `N = 1` and `w = 0` this is the original greedy one-step planner.
+//!
+//! Positions have `P` entries: `P = 2` with the `ObstacleSpec` obstacles,
+//! and `P = 3` with the `ObstacleSpec3` ones and the 3D `Holonomic` or
+//! `DoubleIntegrator` models.
+//!
+//! The solver parameters are a `SolverSettings`. A `Scenario` describes a
+//! whole planning run (robot, obstacles, goal, solver and plot settings) and
+//! is read from a TOML or JSON file (Examples 09 and 11), or drawn at random
+//! by a `ScenarioGenerator`. `run_batch` plans many scenarios in parallel
+//! and collects their statistics (`BatchReport`). The path of a run is
+//! exported as timed waypoints with their heading and solver status
+//! (`PlannedPath`, CSV, JSON or GPX-like), and read back from CSV or JSON.
+
+// For Obstacles
+use obstacles::{BuildObstacle, Footprint};
+// For Time Limits
+use std::time::{Duration, Instant};
+
+mod batch;
+mod dynamics;
+mod generator;
+mod outcome;
+mod planner;
+mod problem;
+mod scenario;
+mod waypoints;
+
+pub use crate::planner::{Planner, SolverSettings, WarmStart};
+pub use batch::{run_batch, BatchReport, BatchRun, RunStats};
+pub use dynamics::{
+    DifferentialDrive, DoubleIntegrator, Dynamics, Holonomic, KinematicBicycle, Unicycle,
+};
+pub use generator::{GeneratorError, ObstacleKind, ScenarioGenerator};
+pub use outcome::{Limits, Outcome, Plan, PlanError, SolveStatus};
+
+use outcome::Progress;
+pub use problem::{Horizon, ObstacleConstraint, ProblemMaster, Shooting};
+pub use scenario::{PlotSettings, RobotModel, Scenario, ScenarioError};
+pub use waypoints::{PathFileError, PathFormat, PlannedPath, Waypoint, WaypointStatus};
+
+// == Optimisation Loop for Each Time Step ================================
+// One-off solve from scratch (see `Planner` to reuse the solver state)
+// Returns the N predicted states of the horizon, stacked
+pub fn main_loop<D: Dynamics<P>, O: BuildObstacle<P>, const P: usize>(
+    x_now: &[f64],
+    x_ref: &[f64],
+    x_obs: &[O],
+    dynamics: D,
+    footprint: &Footprint,
+    horizon: Horizon,
+) -> Result<Vec<f64>, PlanError> {
+    let mut planner = Planner::new(x_now, x_ref, x_obs, dynamics, footprint, horizon)?;
+    let _r = planner.solve()?;
+    // println!("\n\nSolver result : {:#.7?}\n", _r);
+
+    Ok(planner.predicted_states())
+}
+
+// == Main Function for Each Problem Instance =======================
+// Stops when the goal is reached or a limit is hit (see `Outcome`); fails if
+// the limits are out of range or an obstacle is invalid
+pub fn main_instance<D: Dynamics<P>, O: BuildObstacle<P>, const P: usize>(
+    x_start: &[f64],
+    x_ref: &[f64],
+    x_obs: &[O],
+    dynamics: D,
+    footprint: &Footprint,
+    horizon: Horizon,
+    limits: Limits,
+) -> Result<Plan, PlanError> {
+    limits.validate().map_err(PlanError::Limits)?;
+    let planner = Planner::new(x_start, x_ref, x_obs, dynamics, footprint, horizon)?;
+    Ok(run(planner, limits, true))
+}
+
+// Shortest time left for a solve under `Limits::max_wall_time` (OpEn panics
+// if it runs out of time before its first iteration)
+const MIN_SOLVE_TIME: Duration = Duration::from_millis(1);
+
+// Receding horizon loop of `main_instance` from the current state of a planner
+// built by the caller (e.g. with the solver settings of a scenario) towards its
+// goal; prints the positions if `verbose`
+pub(crate) fn run<D: Dynamics<P>, const P: usize>(
+    mut planner: Planner<D, P>,
+    limits: Limits,
+    verbose: bool,
+) -> Plan {
+    let print_position = |position: &[f64]| {
+        if verbose {
+            print_position(position)
+        }
+    };
+    let mut x_now = planner.problem().x_now().to_vec();
+    let x_ref = *planner.problem().x_ref();
+    let dt = planner.problem().dynamics().time_step();
+    let distance = |x: &[f64]| {
+        (0..P)
+            .map(|i| (x[i] - x_ref[i]).powi(2))
+            .sum::<f64>()
+            .sqrt()
+    };
+    let mut progress = Progress::new(distance(&x_now));
+
+    let mut path_result = vec![(x_now[0], x_now[1])];
+    let mut states = vec![x_now.clone()];
+    let mut solves = Vec::new();
+    print_position(&x_now[..P]);
+
+    let deadline = limits
+        .max_wall_time
+        .map(|seconds| Instant::now() + Duration::from_secs_f64(seconds));
+    let mut outcome = Outcome::MaxStepsExceeded;
+    for step in 1..=limits.max_steps {
+        if let Some(deadline) = deadline {
+            // A single solve may not outlast the run
+            let time_left = deadline.saturating_duration_since(Instant::now());
+            if time_left < MIN_SOLVE_TIME {
+                outcome = Outcome::TimedOut;
+                break;
+            }
+            planner.set_max_duration(Some(time_left));
+        }
+        // Apply the first waypoint only
+        let status = match planner.solve() {
+            Ok(status) => status,
+            Err(e) => {
+                outcome = Outcome::SolverFailed(e);
+                break;
+            }
+        };
+        if matches!(deadline, Some(deadline) if Instant::now() >= deadline) {
+            // The last solve may have been cut short
+            outcome = Outcome::TimedOut;
+            break;
+        }
+        solves.push(SolveStatus::from(&status));
+        x_now = planner.next_state().to_vec();
+        planner.set_x_now(&x_now);
+        planner.set_time(step as f64 * dt);
+        path_result.push((x_now[0], x_now[1]));
+        states.push(x_now.clone());
+        print_position(&x_now[..P]);
+
+        let delta = distance(&x_now);
+        if delta < limits.goal_tolerance {
+            outcome = Outcome::Reached;
+            break;
+        }
+        if progress.update(step, delta, &limits) {
+            outcome = Outcome::Stalled;
+            break;
+        }
+    }
+
+    Plan {
+        path: path_result,
+        states,
+        solves,
+        outcome,
+    }
+}
+
+fn print_position(position: &[f64]) {
+    let entries: Vec<String> = position.iter().map(|p| format!("{:#.6?}", p)).collect();
+    println!("Solution x_now = {}", entries.join("   "));
+}
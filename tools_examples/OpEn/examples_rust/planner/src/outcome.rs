@@ -0,0 +1,332 @@
+use obstacles::ObstacleError;
+use optimization_engine::alm::AlmOptimizerStatus;
+use optimization_engine::core::ExitStatus;
+use optimization_engine::SolverError;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::time::Duration;
+
+/// Limits on a planning run (`main_instance`)
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Limits {
+    /// Maximum number of time steps
+    pub max_steps: usize,
+    /// The goal is reached within this distance
+    pub goal_tolerance: f64,
+    /// Stalled if the closest distance to the goal has not decreased by
+    /// `stall_progress` over the last `stall_window` steps (at least 1)
+    pub stall_window: usize,
+    pub stall_progress: f64,
+    /// Wall time (s) after which the run stops, if any; a solve still
+    /// running then is cut short
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_wall_time: Option<f64>,
+}
+
+impl Limits {
+    /// Fails if `max_steps` is 0 (no step would be taken)
+    pub fn with_max_steps(mut self, max_steps: usize) -> Result<Self, String> {
+        self.max_steps = max_steps;
+        self.validate()?;
+        Ok(self)
+    }
+
+    /// Fails unless `goal_tolerance` is positive and finite
+    pub fn with_goal_tolerance(mut self, goal_tolerance: f64) -> Result<Self, String> {
+        self.goal_tolerance = goal_tolerance;
+        self.validate()?;
+        Ok(self)
+    }
+
+    /// Fails if `stall_window` is 0 (every run would stall after one step)
+    /// or `stall_progress` is negative
+    pub fn with_stall_detection(
+        mut self,
+        stall_window: usize,
+        stall_progress: f64,
+    ) -> Result<Self, String> {
+        self.stall_window = stall_window;
+        self.stall_progress = stall_progress;
+        self.validate()?;
+        Ok(self)
+    }
+
+    /// Fails unless `max_wall_time` is positive and finite
+    pub fn with_max_wall_time(mut self, max_wall_time: f64) -> Result<Self, String> {
+        self.max_wall_time = Some(max_wall_time);
+        self.validate()?;
+        Ok(self)
+    }
+
+    /// Checks that all the limits are in range
+    pub fn validate(&self) -> Result<(), String> {
+        if self.max_steps == 0 {
+            return Err("max_steps must be positive".to_string());
+        }
+        if !(self.goal_tolerance > 0.0 && self.goal_tolerance.is_finite()) {
+            return Err("goal_tolerance must be positive".to_string());
+        }
+        if self.stall_window == 0 {
+            return Err("stall_window must be at least 1".to_string());
+        }
+        if !(self.stall_progress >= 0.0 && self.stall_progress.is_finite()) {
+            return Err("stall_progress must be non-negative".to_string());
+        }
+        if let Some(max_wall_time) = self.max_wall_time {
+            if !(max_wall_time > 0.0 && max_wall_time.is_finite()) {
+                return Err("max_wall_time must be positive".to_string());
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits {
+            max_steps: 10_000,
+            goal_tolerance: 1e-5,
+            stall_window: 200,
+            stall_progress: 1e-3,
+            max_wall_time: None,
+        }
+    }
+}
+
+/// How a planning run ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    /// Within `goal_tolerance` of the goal
+    Reached,
+    /// `max_steps` steps without reaching the goal
+    MaxStepsExceeded,
+    /// No progress towards the goal over `stall_window` steps
+    Stalled,
+    /// `max_wall_time` elapsed before the goal was reached
+    TimedOut,
+    /// The solver returned an error
+    SolverFailed(SolverError),
+}
+
+impl Outcome {
+    /// Short name (`reached`, `max_steps_exceeded`, `stalled`, `timed_out`
+    /// or `solver_failed`), as written in the CSV and JSON files
+    pub fn code(&self) -> &'static str {
+        match self {
+            Outcome::Reached => "reached",
+            Outcome::MaxStepsExceeded => "max_steps_exceeded",
+            Outcome::Stalled => "stalled",
+            Outcome::TimedOut => "timed_out",
+            Outcome::SolverFailed(_) => "solver_failed",
+        }
+    }
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Outcome::Reached => write!(f, "goal reached"),
+            Outcome::MaxStepsExceeded => write!(f, "maximum number of steps exceeded"),
+            Outcome::Stalled => write!(f, "stalled (no progress towards the goal)"),
+            Outcome::TimedOut => write!(f, "wall-time limit exceeded"),
+            Outcome::SolverFailed(e) => write!(f, "solver failed ({:?})", e),
+        }
+    }
+}
+
+/// Solver statistics of one time step
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SolveStatus {
+    pub outer_iterations: usize,
+    pub inner_iterations: usize,
+    /// Whether ALM met its tolerances (the first input is applied anyway)
+    pub converged: bool,
+    pub solve_time: Duration,
+}
+
+impl From<&AlmOptimizerStatus> for SolveStatus {
+    fn from(status: &AlmOptimizerStatus) -> Self {
+        SolveStatus {
+            outer_iterations: status.num_outer_iterations(),
+            inner_iterations: status.num_inner_iterations(),
+            converged: status.exit_status() == ExitStatus::Converged,
+            solve_time: status.solve_time(),
+        }
+    }
+}
+
+/// Path of a planning run and how it ended
+#[derive(Debug, Clone, PartialEq)]
+pub struct Plan {
+    /// Positions `(x, y)`, from the start to the last position reached
+    pub path: Vec<(f64, f64)>,
+    /// Full states along the path (e.g. the height of 3D positions)
+    pub states: Vec<Vec<f64>>,
+    /// One entry per step of the path (the solve that led to `path[k + 1]`)
+    pub solves: Vec<SolveStatus>,
+    pub outcome: Outcome,
+}
+
+impl Plan {
+    /// Number of time steps taken
+    pub fn steps(&self) -> usize {
+        self.path.len() - 1
+    }
+
+    /// Length of the path `(x, y)`
+    pub fn length(&self) -> f64 {
+        self.path
+            .windows(2)
+            .map(|w| (w[1].0 - w[0].0).hypot(w[1].1 - w[0].1))
+            .sum()
+    }
+}
+
+/// Error of a single solve (`main_loop`), or of a planning run
+/// (`main_instance`) that cannot start
+#[derive(Debug, Clone, PartialEq)]
+pub enum PlanError {
+    Obstacle(ObstacleError),
+    Solver(SolverError),
+    /// The `Limits` are out of range (see `Limits::validate`)
+    Limits(String),
+    /// The start state or the goal does not match the robot model
+    Dimension(String),
+}
+
+impl fmt::Display for PlanError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PlanError::Obstacle(e) => write!(f, "invalid obstacle: {}", e),
+            PlanError::Solver(e) => write!(f, "solver failed ({:?})", e),
+            PlanError::Limits(reason) => write!(f, "invalid limits: {}", reason),
+            PlanError::Dimension(reason) => write!(f, "wrong dimension: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for PlanError {}
+
+impl From<ObstacleError> for PlanError {
+    fn from(e: ObstacleError) -> Self {
+        PlanError::Obstacle(e)
+    }
+}
+
+impl From<SolverError> for PlanError {
+    fn from(e: SolverError) -> Self {
+        PlanError::Solver(e)
+    }
+}
+
+/// Tracks the closest distance to the goal for the stall detection
+pub(crate) struct Progress {
+    best: f64,
+    best_step: usize,
+}
+
+impl Progress {
+    pub(crate) fn new(distance: f64) -> Self {
+        Progress {
+            best: distance,
+            best_step: 0,
+        }
+    }
+
+    /// Records the distance at `step`; true if stalled
+    pub(crate) fn update(&mut self, step: usize, distance: f64, limits: &Limits) -> bool {
+        if distance < self.best - limits.stall_progress {
+            self.best = distance;
+            self.best_step = step;
+        }
+        step - self.best_step >= limits.stall_window
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn limits_must_be_in_range() {
+        assert!(Limits::default().with_max_steps(0).is_err());
+        assert!(Limits::default().with_goal_tolerance(0.0).is_err());
+        assert!(Limits::default().with_goal_tolerance(f64::NAN).is_err());
+        let limits = Limits::default()
+            .with_max_steps(10)
+            .and_then(|limits| limits.with_goal_tolerance(1e-3))
+            .unwrap();
+        assert_eq!((limits.max_steps, limits.goal_tolerance), (10, 1e-3));
+
+        assert!(Limits::default().with_max_wall_time(0.0).is_err());
+        assert!(Limits::default().with_max_wall_time(f64::INFINITY).is_err());
+        let limits = Limits::default().with_max_wall_time(2.5).unwrap();
+        assert_eq!(limits.max_wall_time, Some(2.5));
+    }
+
+    #[test]
+    fn runs_stop_at_the_wall_time_limit() {
+        let run = |max_wall_time: f64| {
+            let limits = Limits::default().with_max_wall_time(max_wall_time).unwrap();
+            crate::main_instance(
+                &[0.0, 0.0],
+                &[1000.0, 0.0],
+                &[] as &[obstacles::ObstacleSpec],
+                crate::Holonomic::new(0.1).unwrap(),
+                &obstacles::Footprint::point(),
+                crate::Horizon::default(),
+                limits,
+            )
+            .unwrap()
+        };
+        // Out of time before the first step
+        let plan = run(1e-9);
+        assert_eq!(plan.outcome, Outcome::TimedOut);
+        assert_eq!(plan.steps(), 0);
+        assert!(plan.solves.is_empty());
+
+        // The 10000 steps to the goal take longer than this
+        let plan = run(0.2);
+        assert_eq!(plan.outcome, Outcome::TimedOut);
+        assert!(plan.steps() > 0);
+        assert_eq!(plan.solves.len(), plan.steps());
+    }
+
+    #[test]
+    fn main_instance_rejects_invalid_limits() {
+        let limits = Limits {
+            stall_window: 0,
+            ..Limits::default()
+        };
+        let plan = crate::main_instance(
+            &[0.0, 0.0],
+            &[1.0, 0.0],
+            &[] as &[obstacles::ObstacleSpec],
+            crate::Holonomic::new(0.1).unwrap(),
+            &obstacles::Footprint::point(),
+            crate::Horizon::default(),
+            limits,
+        );
+        assert_eq!(
+            plan,
+            Err(PlanError::Limits(
+                "stall_window must be at least 1".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn stall_window_must_be_positive() {
+        assert!(Limits::default().with_stall_detection(0, 1e-3).is_err());
+        assert!(Limits::default().with_stall_detection(1, -1.0).is_err());
+        let limits = Limits::default().with_stall_detection(3, 0.1).unwrap();
+
+        // Stalled once the distance has not improved by 0.1 over 3 steps
+        let mut progress = Progress::new(1.0);
+        assert!(!progress.update(1, 0.8, &limits));
+        assert!(!progress.update(2, 0.75, &limits));
+        assert!(!progress.update(3, 0.75, &limits));
+        assert!(progress.update(4, 0.75, &limits));
+    }
+}
@@ -0,0 +1,552 @@
+// For Optimisation
+use optimization_engine::{alm::*, constraints::*, panoc::*, *};
+// For Linear Algebra
+use nalgebra::base::*;
+// For Obstacles
+use obstacles::{BuildObstacle, Footprint};
+// For Scenario Files
+use serde::{Deserialize, Serialize};
+// For Time Limits
+use std::time::Duration;
+
+use crate::{Dynamics, Horizon, PlanError, ProblemMaster};
+
+/// Parameters of the ALM/PANOC solver
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SolverSettings {
+    /// Tolerance of the inner (PANOC) problems
+    pub tolerance: f64,
+    /// Memory of the LBFGS buffer
+    pub lbfgs_memory: usize,
+    pub initial_penalty: f64,
+    pub penalty_update_factor: f64,
+    pub initial_lagrange_multiplier: f64,
+    pub max_outer_iterations: usize,
+    /// Tolerance on the infeasibility `F1(u) in C`
+    pub delta_tolerance: f64,
+    /// Tolerance of the last inner problem
+    pub epsilon_tolerance: f64,
+    pub initial_inner_tolerance: f64,
+    pub inner_tolerance_update_factor: f64,
+    pub sufficient_decrease_coefficient: f64,
+}
+
+impl SolverSettings {
+    /// Checks the ranges required by OpEn (which panics otherwise): the
+    /// asserts of `AlmOptimizer` keep the penalty, its update factor and the
+    /// two coefficients `f64::EPSILON` away from their bounds
+    pub fn validate(&self) -> Result<(), String> {
+        const EPSILON: f64 = f64::EPSILON;
+        let positive = [
+            ("tolerance", self.tolerance),
+            ("delta_tolerance", self.delta_tolerance),
+            ("epsilon_tolerance", self.epsilon_tolerance),
+        ];
+        let unit_interval = [
+            (
+                "inner_tolerance_update_factor",
+                self.inner_tolerance_update_factor,
+            ),
+            (
+                "sufficient_decrease_coefficient",
+                self.sufficient_decrease_coefficient,
+            ),
+        ];
+        if let Some((name, _)) = positive.iter().find(|(_, v)| !(*v > 0.0 && v.is_finite())) {
+            Err(format!("{} must be positive", name))
+        } else if !(self.initial_penalty > EPSILON && self.initial_penalty.is_finite()) {
+            Err("initial_penalty must be greater than f64::EPSILON".to_string())
+        } else if let Some((name, _)) = unit_interval
+            .iter()
+            .find(|(_, v)| !(*v > EPSILON && *v < 1.0 - EPSILON))
+        {
+            Err(format!(
+                "{} must be between f64::EPSILON and 1 - f64::EPSILON",
+                name
+            ))
+        } else if !(self.penalty_update_factor > 1.0 + EPSILON
+            && self.penalty_update_factor.is_finite())
+        {
+            Err("penalty_update_factor must be greater than 1 + f64::EPSILON".to_string())
+        } else if !self.initial_lagrange_multiplier.is_finite() {
+            Err("initial_lagrange_multiplier must be finite".to_string())
+        } else if self.lbfgs_memory == 0 || self.max_outer_iterations == 0 {
+            Err("lbfgs_memory and max_outer_iterations must be positive".to_string())
+        } else if !self.initial_inner_tolerance.is_finite()
+            || self.initial_inner_tolerance < self.epsilon_tolerance
+        {
+            Err("initial_inner_tolerance must not be below epsilon_tolerance".to_string())
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl Default for SolverSettings {
+    fn default() -> Self {
+        SolverSettings {
+            tolerance: 1e-5,
+            lbfgs_memory: 5,
+            initial_penalty: 100.0,
+            penalty_update_factor: 1.05,
+            initial_lagrange_multiplier: 5.0,
+            max_outer_iterations: 200,
+            delta_tolerance: 1e-5,
+            epsilon_tolerance: 1e-4, // Absolute: the gradients of single shooting grow with the horizon
+            initial_inner_tolerance: 1e-2,
+            inner_tolerance_update_factor: 0.5,
+            sufficient_decrease_coefficient: 0.2,
+        }
+    }
+}
+
+/// Initial guess of `Planner::solve` after the robot has moved
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WarmStart {
+    /// Zero inputs (and the states they reach), default multipliers (as `main_loop`)
+    Cold,
+    /// Previous inputs shifted by one stage, default multipliers
+    #[default]
+    Inputs,
+    /// Previous inputs and Lagrange multipliers shifted by one stage
+    InputsAndMultipliers,
+}
+
+/// Planner that keeps its solver state across time steps
+///
+/// The caches are allocated once and `set_x_now` updates the problem in
+/// place. The initial guess of the next `solve` is set by `WarmStart`.
+///
+/// The default is `WarmStart::Inputs`. Reusing the multipliers is opt-in:
+/// with the exact multipliers the minimiser of every inner problem sits on a
+/// kink of the `max(0, .)` constraints, where PANOC can stall. It pays off
+/// when the inner problems are well conditioned, as with `Holonomic` on the
+/// `benchmark` scenario.
+pub struct Planner<D: Dynamics<P>, const P: usize = 2> {
+    pm: ProblemMaster<D, P>,
+    alm_cache: AlmCache,
+    u: Vec<f64>,      // Decision vector (solution of the last solve, or initial guess)
+    y: Vec<f64>,      // Lagrange multipliers of F1 (initial guess)
+    u_min: Vec<f64>,  // Bounds over the horizon (Set U)
+    u_max: Vec<f64>,  //
+    c_min: Vec<f64>,  // Bounds on F1 over the horizon (Set C)
+    c_max: Vec<f64>,  //
+    x_next: Vec<f64>, // State after the first input of the last solve
+    warm_start: WarmStart,
+    settings: SolverSettings,
+    max_duration: Option<Duration>, // Time limit of every solve
+}
+
+impl<D: Dynamics<P>, const P: usize> Planner<D, P> {
+    /// Fails if `x_start` is not a state of `dynamics`, if `x_ref` has fewer
+    /// than `P` entries (only its position is used), or if any of the
+    /// obstacle specifications or the footprint is invalid
+    pub fn new<O: BuildObstacle<P>>(
+        x_start: &[f64],
+        x_ref: &[f64],
+        x_obs: &[O],
+        dynamics: D,
+        footprint: &Footprint,
+        horizon: Horizon,
+    ) -> Result<Self, PlanError> {
+        if x_ref.len() < P {
+            return Err(PlanError::Dimension(format!(
+                "the goal has {} entries, a position {}",
+                x_ref.len(),
+                P
+            )));
+        }
+        let pm = ProblemMaster::init(
+            x_start,
+            SVector::from_column_slice(&x_ref[..P]),
+            x_obs,
+            dynamics,
+            footprint,
+            horizon,
+        )?;
+        let (u_min, u_max) = pm.bounds();
+        let (c_min, c_max) = pm.constraint_bounds();
+        let settings = SolverSettings::default();
+
+        Ok(Planner {
+            alm_cache: new_cache(&pm, &settings),
+            u: pm.cold_start(), // Initial guess
+            y: vec![settings.initial_lagrange_multiplier; pm.n1()],
+            u_min,
+            u_max,
+            c_min,
+            c_max,
+            x_next: x_start.to_vec(),
+            warm_start: WarmStart::Inputs,
+            settings,
+            max_duration: None,
+            pm,
+        })
+    }
+
+    pub fn with_warm_start(mut self, warm_start: WarmStart) -> Self {
+        self.warm_start = warm_start;
+        self
+    }
+
+    /// Fails if the settings are out of range (see `SolverSettings::validate`)
+    pub fn with_solver_settings(mut self, settings: SolverSettings) -> Result<Self, String> {
+        settings.validate()?;
+        self.alm_cache = new_cache(&self.pm, &settings);
+        self.y
+            .iter_mut()
+            .for_each(|y_i| *y_i = settings.initial_lagrange_multiplier);
+        self.settings = settings;
+        Ok(self)
+    }
+
+    pub fn solver_settings(&self) -> &SolverSettings {
+        &self.settings
+    }
+
+    /// Time limit of every `solve` (none by default), after which ALM stops
+    /// with `ExitStatus::NotConvergedOutOfTime`
+    pub fn set_max_duration(&mut self, max_duration: Option<Duration>) {
+        self.max_duration = max_duration;
+    }
+
+    /// Moves the robot to the state `x_now` and prepares the initial guess
+    pub fn set_x_now(&mut self, x_now: &[f64]) {
+        self.pm.set_x_now(x_now);
+        // (u_0, ..., u_{N-1}) -> (u_1, ..., u_{N-1}, u_{N-1}), and the same for the
+        // states of multiple shooting and for the multipliers
+        match self.warm_start {
+            WarmStart::Cold => self.u = self.pm.cold_start(),
+            WarmStart::Inputs | WarmStart::InputsAndMultipliers => {
+                shift_stages(&mut self.u, self.pm.nx_stage())
+            }
+        }
+        let n1_stage = self.pm.n1_stage();
+        let y_init = self.settings.initial_lagrange_multiplier;
+        match self.warm_start {
+            WarmStart::Cold | WarmStart::Inputs => self.y.iter_mut().for_each(|y_i| *y_i = y_init),
+            WarmStart::InputsAndMultipliers => shift_stages(&mut self.y, n1_stage),
+        }
+    }
+
+    /// Sets the current time, which places the moving obstacles
+    pub fn set_time(&mut self, t_now: f64) {
+        self.pm.set_time(t_now);
+    }
+
+    /// Inputs `(u_0, ..., u_{N-1})` of the last solve, stacked
+    pub fn inputs(&self) -> Vec<f64> {
+        self.pm.inputs(&self.u).collect::<Vec<_>>().concat()
+    }
+
+    /// Predicted states `(x_1, ..., x_N)` of the last solve, stacked
+    pub fn predicted_states(&self) -> Vec<f64> {
+        self.pm.states(&self.u).concat()
+    }
+
+    /// State after the first input of the last solve
+    pub fn next_state(&self) -> &[f64] {
+        &self.x_next
+    }
+
+    /// Position after the first input of the last solve
+    pub fn next_position(&self) -> &[f64] {
+        &self.x_next[..P]
+    }
+
+    pub fn problem(&self) -> &ProblemMaster<D, P> {
+        &self.pm
+    }
+
+    /// Solves the horizon problem from the current warm start
+    pub fn solve(&mut self) -> Result<AlmOptimizerStatus, SolverError> {
+        let pm = &self.pm;
+        let (n1, n2) = (pm.n1(), 0);
+
+        let set_c = Rectangle::new(Some(&self.c_min), Some(&self.c_max)); // Set C
+        let bounds = input_set(pm, &self.u_min, &self.u_max); // Set U
+        let set_y = Ball2::new(None, 1e12); // Set Y
+
+        // =============
+        // Re-define the functions linked to user parameters
+        let f = |u: &[f64], cost: &mut f64| -> Result<(), SolverError> {
+            pm.f(u, cost);
+            Ok(())
+        };
+
+        let df = |u: &[f64], grad: &mut [f64]| -> Result<(), SolverError> {
+            pm.df(u, grad);
+            Ok(())
+        };
+
+        let f1 = |u: &[f64], f1u: &mut [f64]| -> Result<(), SolverError> {
+            pm.f1(u, f1u);
+            Ok(())
+        };
+
+        let f1_jacobian_product =
+            |u: &[f64], d: &[f64], res: &mut [f64]| -> Result<(), SolverError> {
+                pm.f1_jacobian_product(u, d, res);
+                Ok(())
+            };
+        // ==============
+
+        // AlmFactory: Prepare function psi and its gradient
+        // given the problem data such as f, del_f and
+        // optionally F_1, JF_1, C, F_2
+        let factory = AlmFactory::new(
+            f,                         // Cost function
+            df,                        // Cost Gradient
+            Some(f1),                  // MappingF1
+            Some(f1_jacobian_product), // Jacobian Mapping F1 Trans
+            NO_MAPPING,                // MappingF2
+            NO_JACOBIAN_MAPPING,       // Jacobian Mapping F2 Trans
+            Some(set_c),               // Constraint set
+            n2,
+        );
+
+        // Define an optimisation problem
+        // to be solved with AlmOptimizer
+        let alm_problem = AlmProblem::new(
+            bounds,
+            Some(set_c),
+            Some(set_y),
+            |u: &[f64], xi: &[f64], cost: &mut f64| -> Result<(), SolverError> {
+                factory.psi(u, xi, cost)
+            },
+            |u: &[f64], xi: &[f64], grad: &mut [f64]| -> Result<(), SolverError> {
+                factory.d_psi(u, xi, grad)
+            },
+            Some(f1),
+            NO_MAPPING,
+            n1,
+            n2,
+        );
+
+        // The penalty is reset at every solve
+        let settings = &self.settings;
+        let mut alm_optimizer = AlmOptimizer::new(&mut self.alm_cache, alm_problem)
+            .with_delta_tolerance(settings.delta_tolerance)
+            .with_max_outer_iterations(settings.max_outer_iterations)
+            .with_epsilon_tolerance(settings.epsilon_tolerance)
+            .with_initial_inner_tolerance(settings.initial_inner_tolerance)
+            .with_inner_tolerance_update_factor(settings.inner_tolerance_update_factor)
+            .with_initial_penalty(settings.initial_penalty)
+            .with_penalty_update_factor(settings.penalty_update_factor)
+            .with_sufficient_decrease_coefficient(settings.sufficient_decrease_coefficient)
+            .with_initial_lagrange_multipliers(&self.y);
+        if let Some(max_duration) = self.max_duration {
+            alm_optimizer = alm_optimizer.with_max_duration(max_duration);
+        }
+
+        let status = alm_optimizer.solve(&mut self.u)?;
+        let m = pm.dynamics().input_dim();
+        self.x_next = pm.dynamics().step(pm.x_now(), &self.u[..m]);
+        if let Some(y) = status.lagrange_multipliers() {
+            self.y.copy_from_slice(y);
+        }
+        Ok(status)
+    }
+}
+
+/// Solver caches for the dimensions of the problem
+fn new_cache<D: Dynamics<P>, const P: usize>(
+    pm: &ProblemMaster<D, P>,
+    settings: &SolverSettings,
+) -> AlmCache {
+    // PANOCCache: All the information needed at every step of the algorithm
+    let panoc_cache = PANOCCache::new(pm.nx(), settings.tolerance, settings.lbfgs_memory);
+    // AlmCache: all the data that the ALM/PM algorithm updates
+    AlmCache::new(panoc_cache, pm.n1(), 0)
+}
+
+/// Set U: the box bounds, or per stage the ball of the inputs (see
+/// `Dynamics::input_radius`) and the box of the states
+fn input_set<'a, D: Dynamics<P>, const P: usize>(
+    pm: &ProblemMaster<D, P>,
+    u_min: &'a [f64],
+    u_max: &'a [f64],
+) -> CartesianProduct<'a> {
+    let nx = pm.nx();
+    let Some(radius) = pm.dynamics().input_radius() else {
+        return CartesianProduct::new()
+            .add_constraint(nx, Rectangle::new(Some(u_min), Some(u_max)));
+    };
+    let (m, nx_stage) = (pm.dynamics().input_dim(), pm.nx_stage());
+    let mut set = CartesianProduct::new_with_capacity(2 * pm.horizon().steps);
+    for start in (0..nx).step_by(nx_stage) {
+        set = set.add_constraint(start + m, Ball2::new(None, radius));
+        if nx_stage > m {
+            let states = start + m..start + nx_stage;
+            set = set.add_constraint(
+                start + nx_stage,
+                Rectangle::new(Some(&u_min[states.clone()]), Some(&u_max[states])),
+            );
+        }
+    }
+    set
+}
+
+/// Drops the first stage (`stage` entries) and repeats the last one
+fn shift_stages(v: &mut [f64], stage: usize) {
+    let n = v.len();
+    if n > stage {
+        v.copy_within(stage.., 0);
+        v.copy_within(n - 2 * stage..n - stage, n - stage);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{main_instance, Holonomic, Limits, Outcome, Unicycle};
+    use obstacles::ObstacleSpec;
+
+    const EPSILON: f64 = f64::EPSILON;
+
+    /// Whether the default settings with `change` applied are accepted
+    fn accepts(change: impl Fn(&mut SolverSettings)) -> bool {
+        let mut settings = SolverSettings::default();
+        change(&mut settings);
+        settings.validate().is_ok()
+    }
+
+    #[test]
+    fn initial_penalty_must_exceed_epsilon() {
+        assert!(!accepts(|s| s.initial_penalty = 1e-17));
+        assert!(!accepts(|s| s.initial_penalty = EPSILON));
+        assert!(accepts(|s| s.initial_penalty = 2.0 * EPSILON));
+    }
+
+    #[test]
+    fn penalty_update_factor_must_exceed_one_plus_epsilon() {
+        assert!(!accepts(|s| s.penalty_update_factor = 1.0));
+        assert!(!accepts(|s| s.penalty_update_factor = 1.0 + EPSILON));
+        assert!(accepts(|s| s.penalty_update_factor = 1.0 + 4.0 * EPSILON));
+        assert!(!accepts(|s| s.penalty_update_factor = f64::INFINITY));
+    }
+
+    #[test]
+    fn inner_tolerance_update_factor_must_stay_inside_the_unit_interval() {
+        assert!(!accepts(|s| s.inner_tolerance_update_factor = 1e-17));
+        assert!(!accepts(|s| s.inner_tolerance_update_factor = EPSILON));
+        assert!(accepts(|s| s.inner_tolerance_update_factor = 2.0 * EPSILON));
+        assert!(accepts(
+            |s| s.inner_tolerance_update_factor = 1.0 - 2.0 * EPSILON
+        ));
+        assert!(!accepts(|s| s.inner_tolerance_update_factor = 1.0 - EPSILON));
+    }
+
+    #[test]
+    fn sufficient_decrease_coefficient_must_stay_inside_the_unit_interval() {
+        assert!(!accepts(|s| s.sufficient_decrease_coefficient = 1e-17));
+        assert!(!accepts(|s| s.sufficient_decrease_coefficient = EPSILON));
+        assert!(accepts(
+            |s| s.sufficient_decrease_coefficient = 2.0 * EPSILON
+        ));
+        assert!(accepts(
+            |s| s.sufficient_decrease_coefficient = 1.0 - 2.0 * EPSILON
+        ));
+        assert!(!accepts(
+            |s| s.sufficient_decrease_coefficient = 1.0 - EPSILON
+        ));
+    }
+
+    #[test]
+    fn tolerances_must_be_positive() {
+        assert!(!accepts(|s| s.tolerance = 0.0));
+        assert!(!accepts(|s| s.delta_tolerance = 0.0));
+        assert!(!accepts(|s| s.epsilon_tolerance = 0.0));
+        assert!(!accepts(|s| s.initial_inner_tolerance = 1e-5));
+        assert!(accepts(|s| s.initial_inner_tolerance = 1e-4));
+    }
+
+    #[test]
+    fn invalid_solver_settings_are_rejected() {
+        let obstacles = [ObstacleSpec::Circle {
+            centre: (1.0, 0.0),
+            radius: 0.5,
+        }];
+        let planner = || {
+            Planner::new(
+                &[0.0, 0.0, 0.0],
+                &[2.0, 0.0, 0.0],
+                &obstacles,
+                Unicycle::new(0.2, 1.0, 1.0).unwrap(),
+                &Footprint::point(),
+                Horizon::new(5).unwrap(),
+            )
+            .unwrap()
+        };
+        let settings = SolverSettings {
+            penalty_update_factor: 0.5,
+            ..SolverSettings::default()
+        };
+        assert_eq!(
+            planner().with_solver_settings(settings).err(),
+            Some("penalty_update_factor must be greater than 1 + f64::EPSILON".to_string())
+        );
+        let planner = planner()
+            .with_solver_settings(SolverSettings::default())
+            .unwrap();
+        assert_eq!(planner.solver_settings(), &SolverSettings::default());
+    }
+
+    /// The steps of `Holonomic` are projected onto the ball in `U`: with
+    /// only the `F1` row, the solves along the ellipse took about ten times
+    /// more inner iterations (and Example 11 seemed to hang)
+    #[test]
+    fn holonomic_horizon_passes_an_ellipse() {
+        let obstacles = [ObstacleSpec::Ellipse {
+            centre: (3.0, 4.0),
+            radii: (1.5, 2.0),
+        }];
+        let plan = main_instance(
+            &[0.0, 0.0],
+            &[6.0, 8.0],
+            &obstacles,
+            Holonomic::new(0.1).unwrap(),
+            &Footprint::point(),
+            Horizon::new(5).unwrap(),
+            Limits::default().with_max_steps(150).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(plan.outcome, Outcome::Reached);
+        let inner_iterations: usize = plan.solves.iter().map(|s| s.inner_iterations).sum();
+        assert!(
+            inner_iterations < 50_000,
+            "{} inner iterations",
+            inner_iterations
+        );
+        let ellipse = obstacles[0].build().unwrap();
+        assert!(plan
+            .path
+            .iter()
+            .all(|p| ellipse.penalty(&[p.0, p.1]) < 1e-3));
+    }
+
+    #[test]
+    fn goal_must_hold_a_position() {
+        let new = |x_ref: &[f64]| {
+            Planner::new(
+                &[0.0, 0.0, 0.0],
+                x_ref,
+                &[] as &[ObstacleSpec],
+                Unicycle::new(0.2, 1.0, 1.0).unwrap(),
+                &Footprint::point(),
+                Horizon::default(),
+            )
+        };
+        assert_eq!(
+            new(&[1.0]).err(),
+            Some(PlanError::Dimension(
+                "the goal has 1 entries, a position 2".to_string()
+            ))
+        );
+        // The heading of a full goal state is ignored
+        assert!(new(&[1.0, 2.0]).is_ok());
+        assert!(new(&[1.0, 2.0, 0.5]).is_ok());
+    }
+}
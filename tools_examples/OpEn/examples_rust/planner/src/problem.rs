@@ -0,0 +1,557 @@
+// For Automatic Differentiation
+use derivatives::{forward, reverse, Scalar};
+// For Linear Algebra
+use nalgebra::base::*;
+// For Robot Models
+use crate::{Dynamics, PlanError};
+// For Obstacles
+use obstacles::{BuildObstacle, Footprint, MovingObstacle};
+// For Scenario Files
+use serde::{Deserialize, Serialize};
+
+/// How the predicted states enter the horizon problem
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Shooting {
+    /// The decision vector stacks the inputs and the states are obtained by
+    /// propagating the model; the bounded states are `F1` rows, with their
+    /// bounds in the set `C`
+    Single,
+    /// The decision vector stacks `(u_k, x_{k+1})` for every stage, the state
+    /// bounds are part of `U` and the dynamics `x_{k+1} = f(x_k, u_k)` are
+    /// equality constraints in `F1` (set `C = {0}`)
+    Multiple,
+}
+
+/// How the obstacles enter `F1`
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ObstacleConstraint {
+    /// One row per stage, the sum of the penalties `h(x) >= 0`, in `C = {0}`
+    Penalty,
+    /// One row per obstacle and stage, the signed distance `sdf(x)`, in the
+    /// half-line `[margin, inf)`
+    ///
+    /// Unlike the penalties, the rows are differentiable across the boundary
+    /// and have a gradient away from the obstacles. This helps the models with
+    /// inertia (`DoubleIntegrator`, `KinematicBicycle`) near the obstacles,
+    /// but costs more per solve with many obstacles and `Holonomic`.
+    SignedDistance { margin: f64 },
+}
+
+/// Receding horizon: number of stages and cost weights
+///
+/// Missing fields of scenario files take the values of `Horizon::default`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Horizon {
+    /// Number of stages `N` (the decision vector stacks `N` inputs)
+    pub steps: usize,
+    /// Weight of the control effort `|u_k|^2` (tracking has weight 1)
+    pub effort_weight: f64,
+    pub shooting: Shooting,
+    pub obstacle_constraint: ObstacleConstraint,
+}
+
+impl Horizon {
+    /// Fails if `steps` is 0
+    pub fn new(steps: usize) -> Result<Self, String> {
+        let horizon = Horizon {
+            steps,
+            effort_weight: 0.1,
+            shooting: Shooting::Single,
+            obstacle_constraint: ObstacleConstraint::Penalty,
+        };
+        horizon.validate()?;
+        Ok(horizon)
+    }
+
+    /// Checks that there is at least one step and that the weight and the
+    /// signed distance margin are in range
+    pub fn validate(&self) -> Result<(), String> {
+        if self.steps == 0 {
+            return Err("the horizon needs at least one step".to_string());
+        }
+        if !(self.effort_weight >= 0.0 && self.effort_weight.is_finite()) {
+            return Err("effort_weight must be non-negative".to_string());
+        }
+        if let ObstacleConstraint::SignedDistance { margin } = self.obstacle_constraint {
+            if !margin.is_finite() {
+                return Err("the signed distance margin must be finite".to_string());
+            }
+        }
+        Ok(())
+    }
+
+    pub fn with_effort_weight(mut self, effort_weight: f64) -> Self {
+        self.effort_weight = effort_weight;
+        self
+    }
+
+    pub fn with_shooting(mut self, shooting: Shooting) -> Self {
+        self.shooting = shooting;
+        self
+    }
+
+    pub fn with_obstacle_constraint(mut self, obstacle_constraint: ObstacleConstraint) -> Self {
+        self.obstacle_constraint = obstacle_constraint;
+        self
+    }
+}
+
+/// The original one-step greedy planner
+impl Default for Horizon {
+    fn default() -> Self {
+        Horizon {
+            steps: 1,
+            effort_weight: 0.0,
+            shooting: Shooting::Single,
+            obstacle_constraint: ObstacleConstraint::Penalty,
+        }
+    }
+}
+
+// == Problem Master =========================================
+// Decision vector u = (u_0, ..., u_{N-1}), the inputs of the robot model;
+// the states x_1, ..., x_N are obtained by propagating x_0 = x_now
+// (`Shooting::Single`) or stacked after each input (`Shooting::Multiple`).
+// The state x_k is reached at time t_now + k dt. Positions have P entries (2
+// in the plane, 3 in space)
+pub struct ProblemMaster<D: Dynamics<P>, const P: usize = 2> {
+    x_now: Vec<f64>,               // Robot State
+    t_now: f64,                    // Current Time
+    x_ref: SVector<f64, P>,        // Robot Goal Position
+    x_obs: Vec<MovingObstacle<P>>, // Obstacles
+    dynamics: D,                   // Robot Model
+    horizon: Horizon,              // Prediction Horizon
+    x_min: Vec<f64>,               // State Bounds
+    x_max: Vec<f64>,               //
+    bounded: Vec<usize>,           // States with a finite bound
+}
+
+impl<D: Dynamics<P>, const P: usize> ProblemMaster<D, P> {
+    /// The obstacles are inflated by the footprint of the robot
+    ///
+    /// Fails if `x_start` is not a state of `dynamics`, or if any of the
+    /// obstacle specifications or the footprint is invalid
+    pub fn init<O: BuildObstacle<P>>(
+        x_start: &[f64],
+        x_ref: SVector<f64, P>,
+        x_obs: &[O],
+        dynamics: D,
+        footprint: &Footprint,
+        horizon: Horizon,
+    ) -> Result<Self, PlanError> {
+        if x_start.len() != dynamics.state_dim() {
+            return Err(PlanError::Dimension(format!(
+                "the start state has {} entries, the robot model {}",
+                x_start.len(),
+                dynamics.state_dim()
+            )));
+        }
+        let x_obs = x_obs
+            .iter()
+            .map(|spec| spec.build_moving(footprint))
+            .collect::<Result<Vec<_>, _>>()?;
+        let (x_min, x_max) = dynamics.state_bounds();
+        let bounded = (0..x_min.len())
+            .filter(|&i| x_min[i].is_finite() || x_max[i].is_finite())
+            .collect();
+        Ok(Self {
+            x_now: x_start.to_vec(),
+            t_now: 0.0,
+            x_ref,
+            x_obs,
+            dynamics,
+            horizon,
+            x_min,
+            x_max,
+            bounded,
+        })
+    }
+
+    /// Moves the robot (the start of the horizon) to the state `x_now`
+    pub fn set_x_now(&mut self, x_now: &[f64]) {
+        self.x_now.copy_from_slice(x_now);
+    }
+
+    pub fn x_now(&self) -> &[f64] {
+        &self.x_now
+    }
+
+    /// Goal position
+    pub fn x_ref(&self) -> &SVector<f64, P> {
+        &self.x_ref
+    }
+
+    /// Sets the current time (the time of `x_now`), which places the
+    /// moving obstacles
+    pub fn set_time(&mut self, t_now: f64) {
+        self.t_now = t_now;
+    }
+
+    pub fn time(&self) -> f64 {
+        self.t_now
+    }
+
+    pub fn dynamics(&self) -> &D {
+        &self.dynamics
+    }
+
+    pub fn horizon(&self) -> Horizon {
+        self.horizon
+    }
+
+    /// Decision variables per stage: the input, and the state with
+    /// multiple shooting
+    pub fn nx_stage(&self) -> usize {
+        match self.horizon.shooting {
+            Shooting::Single => self.dynamics.input_dim(),
+            Shooting::Multiple => self.dynamics.input_dim() + self.dynamics.state_dim(),
+        }
+    }
+
+    /// Dimension of the decision variables
+    pub fn nx(&self) -> usize {
+        self.nx_stage() * self.horizon.steps
+    }
+
+    /// Rows of F1 per stage for the obstacles
+    fn n1_obstacles(&self) -> usize {
+        match self.horizon.obstacle_constraint {
+            ObstacleConstraint::Penalty => 1,
+            ObstacleConstraint::SignedDistance { .. } => self.x_obs.len(),
+        }
+    }
+
+    /// Rows of F1 per stage: obstacles, the input constraints, and the state
+    /// bounds (single shooting) or the dynamics (multiple shooting)
+    pub fn n1_stage(&self) -> usize {
+        let states = match self.horizon.shooting {
+            Shooting::Single => self.bounded.len(),
+            Shooting::Multiple => self.dynamics.state_dim(),
+        };
+        self.n1_obstacles() + self.dynamics.num_input_constraints() + states
+    }
+
+    /// Range dimension of F1
+    pub fn n1(&self) -> usize {
+        self.n1_stage() * self.horizon.steps
+    }
+
+    /// Bounds on the decision variables over the horizon (Set U)
+    pub fn bounds(&self) -> (Vec<f64>, Vec<f64>) {
+        let (mut u_min, mut u_max) = self.dynamics.input_bounds();
+        if self.horizon.shooting == Shooting::Multiple {
+            u_min.extend_from_slice(&self.x_min);
+            u_max.extend_from_slice(&self.x_max);
+        }
+        (
+            u_min.repeat(self.horizon.steps),
+            u_max.repeat(self.horizon.steps),
+        )
+    }
+
+    /// Bounds on F1 over the horizon (Set C): zero, except for the signed
+    /// distances and the bounded states of single shooting
+    pub fn constraint_bounds(&self) -> (Vec<f64>, Vec<f64>) {
+        let (mut c_min, mut c_max) = (vec![0.0; self.n1_stage()], vec![0.0; self.n1_stage()]);
+        let n_obstacles = self.n1_obstacles();
+        if let ObstacleConstraint::SignedDistance { margin } = self.horizon.obstacle_constraint {
+            c_min[..n_obstacles].iter_mut().for_each(|c| *c = margin);
+            c_max[..n_obstacles]
+                .iter_mut()
+                .for_each(|c| *c = f64::INFINITY);
+        }
+        if self.horizon.shooting == Shooting::Single {
+            let n_input = n_obstacles + self.dynamics.num_input_constraints();
+            for (j, &i) in self.bounded.iter().enumerate() {
+                c_min[n_input + j] = self.x_min[i];
+                c_max[n_input + j] = self.x_max[i];
+            }
+        }
+        (
+            c_min.repeat(self.horizon.steps),
+            c_max.repeat(self.horizon.steps),
+        )
+    }
+
+    /// Inputs `u_0, ..., u_{N-1}` of the decision vector
+    pub fn inputs<'a, S>(&self, u: &'a [S]) -> impl Iterator<Item = &'a [S]> {
+        let m = self.dynamics.input_dim();
+        u.chunks(self.nx_stage()).map(move |stage| &stage[..m])
+    }
+
+    /// States `x_1, ..., x_N` reached with the inputs of `u`
+    pub fn rollout<S: Scalar>(&self, u: &[S]) -> Vec<Vec<S>> {
+        let mut x = self
+            .x_now
+            .iter()
+            .map(|&x_i| S::from_f64(x_i))
+            .collect::<Vec<_>>();
+        self.inputs(u)
+            .map(|u_k| {
+                x = self.dynamics.step(&x, u_k);
+                x.clone()
+            })
+            .collect()
+    }
+
+    /// Predicted states `x_1, ..., x_N` of the decision vector
+    pub fn states<S: Scalar>(&self, u: &[S]) -> Vec<Vec<S>> {
+        match self.horizon.shooting {
+            Shooting::Single => self.rollout(u),
+            Shooting::Multiple => {
+                let m = self.dynamics.input_dim();
+                u.chunks(self.nx_stage())
+                    .map(|stage| stage[m..].to_vec())
+                    .collect()
+            }
+        }
+    }
+
+    /// Decision vector with zero inputs (and the states they reach)
+    pub fn cold_start(&self) -> Vec<f64> {
+        let mut u = vec![0.0; self.nx()];
+        if self.horizon.shooting == Shooting::Multiple {
+            let m = self.dynamics.input_dim();
+            let states = self.rollout(&u);
+            for (stage, x) in u.chunks_mut(self.nx_stage()).zip(states) {
+                stage[m..].copy_from_slice(&x);
+            }
+        }
+        u
+    }
+
+    // ========= Cost function (You need to modify this) =========
+    pub fn f_call<S: Scalar>(&self, u: &[S]) -> S {
+        let mut cost = S::from_f64(0.0);
+        let states = self.states(u);
+        for (x, u_k) in states.iter().zip(self.inputs(u)) {
+            // Tracking
+            for (&x_i, &x_ref_i) in x.iter().zip(self.x_ref.iter()) {
+                cost = cost + (x_i - x_ref_i).powi(2);
+            }
+            // Control effort
+            let effort = u_k.iter().fold(S::from_f64(0.0), |e, &u_i| e + u_i * u_i);
+            cost = cost + effort * self.horizon.effort_weight;
+        }
+        cost
+    }
+    // ===========================================================
+
+    pub fn f(&self, u: &[f64], cost: &mut f64) {
+        *cost = self.f_call(u);
+    }
+
+    // Gradient of the cost function (by forward-mode AD of f_call)
+    pub fn df(&self, u: &[f64], grad: &mut [f64]) {
+        forward::gradient(|u| self.f_call(u), u, grad);
+    }
+
+    // ========= F1 Constraint (You need to modify this) =========
+    // Rows of stage k: obstacles at x_{k+1} (see `ObstacleConstraint`), the constraints of u_k, then the
+    // bounded entries of x_{k+1} or the dynamics x_{k+1} - f(x_k, u_k)
+    pub fn f1_call<S: Scalar>(&self, u: &[S]) -> Vec<S> {
+        let n1_stage = self.n1_stage();
+        let n_obstacles = self.n1_obstacles();
+        let n_input = n_obstacles + self.dynamics.num_input_constraints();
+        let mut f1u = vec![S::from_f64(0.0); self.n1()];
+        let states = self.states(u);
+        let mut x_prev = self
+            .x_now
+            .iter()
+            .map(|&x_i| S::from_f64(x_i))
+            .collect::<Vec<_>>();
+        let dt = self.dynamics.time_step();
+        let stages = states.iter().zip(self.inputs(u)).enumerate();
+        for ((k, (x, u_k)), f1u_k) in stages.zip(f1u.chunks_mut(n1_stage)) {
+            // Obstacle Avoidance Constraint (C2), where the obstacles will be
+            let t = self.t_now + (k + 1) as f64 * dt;
+            match self.horizon.obstacle_constraint {
+                ObstacleConstraint::Penalty => {
+                    for obstacle in &self.x_obs {
+                        f1u_k[0] = f1u_k[0] + penalty(obstacle, t, x);
+                    }
+                }
+                ObstacleConstraint::SignedDistance { .. } => {
+                    for (f1u_i, obstacle) in f1u_k.iter_mut().zip(&self.x_obs) {
+                        *f1u_i = signed_distance(obstacle, t, x);
+                    }
+                }
+            }
+
+            // Dynamics Constraint (C1)
+            self.dynamics
+                .input_constraints(u_k, &mut f1u_k[n_obstacles..n_input]);
+            match self.horizon.shooting {
+                Shooting::Single => {
+                    for (f1u_i, &i) in f1u_k[n_input..].iter_mut().zip(&self.bounded) {
+                        *f1u_i = x[i];
+                    }
+                }
+                Shooting::Multiple => {
+                    let x_next = self.dynamics.step(&x_prev, u_k);
+                    for ((f1u_i, &x_i), x_next_i) in f1u_k[n_input..].iter_mut().zip(x).zip(x_next)
+                    {
+                        *f1u_i = x_i - x_next_i;
+                    }
+                }
+            }
+            x_prev = x.clone();
+        }
+
+        f1u
+    }
+    // ===========================================================
+
+    pub fn f1(&self, u: &[f64], f1u: &mut [f64]) {
+        let f1u_vec = self.f1_call(u);
+        f1u[..f1u_vec.len()].copy_from_slice(&f1u_vec);
+    }
+
+    // Jacobian Product (JF_1^{\top}*d) (by reverse-mode AD of f1_call)
+    pub fn f1_jacobian_product(&self, u: &[f64], d: &[f64], res: &mut [f64]) {
+        reverse::jacobian_transpose_product(|u| self.f1_call(u), u, d, res);
+    }
+}
+
+/// Obstacle penalty at time `t` at the position of the state `x`, carrying
+/// its closed-form gradient
+fn penalty<S: Scalar, const P: usize>(obstacle: &MovingObstacle<P>, t: f64, x: &[S]) -> S {
+    let p: [f64; P] = std::array::from_fn(|i| x[i].value());
+    let mut grad = [0.0; P];
+    obstacle.gradient(t, &p, &mut grad);
+    S::chain(&x[..P], obstacle.penalty(t, &p), &grad)
+}
+
+/// Signed distance to the obstacle at time `t` from the position of the
+/// state `x`, carrying its gradient
+fn signed_distance<S: Scalar, const P: usize>(obstacle: &MovingObstacle<P>, t: f64, x: &[S]) -> S {
+    let p: [f64; P] = std::array::from_fn(|i| x[i].value());
+    let mut grad = [0.0; P];
+    obstacle.signed_distance_gradient(t, &p, &mut grad);
+    S::chain(&x[..P], obstacle.signed_distance(t, &p), &grad)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DoubleIntegrator, Unicycle};
+    use derivatives::check::{check_derivatives, CheckOptions};
+    use obstacles::ObstacleSpec;
+    use optimization_engine::SolverError;
+
+    /// Checks `df` and `f1_jacobian_product` against finite differences, with
+    /// obstacles that the sampled stages enter
+    fn assert_derivatives<D: Dynamics<2>>(x_start: &[f64], dynamics: D, shooting: Shooting) {
+        let obstacles = [
+            ObstacleSpec::Circle {
+                centre: (0.5, 0.3),
+                radius: 0.6,
+            },
+            ObstacleSpec::Ellipse {
+                centre: (-0.4, -0.2),
+                radii: (0.5, 0.3),
+            },
+        ];
+        let horizon = Horizon::new(3)
+            .unwrap()
+            .with_effort_weight(0.1)
+            .with_shooting(shooting);
+        let pm = ProblemMaster::init(
+            x_start,
+            Vector2::new(2.0, 1.0),
+            &obstacles,
+            dynamics,
+            &Footprint::point(),
+            horizon,
+        )
+        .unwrap();
+
+        let (grad_report, jac_report) = check_derivatives(
+            &CheckOptions::new(pm.nx()).with_num_points(20),
+            |u: &[f64], cost: &mut f64| -> Result<(), SolverError> {
+                pm.f(u, cost);
+                Ok(())
+            },
+            |u: &[f64], grad: &mut [f64]| -> Result<(), SolverError> {
+                pm.df(u, grad);
+                Ok(())
+            },
+            |u: &[f64], f1u: &mut [f64]| -> Result<(), SolverError> {
+                pm.f1(u, f1u);
+                Ok(())
+            },
+            |u: &[f64], d: &[f64], res: &mut [f64]| -> Result<(), SolverError> {
+                pm.f1_jacobian_product(u, d, res);
+                Ok(())
+            },
+            pm.n1(),
+        )
+        .unwrap();
+        assert!(grad_report.passed(), "{}", grad_report);
+        assert!(jac_report.passed(), "{}", jac_report);
+    }
+
+    #[test]
+    fn horizon_needs_a_step() {
+        assert!(Horizon::new(0).is_err());
+        assert_eq!(Horizon::new(4).unwrap().steps, 4);
+        assert!(Horizon::default().validate().is_ok());
+        let horizon = Horizon {
+            effort_weight: -1.0,
+            ..Horizon::default()
+        };
+        assert!(horizon.validate().is_err());
+    }
+
+    #[test]
+    fn derivatives_single_shooting() {
+        assert_derivatives(
+            &[0.0, 0.0, 0.0],
+            Unicycle::new(0.2, 1.0, 1.0).unwrap(),
+            Shooting::Single,
+        );
+        assert_derivatives(
+            &[0.0, 0.0, 0.0, 0.0],
+            DoubleIntegrator::new(0.2, 1.0, 1.0).unwrap(),
+            Shooting::Single,
+        );
+    }
+
+    #[test]
+    fn derivatives_multiple_shooting() {
+        assert_derivatives(
+            &[0.0, 0.0, 0.0],
+            Unicycle::new(0.2, 1.0, 1.0).unwrap(),
+            Shooting::Multiple,
+        );
+        assert_derivatives(
+            &[0.0, 0.0, 0.0, 0.0],
+            DoubleIntegrator::new(0.2, 1.0, 1.0).unwrap(),
+            Shooting::Multiple,
+        );
+    }
+
+    #[test]
+    fn start_state_must_match_the_model() {
+        let init = |x_start: &[f64]| {
+            ProblemMaster::init(
+                x_start,
+                Vector2::new(2.0, 1.0),
+                &[] as &[ObstacleSpec],
+                Unicycle::new(0.2, 1.0, 1.0).unwrap(),
+                &Footprint::point(),
+                Horizon::default(),
+            )
+        };
+        assert_eq!(
+            init(&[0.0, 0.0]).err(),
+            Some(PlanError::Dimension(
+                "the start state has 2 entries, the robot model 3".to_string()
+            ))
+        );
+        assert!(init(&[0.0, 0.0, 0.0]).is_ok());
+    }
+}
@@ -0,0 +1,780 @@
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+// For Obstacles
+use obstacles::{Footprint, MapError, ObstacleError, ObstacleSpec, OccupancyGrid};
+// For Scenario Files
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    run, DifferentialDrive, DoubleIntegrator, Dynamics, Holonomic, Horizon, KinematicBicycle,
+    Limits, Plan, PlanError, PlannedPath, Planner, SolverSettings, Unicycle, WarmStart,
+};
+
+/// Robot model of a scenario, with the parameters of its constructor
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "model", rename_all = "snake_case")]
+pub enum RobotModel {
+    /// See [`Holonomic`]
+    Holonomic { u_max: f64 },
+    /// See [`Unicycle`]
+    Unicycle { dt: f64, v_max: f64, omega_max: f64 },
+    /// See [`DifferentialDrive`]
+    DifferentialDrive {
+        dt: f64,
+        wheel_radius: f64,
+        track_width: f64,
+        wheel_speed_max: f64,
+    },
+    /// See [`DoubleIntegrator`]
+    DoubleIntegrator { dt: f64, v_max: f64, a_max: f64 },
+    /// See [`KinematicBicycle`]
+    KinematicBicycle {
+        dt: f64,
+        wheelbase: f64,
+        v_max: f64,
+        a_max: f64,
+        steering_max: f64,
+    },
+}
+
+impl RobotModel {
+    /// Number of entries of the state (and of `Scenario::start`)
+    pub fn state_dim(&self) -> usize {
+        match self {
+            RobotModel::Holonomic { .. } => 2,
+            RobotModel::Unicycle { .. } | RobotModel::DifferentialDrive { .. } => 3,
+            RobotModel::DoubleIntegrator { .. } | RobotModel::KinematicBicycle { .. } => 4,
+        }
+    }
+
+    /// Duration of one time step (see `Dynamics::time_step`)
+    pub fn time_step(&self) -> f64 {
+        match *self {
+            RobotModel::Holonomic { .. } => 1.0,
+            RobotModel::Unicycle { dt, .. }
+            | RobotModel::DifferentialDrive { dt, .. }
+            | RobotModel::DoubleIntegrator { dt, .. }
+            | RobotModel::KinematicBicycle { dt, .. } => dt,
+        }
+    }
+
+    /// State at rest at `position`, facing `heading` (rad) if the model has
+    /// a heading
+    pub fn initial_state(&self, position: (f64, f64), heading: f64) -> Vec<f64> {
+        let (x, y) = position;
+        match self {
+            RobotModel::Holonomic { .. } => vec![x, y],
+            RobotModel::Unicycle { .. } | RobotModel::DifferentialDrive { .. } => {
+                vec![x, y, heading]
+            }
+            RobotModel::DoubleIntegrator { .. } => vec![x, y, 0.0, 0.0],
+            RobotModel::KinematicBicycle { .. } => vec![x, y, heading, 0.0],
+        }
+    }
+
+    /// Index of the heading (rad) in the state, if the model has one
+    pub fn heading_index(&self) -> Option<usize> {
+        match self {
+            RobotModel::Unicycle { .. }
+            | RobotModel::DifferentialDrive { .. }
+            | RobotModel::KinematicBicycle { .. } => Some(2),
+            RobotModel::Holonomic { .. } | RobotModel::DoubleIntegrator { .. } => None,
+        }
+    }
+
+    /// All the parameters must be positive (and the steering angle below pi/2),
+    /// as checked by the constructors of the models
+    pub fn validate(&self) -> Result<(), String> {
+        match *self {
+            RobotModel::Holonomic { u_max } => Holonomic::<2>::new(u_max).map(drop),
+            RobotModel::Unicycle {
+                dt,
+                v_max,
+                omega_max,
+            } => Unicycle::new(dt, v_max, omega_max).map(drop),
+            RobotModel::DifferentialDrive {
+                dt,
+                wheel_radius,
+                track_width,
+                wheel_speed_max,
+            } => DifferentialDrive::new(dt, wheel_radius, track_width, wheel_speed_max).map(drop),
+            RobotModel::DoubleIntegrator { dt, v_max, a_max } => {
+                DoubleIntegrator::<2>::new(dt, v_max, a_max).map(drop)
+            }
+            RobotModel::KinematicBicycle {
+                dt,
+                wheelbase,
+                v_max,
+                a_max,
+                steering_max,
+            } => KinematicBicycle::new(dt, wheelbase, v_max, a_max, steering_max).map(drop),
+        }
+    }
+}
+
+/// Figure of a scenario: the plotted area and the output file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlotSettings {
+    pub x_range: (f64, f64),
+    pub y_range: (f64, f64),
+    /// Spacing of the grid points drawn inside the obstacles
+    #[serde(default = "default_resolution")]
+    pub resolution: f64,
+    /// SVG file, relative to the working directory
+    #[serde(default = "default_output")]
+    pub output: PathBuf,
+}
+
+fn default_resolution() -> f64 {
+    0.05
+}
+
+fn default_output() -> PathBuf {
+    PathBuf::from("path_result.svg")
+}
+
+impl PlotSettings {
+    /// `((x_min, x_max), (y_min, y_max), resolution)`, as taken by `get_points`
+    pub fn search_area(&self) -> ((f64, f64), (f64, f64), f64) {
+        (self.x_range, self.y_range, self.resolution)
+    }
+}
+
+/// Planning problem read from a TOML or JSON file
+///
+/// Only `start`, `goal` and `robot` are required; the other fields default to
+/// a point robot without obstacles, the greedy one-step planner
+/// (`Horizon::default`), `Limits::default`, `SolverSettings::default`,
+/// `WarmStart::Inputs` and no figure. `maps` lists the YAML files of
+/// occupancy grids (see `OccupancyGrid::load`), relative to the scenario file.
+///
+/// ```toml
+/// start = [0.0, 0.0]
+/// goal = [10.0, 10.0]
+/// robot = { model = "holonomic", u_max = 0.1 }
+/// footprint = { shape = { circle = 0.2 }, clearance = 0.1 }
+///
+/// [[obstacles]]
+/// type = "circle"
+/// centre = [5.0, 4.0]
+/// radius = 1.5
+///
+/// [solver]
+/// tolerance = 1e-5
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Scenario {
+    /// Initial state of the robot (its first two entries are the position)
+    pub start: Vec<f64>,
+    /// Goal position `(x, y)`
+    pub goal: Vec<f64>,
+    pub robot: RobotModel,
+    #[serde(default = "Footprint::point")]
+    pub footprint: Footprint,
+    #[serde(default)]
+    pub obstacles: Vec<ObstacleSpec>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub maps: Vec<PathBuf>,
+    #[serde(default)]
+    pub horizon: Horizon,
+    #[serde(default)]
+    pub limits: Limits,
+    #[serde(default)]
+    pub solver: SolverSettings,
+    #[serde(default)]
+    pub warm_start: WarmStart,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub plot: Option<PlotSettings>,
+    /// Directory of the scenario file, for the relative paths of `maps`
+    #[serde(skip)]
+    base_dir: PathBuf,
+}
+
+/// Reasons for rejecting a scenario file
+#[derive(Debug)]
+pub enum ScenarioError {
+    Io(io::Error),
+    Toml(toml::de::Error),
+    TomlSerialize(toml::ser::Error),
+    Json(serde_json::Error),
+    /// The file extension is neither `.toml` nor `.json`
+    UnknownFormat(PathBuf),
+    Obstacle(ObstacleError),
+    Map(MapError),
+    /// A parameter is out of range
+    Invalid(String),
+}
+
+impl fmt::Display for ScenarioError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScenarioError::Io(e) => write!(f, "cannot access the scenario: {}", e),
+            ScenarioError::Toml(e) => write!(f, "invalid TOML scenario: {}", e),
+            ScenarioError::TomlSerialize(e) => write!(f, "cannot write TOML: {}", e),
+            ScenarioError::Json(e) => write!(f, "invalid JSON scenario: {}", e),
+            ScenarioError::UnknownFormat(path) => write!(
+                f,
+                "unknown scenario format: {} (expected .toml or .json)",
+                path.display()
+            ),
+            ScenarioError::Obstacle(e) => write!(f, "invalid obstacle: {}", e),
+            ScenarioError::Map(e) => write!(f, "{}", e),
+            ScenarioError::Invalid(reason) => write!(f, "invalid scenario: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for ScenarioError {}
+
+impl From<io::Error> for ScenarioError {
+    fn from(e: io::Error) -> Self {
+        ScenarioError::Io(e)
+    }
+}
+
+impl From<toml::de::Error> for ScenarioError {
+    fn from(e: toml::de::Error) -> Self {
+        ScenarioError::Toml(e)
+    }
+}
+
+impl From<toml::ser::Error> for ScenarioError {
+    fn from(e: toml::ser::Error) -> Self {
+        ScenarioError::TomlSerialize(e)
+    }
+}
+
+impl From<serde_json::Error> for ScenarioError {
+    fn from(e: serde_json::Error) -> Self {
+        ScenarioError::Json(e)
+    }
+}
+
+impl From<ObstacleError> for ScenarioError {
+    fn from(e: ObstacleError) -> Self {
+        ScenarioError::Obstacle(e)
+    }
+}
+
+impl From<PlanError> for ScenarioError {
+    fn from(e: PlanError) -> Self {
+        match e {
+            PlanError::Obstacle(e) => ScenarioError::Obstacle(e),
+            e => ScenarioError::Invalid(e.to_string()),
+        }
+    }
+}
+
+impl From<MapError> for ScenarioError {
+    fn from(e: MapError) -> Self {
+        ScenarioError::Map(e)
+    }
+}
+
+enum Format {
+    Toml,
+    Json,
+}
+
+fn format_of(path: &Path) -> Result<Format, ScenarioError> {
+    match path.extension().and_then(|e| e.to_str()) {
+        Some("toml") => Ok(Format::Toml),
+        Some("json") => Ok(Format::Json),
+        _ => Err(ScenarioError::UnknownFormat(path.to_path_buf())),
+    }
+}
+
+impl Scenario {
+    /// Scenario with the default settings (see above)
+    pub fn new(start: Vec<f64>, goal: Vec<f64>, robot: RobotModel) -> Self {
+        Scenario {
+            start,
+            goal,
+            robot,
+            footprint: Footprint::point(),
+            obstacles: Vec::new(),
+            maps: Vec::new(),
+            horizon: Horizon::default(),
+            limits: Limits::default(),
+            solver: SolverSettings::default(),
+            warm_start: WarmStart::default(),
+            plot: None,
+            base_dir: PathBuf::new(),
+        }
+    }
+
+    /// Reads a `.toml` or `.json` file and validates it
+    pub fn load<Q: AsRef<Path>>(path: Q) -> Result<Self, ScenarioError> {
+        let path = path.as_ref();
+        let format = format_of(path)?;
+        let text = fs::read_to_string(path)?;
+        let mut scenario = match format {
+            Format::Toml => Scenario::from_toml_str(&text)?,
+            Format::Json => Scenario::from_json_str(&text)?,
+        };
+        scenario.base_dir = path.parent().map(Path::to_path_buf).unwrap_or_default();
+        Ok(scenario)
+    }
+
+    /// Maps are relative to the working directory
+    pub fn from_toml_str(text: &str) -> Result<Self, ScenarioError> {
+        let scenario: Scenario = toml::from_str(text)?;
+        scenario.validate()?;
+        Ok(scenario)
+    }
+
+    /// Maps are relative to the working directory
+    pub fn from_json_str(text: &str) -> Result<Self, ScenarioError> {
+        let scenario: Scenario = serde_json::from_str(text)?;
+        scenario.validate()?;
+        Ok(scenario)
+    }
+
+    /// Writes a `.toml` or `.json` file
+    pub fn save<Q: AsRef<Path>>(&self, path: Q) -> Result<(), ScenarioError> {
+        let path = path.as_ref();
+        let text = match format_of(path)? {
+            Format::Toml => self.to_toml()?,
+            Format::Json => self.to_json()?,
+        };
+        fs::write(path, text)?;
+        Ok(())
+    }
+
+    pub fn to_toml(&self) -> Result<String, ScenarioError> {
+        Ok(toml::to_string(self)?)
+    }
+
+    pub fn to_json(&self) -> Result<String, ScenarioError> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// Checks the dimensions and ranges of all the parameters
+    ///
+    /// The maps are only checked when they are loaded (`obstacle_specs`).
+    pub fn validate(&self) -> Result<(), ScenarioError> {
+        let invalid = |reason: String| Err(ScenarioError::Invalid(reason));
+        let state_dim = self.robot.state_dim();
+        if self.start.len() != state_dim {
+            return invalid(format!(
+                "the start needs {} entries for this robot model (got {})",
+                state_dim,
+                self.start.len()
+            ));
+        }
+        if self.goal.len() != 2 {
+            return invalid(format!(
+                "the goal is a position (x, y) (got {} entries)",
+                self.goal.len()
+            ));
+        }
+        if !self
+            .start
+            .iter()
+            .chain(self.goal.iter())
+            .all(|v| v.is_finite())
+        {
+            return invalid("start and goal must be finite".to_string());
+        }
+        self.robot.validate().map_err(ScenarioError::Invalid)?;
+        self.footprint.validate()?;
+        for obstacle in &self.obstacles {
+            obstacle.validate()?;
+        }
+        self.horizon.validate().map_err(ScenarioError::Invalid)?;
+        self.limits.validate().map_err(ScenarioError::Invalid)?;
+        self.solver.validate().map_err(ScenarioError::Invalid)?;
+        if let Some(plot) = &self.plot {
+            let ordered = |r: (f64, f64)| r.0 < r.1 && r.0.is_finite() && r.1.is_finite();
+            let positive = plot.resolution > 0.0 && plot.resolution.is_finite();
+            if !(ordered(plot.x_range) && ordered(plot.y_range) && positive) {
+                return invalid("the plot needs ordered ranges and a positive resolution".into());
+            }
+        }
+        Ok(())
+    }
+
+    /// The obstacles, followed by the distance fields of the maps
+    pub fn obstacle_specs(&self) -> Result<Vec<ObstacleSpec>, ScenarioError> {
+        let mut specs = self.obstacles.clone();
+        for map in &self.maps {
+            let grid = OccupancyGrid::load(self.base_dir.join(map))?;
+            specs.push(ObstacleSpec::Map {
+                field: grid.distance_field(),
+            });
+        }
+        Ok(specs)
+    }
+
+    /// Plans from `start` to `goal` (see `main_instance`)
+    pub fn run(&self) -> Result<Plan, ScenarioError> {
+        self.execute(true)
+    }
+
+    /// `run` without printing the positions (e.g. for batches)
+    pub fn run_quietly(&self) -> Result<Plan, ScenarioError> {
+        self.execute(false)
+    }
+
+    /// Smallest signed distance from the positions of `plan` to the obstacles
+    /// inflated by the footprint, each where it is when the position is
+    /// reached (negative if the robot touches an obstacle)
+    pub fn min_clearance(&self, plan: &Plan) -> Result<f64, ScenarioError> {
+        let dt = self.robot.time_step();
+        let mut clearance = f64::INFINITY;
+        for spec in self.obstacle_specs()? {
+            let obstacle = spec.build_moving(&self.footprint)?;
+            for (k, &(x, y)) in plan.path.iter().enumerate() {
+                clearance = clearance.min(obstacle.signed_distance(k as f64 * dt, &[x, y]));
+            }
+        }
+        Ok(clearance)
+    }
+
+    /// Waypoints of `plan`, timed by the time step of the robot model
+    pub fn waypoints(&self, plan: &Plan) -> PlannedPath {
+        PlannedPath::from_plan(plan, self.robot.time_step(), self.robot.heading_index())
+    }
+
+    fn execute(&self, verbose: bool) -> Result<Plan, ScenarioError> {
+        self.validate()?;
+        let obstacles = self.obstacle_specs()?;
+        let invalid = ScenarioError::Invalid;
+        match self.robot {
+            RobotModel::Holonomic { u_max } => {
+                let dynamics = Holonomic::new(u_max).map_err(invalid)?;
+                self.plan(&obstacles, verbose, dynamics)
+            }
+            RobotModel::Unicycle {
+                dt,
+                v_max,
+                omega_max,
+            } => {
+                let dynamics = Unicycle::new(dt, v_max, omega_max).map_err(invalid)?;
+                self.plan(&obstacles, verbose, dynamics)
+            }
+            RobotModel::DifferentialDrive {
+                dt,
+                wheel_radius,
+                track_width,
+                wheel_speed_max,
+            } => {
+                let dynamics =
+                    DifferentialDrive::new(dt, wheel_radius, track_width, wheel_speed_max)
+                        .map_err(invalid)?;
+                self.plan(&obstacles, verbose, dynamics)
+            }
+            RobotModel::DoubleIntegrator { dt, v_max, a_max } => {
+                let dynamics = DoubleIntegrator::new(dt, v_max, a_max).map_err(invalid)?;
+                self.plan(&obstacles, verbose, dynamics)
+            }
+            RobotModel::KinematicBicycle {
+                dt,
+                wheelbase,
+                v_max,
+                a_max,
+                steering_max,
+            } => {
+                let dynamics = KinematicBicycle::new(dt, wheelbase, v_max, a_max, steering_max)
+                    .map_err(invalid)?;
+                self.plan(&obstacles, verbose, dynamics)
+            }
+        }
+    }
+
+    fn plan<D: Dynamics>(
+        &self,
+        obstacles: &[ObstacleSpec],
+        verbose: bool,
+        dynamics: D,
+    ) -> Result<Plan, ScenarioError> {
+        let planner = Planner::new(
+            &self.start,
+            &self.goal,
+            obstacles,
+            dynamics,
+            &self.footprint,
+            self.horizon,
+        )?
+        .with_warm_start(self.warm_start)
+        .with_solver_settings(self.solver)
+        .map_err(ScenarioError::Invalid)?;
+        Ok(run(planner, self.limits, verbose))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ObstacleConstraint, Outcome, Shooting};
+
+    /// Empty directory for the files of one test
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("planner-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn example(name: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("..")
+            .join(name)
+            .join("scenario.toml")
+    }
+
+    /// A scenario that sets every field
+    fn full_scenario() -> Scenario {
+        let mut scenario = Scenario::new(
+            vec![0.0, 0.0, 0.5],
+            vec![10.0, 5.0],
+            RobotModel::Unicycle {
+                dt: 0.1,
+                v_max: 1.0,
+                omega_max: 2.0,
+            },
+        );
+        scenario.footprint = Footprint::circle(0.2).with_clearance(0.1);
+        scenario.obstacles = vec![
+            ObstacleSpec::Circle {
+                centre: (5.0, 4.0),
+                radius: 1.5,
+            },
+            ObstacleSpec::Polyhedron {
+                centre: (7.0, 1.0),
+                halfspaces: vec![((1.0, 0.0), 0.5), ((-1.0, 0.0), 0.5), ((0.0, 1.0), 1.0)],
+            },
+        ];
+        scenario.horizon = Horizon::new(4)
+            .unwrap()
+            .with_effort_weight(0.2)
+            .with_shooting(Shooting::Multiple)
+            .with_obstacle_constraint(ObstacleConstraint::SignedDistance { margin: 0.05 });
+        scenario.limits = Limits::default().with_max_steps(300).unwrap();
+        scenario.solver.lbfgs_memory = 7;
+        scenario.warm_start = WarmStart::InputsAndMultipliers;
+        scenario.plot = Some(PlotSettings {
+            x_range: (-1.0, 11.0),
+            y_range: (-2.0, 8.0),
+            resolution: 0.1,
+            output: PathBuf::from("figure.svg"),
+        });
+        scenario
+    }
+
+    fn rejects(change: impl Fn(&mut Scenario)) -> bool {
+        let mut scenario = full_scenario();
+        change(&mut scenario);
+        matches!(scenario.validate(), Err(ScenarioError::Invalid(_)))
+    }
+
+    #[test]
+    fn round_trips_through_toml_and_json() {
+        let scenario = full_scenario();
+        let toml = scenario.to_toml().unwrap();
+        assert_eq!(Scenario::from_toml_str(&toml).unwrap(), scenario);
+        let json = scenario.to_json().unwrap();
+        assert_eq!(Scenario::from_json_str(&json).unwrap(), scenario);
+
+        // Through files, which set the directory of the maps
+        let dir = scratch_dir("round-trip");
+        for name in ["scenario.toml", "scenario.json"] {
+            scenario.save(dir.join(name)).unwrap();
+            let mut loaded = Scenario::load(dir.join(name)).unwrap();
+            assert_eq!(loaded.base_dir, dir);
+            loaded.base_dir = PathBuf::new();
+            assert_eq!(loaded, scenario);
+        }
+        assert!(matches!(
+            scenario.save(dir.join("scenario.yaml")),
+            Err(ScenarioError::UnknownFormat(_))
+        ));
+        assert!(matches!(
+            Scenario::load(dir.join("missing.toml")),
+            Err(ScenarioError::Io(_))
+        ));
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn missing_fields_take_the_defaults() {
+        let text = "start = [0.0, 0.0]\ngoal = [1.0, 2.0]\nrobot = { model = \"holonomic\", u_max = 0.1 }\n";
+        let scenario = Scenario::from_toml_str(text).unwrap();
+        assert_eq!(
+            scenario,
+            Scenario::new(
+                vec![0.0, 0.0],
+                vec![1.0, 2.0],
+                RobotModel::Holonomic { u_max: 0.1 }
+            )
+        );
+    }
+
+    #[test]
+    fn unreadable_text_is_rejected() {
+        let robot = "robot = { model = \"holonomic\", u_max = 0.1 }";
+        let toml = |text: &str| Scenario::from_toml_str(text);
+        assert!(matches!(
+            toml("start = [0.0, 0.0"),
+            Err(ScenarioError::Toml(_))
+        ));
+        // No goal
+        assert!(matches!(
+            toml(&format!("start = [0.0, 0.0]\n{}", robot)),
+            Err(ScenarioError::Toml(_))
+        ));
+        assert!(matches!(
+            toml("start = [0.0, 0.0]\ngoal = [1.0, 1.0]\nrobot = { model = \"hovercraft\" }"),
+            Err(ScenarioError::Toml(_))
+        ));
+        assert!(matches!(
+            toml("start = [0.0, 0.0]\ngoal = [1.0, 1.0]\nrobot = { model = \"holonomic\" }"),
+            Err(ScenarioError::Toml(_))
+        ));
+
+        let json = |text: &str| Scenario::from_json_str(text);
+        assert!(matches!(
+            json("{\"start\": [0.0, 0.0]"),
+            Err(ScenarioError::Json(_))
+        ));
+        assert!(matches!(
+            json("{\"start\": [0.0], \"goal\": \"far\", \"robot\": {\"model\": \"holonomic\", \"u_max\": 0.1}}"),
+            Err(ScenarioError::Json(_))
+        ));
+        // Well formed, but out of range
+        assert!(matches!(
+            json("{\"start\": [0.0], \"goal\": [1.0, 1.0], \"robot\": {\"model\": \"holonomic\", \"u_max\": 0.1}}"),
+            Err(ScenarioError::Invalid(_))
+        ));
+    }
+
+    #[test]
+    fn out_of_range_parameters_are_rejected() {
+        assert!(full_scenario().validate().is_ok());
+        // Start and goal
+        assert!(rejects(|s| s.start = vec![0.0, 0.0]));
+        assert!(rejects(|s| s.goal = vec![1.0, 2.0, 3.0]));
+        assert!(rejects(|s| s.goal[1] = f64::NAN));
+        assert!(rejects(|s| s.start[2] = f64::INFINITY));
+        // Robot parameters
+        assert!(rejects(|s| {
+            s.robot = RobotModel::Unicycle {
+                dt: 0.0,
+                v_max: 1.0,
+                omega_max: 2.0,
+            }
+        }));
+        assert!(rejects(|s| {
+            s.start = vec![0.0, 0.0];
+            s.robot = RobotModel::Holonomic { u_max: -0.1 };
+        }));
+        assert!(rejects(|s| {
+            s.start = vec![0.0, 0.0, 0.0, 0.0];
+            s.robot = RobotModel::KinematicBicycle {
+                dt: 0.1,
+                wheelbase: 1.0,
+                v_max: 1.0,
+                a_max: 1.0,
+                steering_max: 2.0,
+            };
+        }));
+        // Horizon, limits, solver and plot
+        assert!(rejects(|s| s.horizon.steps = 0));
+        assert!(rejects(|s| s.limits.goal_tolerance = 0.0));
+        assert!(rejects(|s| s.solver.lbfgs_memory = 0));
+        assert!(rejects(|s| s.plot.as_mut().unwrap().x_range = (1.0, -1.0)));
+        assert!(rejects(|s| s.plot.as_mut().unwrap().resolution = 0.0));
+
+        let mut scenario = full_scenario();
+        scenario.obstacles.push(ObstacleSpec::Circle {
+            centre: (1.0, 1.0),
+            radius: -1.0,
+        });
+        assert!(matches!(
+            scenario.validate(),
+            Err(ScenarioError::Obstacle(ObstacleError::NonPositiveRadius(_)))
+        ));
+        let mut scenario = full_scenario();
+        scenario.footprint = Footprint::circle(0.2).with_clearance(-0.5);
+        assert!(matches!(
+            scenario.validate(),
+            Err(ScenarioError::Obstacle(_))
+        ));
+    }
+
+    #[test]
+    fn maps_are_relative_to_the_scenario_file() {
+        let dir = scratch_dir("maps");
+        let map = "image: map.pgm\nresolution: 0.5\norigin: [2.0, 2.0, 0.0]\n";
+        fs::write(dir.join("map.yaml"), map).unwrap();
+        fs::write(dir.join("map.pgm"), "P2 2 2 255\n0 255\n255 255\n").unwrap();
+        fs::write(
+            dir.join("broken.yaml"),
+            map.replace("map.pgm", "broken.pgm"),
+        )
+        .unwrap();
+        fs::write(dir.join("broken.pgm"), "P2 2 2 255\n0 255\n").unwrap();
+
+        let mut scenario = full_scenario();
+        scenario.maps = vec![PathBuf::from("map.yaml")];
+        scenario.save(dir.join("scenario.toml")).unwrap();
+        let loaded = Scenario::load(dir.join("scenario.toml")).unwrap();
+        let specs = loaded.obstacle_specs().unwrap();
+        assert_eq!(specs.len(), 3);
+        let map = specs[2].build().unwrap();
+        // The occupied cell is the top left one
+        assert!(map.is_inside(&[2.25, 2.75]));
+        assert!(!map.is_inside(&[2.75, 2.25]));
+
+        // Maps are only read when the obstacles are built
+        for (name, expected) in [("missing.yaml", "io"), ("broken.yaml", "pgm")] {
+            scenario.maps = vec![PathBuf::from(name)];
+            scenario.save(dir.join("scenario.toml")).unwrap();
+            let loaded = Scenario::load(dir.join("scenario.toml")).unwrap();
+            let error = loaded.run_quietly().unwrap_err();
+            match (error, expected) {
+                (ScenarioError::Map(MapError::Io(_)), "io") => {}
+                (ScenarioError::Map(MapError::InvalidPgm(_)), "pgm") => {}
+                (error, _) => panic!("unexpected error for {}: {}", name, error),
+            }
+        }
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn example_scenarios_load() {
+        let scenario = Scenario::load(example("example_09_multple_obstacles")).unwrap();
+        assert_eq!(scenario.goal, vec![10.0, 10.0]);
+        assert_eq!(scenario.robot, RobotModel::Holonomic { u_max: 0.1 });
+        assert_eq!(
+            scenario.footprint,
+            Footprint::circle(0.2).with_clearance(0.1)
+        );
+        assert_eq!(scenario.obstacles.len(), 3);
+        assert_eq!(scenario.horizon.steps, 5);
+        assert_eq!(scenario.solver.max_outer_iterations, 200);
+
+        let scenario = Scenario::load(example("example_11_nonlinear_obstacles")).unwrap();
+        assert_eq!(scenario.goal, vec![30.0, 30.0]);
+        assert_eq!(scenario.obstacles.len(), 4);
+        assert_eq!(scenario.obstacle_specs().unwrap().len(), 4);
+        assert_eq!(scenario.horizon.steps, 5);
+        assert_eq!(scenario.plot.unwrap().x_range, (-5.0, 35.0));
+    }
+
+    #[test]
+    fn example_11_takes_its_first_steps() {
+        // The shipped horizon of 5 waypoints (see also
+        // `holonomic_horizon_passes_an_ellipse` for the steps by the obstacles)
+        let mut scenario = Scenario::load(example("example_11_nonlinear_obstacles")).unwrap();
+        scenario.limits = scenario.limits.with_max_steps(3).unwrap();
+        let plan = scenario.run_quietly().unwrap();
+        assert_eq!(plan.outcome, Outcome::MaxStepsExceeded);
+        assert_eq!(plan.steps(), 3);
+        assert!(plan.solves.iter().all(|s| s.converged));
+        assert!(scenario.min_clearance(&plan).unwrap() > 0.0);
+    }
+}
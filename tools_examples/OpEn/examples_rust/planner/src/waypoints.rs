@@ -0,0 +1,553 @@
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+// For Path Files
+use serde::{Deserialize, Serialize};
+
+use crate::Plan;
+
+/// Solver status of the step that led to a waypoint
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WaypointStatus {
+    /// The start (no solve)
+    Start,
+    Converged,
+    /// ALM stopped before meeting its tolerances; the input was applied anyway
+    NotConverged,
+}
+
+impl WaypointStatus {
+    /// Name in the CSV and JSON files
+    pub fn code(&self) -> &'static str {
+        match self {
+            WaypointStatus::Start => "start",
+            WaypointStatus::Converged => "converged",
+            WaypointStatus::NotConverged => "not_converged",
+        }
+    }
+
+    fn from_code(code: &str) -> Option<Self> {
+        match code {
+            "start" => Some(WaypointStatus::Start),
+            "converged" => Some(WaypointStatus::Converged),
+            "not_converged" => Some(WaypointStatus::NotConverged),
+            _ => None,
+        }
+    }
+}
+
+/// One position of a planned path
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Waypoint {
+    pub step: usize,
+    /// Time at which the position is reached (s), `step * dt`
+    pub time: f64,
+    pub x: f64,
+    pub y: f64,
+    /// Heading (rad): that of the state if the model has one, otherwise the
+    /// direction of the next move (of the previous one at the end)
+    pub heading: f64,
+    pub status: WaypointStatus,
+    /// Solver statistics of the step (zero at the start)
+    pub outer_iterations: usize,
+    pub inner_iterations: usize,
+    /// Solve time (s)
+    pub solve_time: f64,
+}
+
+/// Waypoints of a planning run, for the controllers and analysis scripts
+///
+/// Written to and read back from CSV and JSON files, and written to a
+/// GPX-like XML route (with planar `x` and `y` instead of latitude and
+/// longitude) for the tools that take routes.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlannedPath {
+    /// `Outcome::code` of the run
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub outcome: Option<String>,
+    pub waypoints: Vec<Waypoint>,
+}
+
+/// Format of a path file, by its extension
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PathFormat {
+    Csv,
+    Json,
+    /// Write only
+    Gpx,
+}
+
+impl PathFormat {
+    pub fn of(path: &Path) -> Result<Self, PathFileError> {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("csv") => Ok(PathFormat::Csv),
+            Some("json") => Ok(PathFormat::Json),
+            Some("gpx") => Ok(PathFormat::Gpx),
+            _ => Err(PathFileError::UnknownFormat(path.to_path_buf())),
+        }
+    }
+}
+
+/// Reasons for rejecting a path file
+#[derive(Debug)]
+pub enum PathFileError {
+    Io(io::Error),
+    Json(serde_json::Error),
+    /// Invalid row or header (`line` counts from 1)
+    Csv {
+        line: usize,
+        reason: String,
+    },
+    /// The file extension is not `.csv`, `.json` or `.gpx`
+    UnknownFormat(PathBuf),
+    /// GPX files are not read back
+    NotReadable(PathBuf),
+}
+
+impl fmt::Display for PathFileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PathFileError::Io(e) => write!(f, "cannot access the path file: {}", e),
+            PathFileError::Json(e) => write!(f, "invalid JSON path: {}", e),
+            PathFileError::Csv { line, reason } => {
+                write!(f, "invalid CSV path (line {}): {}", line, reason)
+            }
+            PathFileError::UnknownFormat(path) => write!(
+                f,
+                "unknown path format: {} (expected .csv, .json or .gpx)",
+                path.display()
+            ),
+            PathFileError::NotReadable(path) => write!(
+                f,
+                "cannot read {} back (only .csv and .json are)",
+                path.display()
+            ),
+        }
+    }
+}
+
+impl std::error::Error for PathFileError {}
+
+impl From<io::Error> for PathFileError {
+    fn from(e: io::Error) -> Self {
+        PathFileError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for PathFileError {
+    fn from(e: serde_json::Error) -> Self {
+        PathFileError::Json(e)
+    }
+}
+
+const CSV_HEADER: &str =
+    "step,time,x,y,heading,status,outer_iterations,inner_iterations,solve_time_s";
+const OUTCOME_COMMENT: &str = "# outcome: ";
+
+impl PlannedPath {
+    /// Waypoints of `plan`, `dt` seconds apart; `heading_index` is the index
+    /// of the heading in the states, if any (see `RobotModel::heading_index`
+    /// and `Scenario::waypoints`)
+    pub fn from_plan(plan: &Plan, dt: f64, heading_index: Option<usize>) -> Self {
+        let path = &plan.path;
+        let waypoints = path
+            .iter()
+            .enumerate()
+            .map(|(k, &(x, y))| {
+                let heading = match heading_index {
+                    Some(i) => plan.states[k][i],
+                    None => direction(path, k),
+                };
+                let mut waypoint = Waypoint {
+                    step: k,
+                    time: k as f64 * dt,
+                    x,
+                    y,
+                    heading,
+                    status: WaypointStatus::Start,
+                    outer_iterations: 0,
+                    inner_iterations: 0,
+                    solve_time: 0.0,
+                };
+                // The solve of step k - 1 led to waypoint k
+                if let Some(solve) = k.checked_sub(1).and_then(|j| plan.solves.get(j)) {
+                    waypoint.status = if solve.converged {
+                        WaypointStatus::Converged
+                    } else {
+                        WaypointStatus::NotConverged
+                    };
+                    waypoint.outer_iterations = solve.outer_iterations;
+                    waypoint.inner_iterations = solve.inner_iterations;
+                    waypoint.solve_time = solve.solve_time.as_secs_f64();
+                }
+                waypoint
+            })
+            .collect();
+        PlannedPath {
+            outcome: Some(plan.outcome.code().to_string()),
+            waypoints,
+        }
+    }
+
+    /// Reads a `.csv` or `.json` file
+    pub fn load<Q: AsRef<Path>>(path: Q) -> Result<Self, PathFileError> {
+        let path = path.as_ref();
+        match PathFormat::of(path)? {
+            PathFormat::Csv => PlannedPath::from_csv(&fs::read_to_string(path)?),
+            PathFormat::Json => PlannedPath::from_json(&fs::read_to_string(path)?),
+            PathFormat::Gpx => Err(PathFileError::NotReadable(path.to_path_buf())),
+        }
+    }
+
+    /// Writes a `.csv`, `.json` or `.gpx` file
+    pub fn save<Q: AsRef<Path>>(&self, path: Q) -> Result<(), PathFileError> {
+        let path = path.as_ref();
+        self.write(path, PathFormat::of(path)?)
+    }
+
+    /// Writes the file in `format`, whatever its extension
+    pub fn write<Q: AsRef<Path>>(&self, path: Q, format: PathFormat) -> Result<(), PathFileError> {
+        let text = match format {
+            PathFormat::Csv => self.to_csv(),
+            PathFormat::Json => self.to_json()?,
+            PathFormat::Gpx => self.to_gpx(),
+        };
+        fs::write(path, text)?;
+        Ok(())
+    }
+
+    /// `# outcome: <code>` (if known), the header and one row per waypoint
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::new();
+        if let Some(outcome) = &self.outcome {
+            csv.push_str(&format!("{}{}\n", OUTCOME_COMMENT, outcome));
+        }
+        csv.push_str(CSV_HEADER);
+        csv.push('\n');
+        for w in &self.waypoints {
+            csv.push_str(&format!(
+                "{},{},{},{},{},{},{},{},{}\n",
+                w.step,
+                w.time,
+                w.x,
+                w.y,
+                w.heading,
+                w.status.code(),
+                w.outer_iterations,
+                w.inner_iterations,
+                w.solve_time
+            ));
+        }
+        csv
+    }
+
+    /// Reads the output of `to_csv`; other comment lines and blank lines are skipped
+    pub fn from_csv(text: &str) -> Result<Self, PathFileError> {
+        let mut outcome = None;
+        let mut header = false;
+        let mut waypoints = Vec::new();
+        for (index, row) in text.lines().enumerate() {
+            let line = index + 1;
+            let row = row.trim();
+            if let Some(code) = row.strip_prefix(OUTCOME_COMMENT.trim_end()) {
+                outcome = Some(code.trim().to_string());
+                continue;
+            }
+            if row.is_empty() || row.starts_with('#') {
+                continue;
+            }
+            if !header {
+                if row != CSV_HEADER {
+                    return Err(PathFileError::Csv {
+                        line,
+                        reason: format!("expected the header {}", CSV_HEADER),
+                    });
+                }
+                header = true;
+                continue;
+            }
+            let waypoint = parse_row(row).map_err(|reason| PathFileError::Csv { line, reason })?;
+            waypoints.push(waypoint);
+        }
+        if !header {
+            return Err(PathFileError::Csv {
+                line: text.lines().count().max(1),
+                reason: "no header".to_string(),
+            });
+        }
+        Ok(PlannedPath { outcome, waypoints })
+    }
+
+    pub fn to_json(&self) -> Result<String, PathFileError> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    pub fn from_json(text: &str) -> Result<Self, PathFileError> {
+        Ok(serde_json::from_str(text)?)
+    }
+
+    /// GPX-like route: one `rtept` per waypoint, with `x` and `y` attributes
+    /// and the other fields as child elements
+    pub fn to_gpx(&self) -> String {
+        let mut gpx = String::from(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<gpx version=\"1.1\" creator=\"planner\">\n  <rte>\n",
+        );
+        if let Some(outcome) = &self.outcome {
+            gpx.push_str(&format!("    <desc>outcome: {}</desc>\n", outcome));
+        }
+        for w in &self.waypoints {
+            gpx.push_str(&format!(
+                "    <rtept x=\"{}\" y=\"{}\">\n      <step>{}</step>\n      <time>{}</time>\n      <heading>{}</heading>\n      <status>{}</status>\n      <outer_iterations>{}</outer_iterations>\n      <inner_iterations>{}</inner_iterations>\n      <solve_time>{}</solve_time>\n    </rtept>\n",
+                w.x,
+                w.y,
+                w.step,
+                w.time,
+                w.heading,
+                w.status.code(),
+                w.outer_iterations,
+                w.inner_iterations,
+                w.solve_time
+            ));
+        }
+        gpx.push_str("  </rte>\n</gpx>\n");
+        gpx
+    }
+}
+
+/// Direction of the move from `path[k]`, or into it for the last position
+fn direction(path: &[(f64, f64)], k: usize) -> f64 {
+    let (from, to) = if k + 1 < path.len() {
+        (path[k], path[k + 1])
+    } else if k > 0 {
+        (path[k - 1], path[k])
+    } else {
+        return 0.0;
+    };
+    (to.1 - from.1).atan2(to.0 - from.0)
+}
+
+fn parse_row(row: &str) -> Result<Waypoint, String> {
+    let fields: Vec<&str> = row.split(',').map(str::trim).collect();
+    if fields.len() != 9 {
+        return Err(format!("expected 9 fields, got {}", fields.len()));
+    }
+    fn parse<T: std::str::FromStr>(field: &str, name: &str) -> Result<T, String> {
+        field
+            .parse()
+            .map_err(|_| format!("invalid {}: {:?}", name, field))
+    }
+    let status = WaypointStatus::from_code(fields[5])
+        .ok_or_else(|| format!("invalid status: {:?}", fields[5]))?;
+    Ok(Waypoint {
+        step: parse(fields[0], "step")?,
+        time: parse(fields[1], "time")?,
+        x: parse(fields[2], "x")?,
+        y: parse(fields[3], "y")?,
+        heading: parse(fields[4], "heading")?,
+        status,
+        outer_iterations: parse(fields[6], "outer_iterations")?,
+        inner_iterations: parse(fields[7], "inner_iterations")?,
+        solve_time: parse(fields[8], "solve_time_s")?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Outcome, SolveStatus};
+    use std::f64::consts::PI;
+    use std::time::Duration;
+
+    /// A square from the origin, counter-clockwise; the second solve did not
+    /// converge
+    fn plan() -> Plan {
+        let path = vec![(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)];
+        let solve = |converged| SolveStatus {
+            outer_iterations: 3,
+            inner_iterations: 42,
+            converged,
+            solve_time: Duration::from_micros(1500),
+        };
+        Plan {
+            states: path
+                .iter()
+                .enumerate()
+                .map(|(k, &(x, y))| vec![x, y, 0.25 * k as f64])
+                .collect(),
+            path,
+            solves: vec![solve(true), solve(false), solve(true)],
+            outcome: Outcome::Reached,
+        }
+    }
+
+    fn csv_error(text: &str) -> (usize, String) {
+        match PlannedPath::from_csv(text) {
+            Err(PathFileError::Csv { line, reason }) => (line, reason),
+            other => panic!("not a CSV error: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn waypoints_of_a_plan() {
+        let path = PlannedPath::from_plan(&plan(), 0.1, Some(2));
+        assert_eq!(path.outcome.as_deref(), Some("reached"));
+        assert_eq!(path.waypoints.len(), 4);
+        let start = path.waypoints[0];
+        assert_eq!(
+            (start.step, start.time, start.status),
+            (0, 0.0, WaypointStatus::Start)
+        );
+        assert_eq!((start.outer_iterations, start.inner_iterations), (0, 0));
+        let second = path.waypoints[2];
+        assert_eq!((second.step, second.x, second.y), (2, 1.0, 1.0));
+        assert_eq!(second.status, WaypointStatus::NotConverged);
+        assert_eq!((second.outer_iterations, second.inner_iterations), (3, 42));
+        assert_eq!(second.solve_time, 0.0015);
+        // The heading of the states
+        assert_eq!(second.heading, 0.5);
+    }
+
+    #[test]
+    fn heading_follows_the_moves_without_a_heading_state() {
+        let path = PlannedPath::from_plan(&plan(), 1.0, None);
+        let headings: Vec<f64> = path.waypoints.iter().map(|w| w.heading).collect();
+        // The last position keeps the direction of the move into it
+        assert_eq!(headings, vec![0.0, PI / 2.0, PI, PI]);
+
+        let mut single = plan();
+        single.path.truncate(1);
+        single.states.truncate(1);
+        single.solves.clear();
+        let path = PlannedPath::from_plan(&single, 1.0, None);
+        assert_eq!(path.waypoints[0].heading, 0.0);
+    }
+
+    #[test]
+    fn csv_and_json_round_trip() {
+        let path = PlannedPath::from_plan(&plan(), 0.1, None);
+        let csv = path.to_csv();
+        assert!(csv.starts_with("# outcome: reached\nstep,time,x,y,heading,status,"));
+        assert_eq!(PlannedPath::from_csv(&csv).unwrap(), path);
+        assert_eq!(
+            PlannedPath::from_json(&path.to_json().unwrap()).unwrap(),
+            path
+        );
+
+        // Without an outcome, and with blank and comment lines
+        let unknown = PlannedPath {
+            outcome: None,
+            ..path.clone()
+        };
+        let csv = unknown.to_csv();
+        assert!(!csv.contains("outcome"));
+        let commented = format!("# planned by hand\n\n{}\n", csv);
+        assert_eq!(PlannedPath::from_csv(&commented).unwrap(), unknown);
+        assert!(!unknown.to_json().unwrap().contains("outcome"));
+        assert_eq!(
+            PlannedPath::from_json(&unknown.to_json().unwrap()).unwrap(),
+            unknown
+        );
+    }
+
+    #[test]
+    fn files_round_trip_by_their_extension() {
+        let dir = std::env::temp_dir().join(format!("planner-waypoints-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = PlannedPath::from_plan(&plan(), 0.1, Some(2));
+        for name in ["path.csv", "path.json"] {
+            path.save(dir.join(name)).unwrap();
+            assert_eq!(PlannedPath::load(dir.join(name)).unwrap(), path);
+        }
+        path.save(dir.join("path.gpx")).unwrap();
+        assert!(matches!(
+            PlannedPath::load(dir.join("path.gpx")),
+            Err(PathFileError::NotReadable(_))
+        ));
+        assert!(matches!(
+            path.save(dir.join("path.txt")),
+            Err(PathFileError::UnknownFormat(_))
+        ));
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn formats_by_extension() {
+        assert_eq!(
+            PathFormat::of(Path::new("out/path.csv")).unwrap(),
+            PathFormat::Csv
+        );
+        assert_eq!(
+            PathFormat::of(Path::new("path.json")).unwrap(),
+            PathFormat::Json
+        );
+        assert_eq!(
+            PathFormat::of(Path::new("path.gpx")).unwrap(),
+            PathFormat::Gpx
+        );
+        assert!(matches!(
+            PathFormat::of(Path::new("path.CSV")),
+            Err(PathFileError::UnknownFormat(_))
+        ));
+        assert!(matches!(
+            PathFormat::of(Path::new("path")),
+            Err(PathFileError::UnknownFormat(_))
+        ));
+    }
+
+    #[test]
+    fn gpx_lists_the_route_points() {
+        let gpx = PlannedPath::from_plan(&plan(), 0.1, None).to_gpx();
+        assert!(gpx.contains("<desc>outcome: reached</desc>"));
+        assert_eq!(gpx.matches("<rtept ").count(), 4);
+        assert!(gpx.contains("<rtept x=\"1\" y=\"0\">"));
+        assert!(gpx.contains("<status>not_converged</status>"));
+        assert!(gpx.trim_end().ends_with("</gpx>"));
+    }
+
+    #[test]
+    fn invalid_csv_reports_the_line() {
+        let row = "1,0.1,1,0,0,converged,3,42,0.0015";
+        assert_eq!(
+            csv_error("# outcome: reached\nstep,time,x,y\n"),
+            (2, format!("expected the header {}", CSV_HEADER))
+        );
+        assert_eq!(csv_error(""), (1, "no header".to_string()));
+        assert_eq!(
+            csv_error("# outcome: reached\n\n"),
+            (2, "no header".to_string())
+        );
+        assert_eq!(
+            csv_error(&format!(
+                "{}\n{}\n\n1,0.1,1,0,0,converged,3,42\n",
+                CSV_HEADER, row
+            )),
+            (4, "expected 9 fields, got 8".to_string())
+        );
+        assert_eq!(
+            csv_error(&format!("{}\n1,0.1,1,0,0,moving,3,42,0.0015\n", CSV_HEADER)),
+            (2, "invalid status: \"moving\"".to_string())
+        );
+        assert_eq!(
+            csv_error(&format!(
+                "{}\n{}\n-1,0.1,1,0,0,converged,3,42,0.0015\n",
+                CSV_HEADER, row
+            )),
+            (3, "invalid step: \"-1\"".to_string())
+        );
+        assert_eq!(
+            csv_error(&format!(
+                "{}\n1,0.1,one,0,0,converged,3,42,0.0015\n",
+                CSV_HEADER
+            )),
+            (2, "invalid x: \"one\"".to_string())
+        );
+
+        let error = PlannedPath::from_csv("step,time\n").unwrap_err();
+        assert!(error
+            .to_string()
+            .starts_with("invalid CSV path (line 1): expected the header"));
+    }
+}